@@ -3,5 +3,5 @@ set -e
 dir=$1
 
 mkdir -p $dir/output
-cargo run -- -s $dir/spec.openapi.yaml -o $dir/output -c $dir/config.json > $dir/output/generate.log
+cargo run -- -s $dir/spec.openapi.yaml -o $dir/output -c $dir/config.json > $dir/output/generate.log 2>&1
 cargo build --manifest-path=$dir/output/Cargo.toml
\ No newline at end of file
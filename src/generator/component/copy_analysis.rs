@@ -0,0 +1,177 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tracing::trace;
+
+use crate::generator::types::{ObjectDatabase, ObjectDefinition};
+
+// Rust primitive types which implement Copy
+const COPY_PRIMITIVE_TYPES: [&str; 14] = [
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64",
+    "u128",
+];
+
+fn short_name(name: &str) -> &str {
+    name.rsplit("::").next().unwrap_or(name)
+}
+
+fn is_copy_type(
+    type_name: &str,
+    copy_objects: &HashSet<String>,
+    objects_by_name: &HashMap<String, Vec<String>>,
+) -> bool {
+    if COPY_PRIMITIVE_TYPES.contains(&type_name) {
+        return true;
+    }
+    // Vec, Map, Box... are never Copy
+    if type_name.contains('<') {
+        return false;
+    }
+    match objects_by_name.get(short_name(type_name)) {
+        Some(object_keys) => object_keys.iter().all(|key| copy_objects.contains(key)),
+        None => false,
+    }
+}
+
+/// Marks all structs and enums in the database which consist only of Copy types
+/// and have at most `max_fields` properties/values. Returns the number of objects
+/// which will derive Copy. Enums with an `Unknown(serde_json::Value)` variant are never Copy.
+pub fn mark_copy_types(
+    object_database: &ObjectDatabase,
+    max_fields: usize,
+    unknown_enum_variant: bool,
+) -> usize {
+    let mut objects_by_name: HashMap<String, Vec<String>> = HashMap::new();
+    for item in object_database.iter() {
+        objects_by_name
+            .entry(short_name(&item.value().name()).to_owned())
+            .or_default()
+            .push(item.key().clone());
+    }
+
+    // objects may reference each other, so we iterate until nothing changes
+    let mut copy_objects = HashSet::new();
+    loop {
+        let mut changed = false;
+        for item in object_database.iter() {
+            if copy_objects.contains(item.key()) {
+                continue;
+            }
+            let copyable = match item.value().as_ref() {
+                ObjectDefinition::Struct(struct_definition) => {
+                    !struct_definition.properties.is_empty()
+                        && struct_definition.properties.len() <= max_fields
+                        && struct_definition.properties.values().all(|property| {
+                            is_copy_type(&property.type_name, &copy_objects, &objects_by_name)
+                        })
+                }
+                ObjectDefinition::Enum(enum_definition) => {
+                    !unknown_enum_variant
+                        && !enum_definition.values.is_empty()
+                        && enum_definition.values.len() <= max_fields
+                        && enum_definition.values.values().all(|enum_value| {
+                            is_copy_type(
+                                &enum_value.value_type.name,
+                                &copy_objects,
+                                &objects_by_name,
+                            )
+                        })
+                }
+                ObjectDefinition::Primitive(primitive_definition) => is_copy_type(
+                    &primitive_definition.primitive_type.name,
+                    &copy_objects,
+                    &objects_by_name,
+                ),
+            };
+            if copyable {
+                trace!("{} is Copy", item.key());
+                copy_objects.insert(item.key().clone());
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut copy_count = 0;
+    for mut item in object_database.iter_mut() {
+        let (key, object_definition) = item.pair_mut();
+        let copyable = copy_objects.contains(key);
+        match Arc::make_mut(object_definition) {
+            ObjectDefinition::Struct(struct_definition) => struct_definition.derive_copy = copyable,
+            ObjectDefinition::Enum(enum_definition) => enum_definition.derive_copy = copyable,
+            ObjectDefinition::Primitive(_) => continue,
+        }
+        if copyable {
+            copy_count += 1;
+        }
+    }
+    copy_count
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::generator::types::{PropertyDefinition, StructDefinition};
+
+    fn property(name: &str, type_name: &str) -> PropertyDefinition {
+        PropertyDefinition {
+            name: name.to_owned(),
+            real_name: name.to_owned(),
+            type_name: type_name.to_owned(),
+            required: true,
+            ..Default::default()
+        }
+    }
+
+    fn struct_object(name: &str, properties: Vec<PropertyDefinition>) -> ObjectDefinition {
+        ObjectDefinition::Struct(StructDefinition {
+            package: "models".to_owned(),
+            name: name.to_owned(),
+            properties: properties
+                .into_iter()
+                .map(|property| (property.name.clone(), property))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_mark_copy_types() {
+        let object_database = ObjectDatabase::new();
+        object_database.insert(
+            "models::Point".to_owned(),
+            Arc::new(struct_object(
+                "Point",
+                vec![property("x", "f64"), property("y", "f64")],
+            )),
+        );
+        object_database.insert(
+            "models::Line".to_owned(),
+            Arc::new(struct_object(
+                "Line",
+                vec![property("a", "Point"), property("b", "Point")],
+            )),
+        );
+        object_database.insert(
+            "models::Named".to_owned(),
+            Arc::new(struct_object("Named", vec![property("name", "String")])),
+        );
+
+        assert_eq!(mark_copy_types(&object_database, 4, false), 2);
+        let is_copy = |key: &str| match object_database.get(key).unwrap().value().as_ref() {
+            ObjectDefinition::Struct(struct_definition) => struct_definition.derive_copy,
+            _ => false,
+        };
+        assert!(is_copy("models::Point"));
+        assert!(is_copy("models::Line"));
+        assert!(!is_copy("models::Named"));
+
+        assert_eq!(mark_copy_types(&object_database, 1, false), 0);
+    }
+}
@@ -2,34 +2,50 @@ use std::{
     collections::HashMap,
     fs::{self},
     path::PathBuf,
+    sync::Arc,
 };
 
-use crate::generator::types::ObjectDatabase;
-use crate::{utils::config::Config, GeneratorError};
-use oas3::Spec;
+use crate::generator::{
+    context::GenerationContext,
+    diagnostics::{json_pointer, Diagnostic},
+    statistics::ComponentStatistics,
+};
+use crate::GeneratorError;
 use object_definition::{generate_object, get_components_base_path, get_object_name};
-use tracing::{error, info, trace};
+use tracing::{info, trace};
 
+pub mod copy_analysis;
 pub mod object_definition;
 pub mod type_definition;
+pub mod version_conversion;
 
 pub fn generate_components(
-    spec: &Spec,
-    config: &Config,
-    object_database: &ObjectDatabase,
-) -> Result<(), GeneratorError> {
+    context: &GenerationContext,
+) -> Result<ComponentStatistics, GeneratorError> {
+    let GenerationContext {
+        spec,
+        object_database,
+        config,
+        ..
+    } = *context;
+    let mut component_statistics = ComponentStatistics::default();
     let components = match spec.components {
         Some(ref components) => components,
-        None => return Ok(()),
+        None => return Ok(component_statistics),
     };
 
+    // object names of this spec with the component key which claimed them
+    let mut name_owners: HashMap<String, String> = HashMap::new();
+
     for (component_name, object_ref) in &components.schemas {
+        let pointer = json_pointer(&["components", "schemas", component_name.as_str()]);
         // fix for broken names
         let component_name = component_name
             .replace("._common___", ".")
             .replace("._common___", ".");
         if config.ignore.component_ignored(&component_name) {
             info!("\"{}\" ignored", component_name);
+            component_statistics.skipped += 1;
             continue;
         }
 
@@ -38,11 +54,11 @@ pub fn generate_components(
         let resolved_object = match object_ref.resolve(spec) {
             Ok(object) => object,
             Err(err) => {
-                error!(
-                    "Unable to parse component {} {}",
-                    component_name,
-                    err.to_string()
-                );
+                context.report(Diagnostic::error(
+                    &pointer,
+                    format!("Unable to parse component: {}", err),
+                ));
+                component_statistics.failed += 1;
                 continue;
             }
         };
@@ -50,38 +66,53 @@ pub fn generate_components(
         let component_name =
             validate_component_name(&component_name, config.name_mapping.use_scope);
         let definition_path = get_components_base_path();
+        let key_object_name = config
+            .name_mapping
+            .name_to_struct_name(&definition_path, &component_name);
         let object_name = match resolved_object.title {
-            Some(ref title) => config
-                .name_mapping
-                .name_to_struct_name(&definition_path, &title),
-            None => config
-                .name_mapping
-                .name_to_struct_name(&definition_path, &component_name),
+            Some(ref title) => {
+                let title_object_name = config
+                    .name_mapping
+                    .name_to_struct_name(&definition_path, &title);
+                match name_owners.get(&title_object_name) {
+                    // another component of this spec already uses the name, the key is used instead
+                    Some(owner) if owner != &component_name => {
+                        context.report(Diagnostic::warning(
+                            &pointer,
+                            format!(
+                                "Components \"{}\" and \"{}\" both resolve to \"{}\" (title \"{}\"), \"{}\" is named {}",
+                                owner, component_name, title_object_name, title, component_name, key_object_name
+                            ),
+                        ));
+                        key_object_name
+                    }
+                    _ => title_object_name,
+                }
+            }
+            None => key_object_name,
         };
+        name_owners
+            .entry(object_name.clone())
+            .or_insert(component_name.clone());
 
         if object_database.contains_key(&object_name) {
             info!(
                 "Component \"{}\" already found in database and will be skipped",
                 object_name
             );
+            component_statistics.skipped += 1;
             continue;
         }
 
-        let object_definition = match generate_object(
-            spec,
-            &object_database,
-            definition_path,
-            &object_name,
-            &resolved_object,
-            &config.name_mapping,
-            config,
-        ) {
-            Ok(object_definition) => object_definition,
-            Err(err) => {
-                error!("{} {}\n", component_name, err);
-                continue;
-            }
-        };
+        let object_definition =
+            match generate_object(context, definition_path, &object_name, &resolved_object) {
+                Ok(object_definition) => object_definition,
+                Err(err) => {
+                    context.report(Diagnostic::error(&pointer, err));
+                    component_statistics.failed += 1;
+                    continue;
+                }
+            };
 
         // if let ObjectDefinition::Primitive(type_definition) = object_definition {
         //     trace!(
@@ -94,21 +125,27 @@ pub fn generate_components(
         let object_name = get_object_name(&object_definition);
 
         match object_database.contains_key(&object_name) {
+            // the object was already generated for another component, nothing is lost
             true => {
-                error!("ObjectDatabase already contains an object {}", object_name);
+                context.report(Diagnostic::warning(
+                    &pointer,
+                    format!("ObjectDatabase already contains an object {}", object_name),
+                ));
+                component_statistics.skipped += 1;
                 continue;
             }
             _ => {
                 trace!("Adding component/struct {} to database", object_name);
-                object_database.insert(object_name.clone(), object_definition);
+                object_database.insert(object_name.clone(), Arc::new(object_definition));
+                component_statistics.generated += 1;
             }
         }
     }
 
-    Ok(())
+    Ok(component_statistics)
 }
 
-fn validate_component_name(component_name: &str, use_scope: bool) -> String {
+pub(crate) fn validate_component_name(component_name: &str, use_scope: bool) -> String {
     let mut result = component_name.replace("___", ".").replace(".", "::");
     if result.starts_with("_") {
         result = result.trim_start_matches("_").to_owned();
@@ -1,21 +1,18 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
+use crate::generator::context::GenerationContext;
+use crate::generator::diagnostics::{definition_pointer, Diagnostic};
+use crate::generator::dialect::{BOOLEAN_SCHEMA_EXTENSION, PATTERN_PROPERTIES_EXTENSION};
 use crate::generator::types::{
-    EnumDefinition, EnumValue, ModuleInfo, ObjectDefinition, PrimitiveDefinition,
+    EnumDefinition, EnumValue, ExternalDocs, ModuleInfo, ObjectDefinition, PrimitiveDefinition,
     PropertyDefinition, StructDefinition,
 };
-use oas3::{
-    spec::{ObjectOrReference, ObjectSchema, SchemaTypeSet},
-    Spec,
-};
-use tracing::{error, info, trace};
+use oas3::spec::{ObjectOrReference, ObjectSchema, SchemaTypeSet};
+use tracing::trace;
 
-use crate::{
-    utils::{config::Config, name_mapping::NameMapping},
-    GeneratorError,
-};
+use crate::GeneratorError;
 
-use super::{type_definition::get_type_from_schema, ObjectDatabase};
+use super::type_definition::{degradation_note, get_type_from_schema, unsupported_keywords};
 
 pub fn get_components_base_path() -> Vec<String> {
     vec![
@@ -42,14 +39,22 @@ pub fn is_object_empty(object_schema: &ObjectSchema) -> bool {
 }
 
 pub fn generate_object(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     name: &str,
     object_schema: &ObjectSchema,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<ObjectDefinition, GeneratorError> {
+    if object_schema.extensions.get(BOOLEAN_SCHEMA_EXTENSION)
+        == Some(&serde_json::Value::Bool(false))
+    {
+        return Ok(generate_never_enum(
+            context,
+            &definition_path,
+            name,
+            object_schema,
+        ));
+    }
+
     if is_object_empty(object_schema) {
         return Err(GeneratorError::InvalidValueError(
             "Object is empty".to_string(),
@@ -57,27 +62,11 @@ pub fn generate_object(
     }
 
     if object_schema.any_of.len() > 0 {
-        return generate_enum_from_any(
-            spec,
-            object_database,
-            definition_path,
-            name,
-            object_schema,
-            name_mapping,
-            config,
-        );
+        return generate_enum_from_any(context, definition_path, name, object_schema);
     }
 
     if object_schema.one_of.len() > 0 {
-        return generate_enum_from_one_of(
-            spec,
-            object_database,
-            definition_path,
-            name,
-            object_schema,
-            name_mapping,
-            config,
-        );
+        return generate_enum_from_one_of(context, definition_path, name, object_schema);
     }
 
     let schema_type = match object_schema.schema_type {
@@ -87,24 +76,10 @@ pub fn generate_object(
 
     match schema_type {
         SchemaTypeSet::Single(single_type) => match single_type {
-            oas3::spec::SchemaType::Object => generate_struct(
-                spec,
-                object_database,
-                definition_path,
-                name,
-                object_schema,
-                name_mapping,
-                config,
-            ),
-            _ => match get_type_from_schema(
-                spec,
-                object_database,
-                definition_path,
-                object_schema,
-                Some(name),
-                name_mapping,
-                config,
-            ) {
+            oas3::spec::SchemaType::Object => {
+                generate_struct(context, definition_path, name, object_schema)
+            }
+            _ => match get_type_from_schema(context, definition_path, object_schema, Some(name)) {
                 Ok(type_definition) => Ok(ObjectDefinition::Primitive(PrimitiveDefinition {
                     name: name.to_owned(),
                     primitive_type: type_definition.clone(),
@@ -132,9 +107,8 @@ pub fn oas3_type_to_string(oas3_type: &oas3::spec::SchemaType) -> String {
 }
 
 pub fn get_object_or_ref_struct_name(
-    spec: &Spec,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
     object_or_reference: &ObjectOrReference<ObjectSchema>,
 ) -> Result<
     (
@@ -150,12 +124,14 @@ pub fn get_object_or_ref_struct_name(
         ObjectOrReference::Ref { ref_path } => {
             let ref_definition_path = get_base_path_to_ref(ref_path)?;
 
-            match object_or_reference.resolve(spec) {
+            match object_or_reference.resolve(context.spec) {
                 Ok(object_schema) => match object_schema.title {
                     Some(ref ref_title) => {
                         return Ok((
                             ref_definition_path.clone(),
-                            name_mapping.name_to_struct_name(&ref_definition_path, ref_title),
+                            context
+                                .name_mapping
+                                .name_to_struct_name(&ref_definition_path, ref_title),
                             object_schema.description.clone(),
                             object_schema.example.clone(),
                         ));
@@ -173,7 +149,9 @@ pub fn get_object_or_ref_struct_name(
 
                         return Ok((
                             ref_definition_path.clone(),
-                            name_mapping.name_to_struct_name(&ref_definition_path, path_name),
+                            context
+                                .name_mapping
+                                .name_to_struct_name(&ref_definition_path, path_name),
                             object_schema.description.clone(),
                             object_schema.example.clone(),
                         ));
@@ -194,7 +172,9 @@ pub fn get_object_or_ref_struct_name(
     if let Some(ref title) = object_schema.title {
         return Ok((
             definition_path.clone(),
-            name_mapping.name_to_struct_name(definition_path, &title),
+            context
+                .name_mapping
+                .name_to_struct_name(definition_path, &title),
             object_schema.description.clone(),
             object_schema.example.clone(),
         ));
@@ -212,7 +192,27 @@ pub fn get_object_or_ref_struct_name(
 
         return Ok((
             definition_path.clone(),
-            name_mapping.name_to_struct_name(definition_path, &type_name),
+            context
+                .name_mapping
+                .name_to_struct_name(definition_path, &type_name),
+            object_schema.description.clone(),
+            object_schema.example.clone(),
+        ));
+    }
+
+    // boolean schemas and pattern maps have no type of their own
+    if object_schema
+        .extensions
+        .contains_key(BOOLEAN_SCHEMA_EXTENSION)
+        || object_schema
+            .extensions
+            .contains_key(PATTERN_PROPERTIES_EXTENSION)
+    {
+        return Ok((
+            definition_path.clone(),
+            context
+                .name_mapping
+                .name_to_struct_name(definition_path, "Object"),
             object_schema.description.clone(),
             object_schema.example.clone(),
         ));
@@ -240,18 +240,38 @@ pub fn get_base_path_to_ref(ref_path: &str) -> Result<Vec<String>, GeneratorErro
     Ok(path_segments)
 }
 
+// `x-enum-descriptions` and `x-enum-deprecated` are arrays in the order of the variants
+fn enum_value_description(object_schema: &ObjectSchema, index: usize) -> Option<String> {
+    object_schema
+        .extensions
+        .get("enum-descriptions")?
+        .as_array()?
+        .get(index)?
+        .as_str()
+        .filter(|description| !description.is_empty())
+        .map(str::to_owned)
+}
+
+fn enum_value_deprecated(object_schema: &ObjectSchema, index: usize) -> bool {
+    object_schema
+        .extensions
+        .get("enum-deprecated")
+        .and_then(|deprecated| deprecated.as_array())
+        .and_then(|deprecated| deprecated.get(index))
+        .and_then(|deprecated| deprecated.as_bool())
+        .unwrap_or(false)
+}
+
 pub fn generate_enum_from_any(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     mut definition_path: Vec<String>,
     name: &str,
     object_schema: &ObjectSchema,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<ObjectDefinition, GeneratorError> {
     trace!("Generating enum");
     let mut enum_definition = EnumDefinition {
-        name: name_mapping
+        name: context
+            .name_mapping
             .name_to_struct_name(&definition_path, name)
             .to_owned(),
         values: HashMap::new(),
@@ -266,22 +286,29 @@ pub fn generate_enum_from_any(
             },
         ],
         description: object_schema.description.clone(),
+        derive_copy: false,
     };
     definition_path.push(enum_definition.name.clone());
 
-    for any_object_ref in &object_schema.any_of {
+    for (index, any_object_ref) in object_schema.any_of.iter().enumerate() {
         trace!("Generating enum value");
         let (any_object_definition_path, any_object) = match any_object_ref {
-            ObjectOrReference::Ref { ref_path } => match any_object_ref.resolve(spec) {
+            ObjectOrReference::Ref { ref_path } => match any_object_ref.resolve(context.spec) {
                 Err(err) => {
-                    error!("{} {}", name, err);
+                    context.report(Diagnostic::error(
+                        &definition_pointer(&definition_path, ""),
+                        format!("{} {}", name, err),
+                    ));
                     continue;
                 }
                 Ok(object_schema) => {
                     let ref_definition_path = match get_base_path_to_ref(ref_path) {
                         Ok(base_path) => base_path,
                         Err(err) => {
-                            error!("Unable to retrieve ref path {}", err);
+                            context.report(Diagnostic::error(
+                                &definition_pointer(&definition_path, ""),
+                                format!("Unable to retrieve ref path {}", err),
+                            ));
                             continue;
                         }
                     };
@@ -294,12 +321,11 @@ pub fn generate_enum_from_any(
         };
 
         let object_type_enum_name = match get_object_or_ref_struct_name(
-            spec,
+            context,
             &any_object_definition_path,
-            name_mapping,
             any_object_ref,
         ) {
-            Ok((_, object_type_struct_name, _, _)) => name_mapping.name_to_struct_name(
+            Ok((_, object_type_struct_name, _, _)) => context.name_mapping.name_to_struct_name(
                 &any_object_definition_path,
                 &format!("{}Value", object_type_struct_name),
             ),
@@ -314,20 +340,22 @@ pub fn generate_enum_from_any(
         enum_definition.values.insert(
             object_type_enum_name.clone(),
             match get_type_from_schema(
-                spec,
-                object_database,
+                context,
                 any_object_definition_path.clone(),
                 &any_object,
                 Some(&object_type_enum_name),
-                name_mapping,
-                config,
             ) {
                 Ok(type_definition) => EnumValue {
                     name: object_type_enum_name,
                     value_type: type_definition,
+                    description: enum_value_description(object_schema, index),
+                    deprecated: enum_value_deprecated(object_schema, index),
                 },
                 Err(err) => {
-                    info!("{} {}", name, err);
+                    context.report(Diagnostic::note(
+                        &definition_pointer(&definition_path, ""),
+                        format!("{} {}", name, err),
+                    ));
                     continue;
                 }
             },
@@ -336,18 +364,44 @@ pub fn generate_enum_from_any(
     Ok(ObjectDefinition::Enum(enum_definition))
 }
 
+/// Schema `false` accepts no value, it becomes an enum without variants which never deserializes
+pub fn generate_never_enum(
+    context: &GenerationContext,
+    definition_path: &Vec<String>,
+    name: &str,
+    object_schema: &ObjectSchema,
+) -> ObjectDefinition {
+    ObjectDefinition::Enum(EnumDefinition {
+        name: context
+            .name_mapping
+            .name_to_struct_name(definition_path, name)
+            .to_owned(),
+        values: HashMap::new(),
+        used_modules: vec![
+            ModuleInfo {
+                name: "Serialize".to_owned(),
+                path: "serde".to_owned(),
+            },
+            ModuleInfo {
+                name: "Deserialize".to_owned(),
+                path: "serde".to_owned(),
+            },
+        ],
+        description: object_schema.description.clone(),
+        derive_copy: false,
+    })
+}
+
 pub fn generate_enum_from_one_of(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     mut definition_path: Vec<String>,
     name: &str,
     object_schema: &ObjectSchema,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<ObjectDefinition, GeneratorError> {
     trace!("Generating enum");
     let mut enum_definition = EnumDefinition {
-        name: name_mapping
+        name: context
+            .name_mapping
             .name_to_struct_name(&definition_path, name)
             .to_owned(),
         values: HashMap::new(),
@@ -362,22 +416,29 @@ pub fn generate_enum_from_one_of(
             },
         ],
         description: object_schema.description.clone(),
+        derive_copy: false,
     };
     definition_path.push(enum_definition.name.clone());
 
-    for one_of_object_ref in &object_schema.one_of {
+    for (index, one_of_object_ref) in object_schema.one_of.iter().enumerate() {
         trace!("Generating enum value");
         let (one_of_object_definition_path, one_of_object) = match one_of_object_ref {
-            ObjectOrReference::Ref { ref_path } => match one_of_object_ref.resolve(spec) {
+            ObjectOrReference::Ref { ref_path } => match one_of_object_ref.resolve(context.spec) {
                 Err(err) => {
-                    error!("{} {}", name, err);
+                    context.report(Diagnostic::error(
+                        &definition_pointer(&definition_path, ""),
+                        format!("{} {}", name, err),
+                    ));
                     continue;
                 }
                 Ok(object_schema) => {
                     let ref_definition_path = match get_base_path_to_ref(ref_path) {
                         Ok(base_path) => base_path,
                         Err(err) => {
-                            error!("Unable to retrieve ref path {}", err);
+                            context.report(Diagnostic::error(
+                                &definition_pointer(&definition_path, ""),
+                                format!("Unable to retrieve ref path {}", err),
+                            ));
                             continue;
                         }
                     };
@@ -390,12 +451,11 @@ pub fn generate_enum_from_one_of(
         };
 
         let object_type_enum_name = match get_object_or_ref_struct_name(
-            spec,
+            context,
             &one_of_object_definition_path,
-            name_mapping,
             one_of_object_ref,
         ) {
-            Ok((_, object_type_struct_name, _, _)) => name_mapping.name_to_struct_name(
+            Ok((_, object_type_struct_name, _, _)) => context.name_mapping.name_to_struct_name(
                 &one_of_object_definition_path,
                 &format!("{}Value", object_type_struct_name),
             ),
@@ -410,20 +470,22 @@ pub fn generate_enum_from_one_of(
         enum_definition.values.insert(
             object_type_enum_name.clone(),
             match get_type_from_schema(
-                spec,
-                object_database,
+                context,
                 one_of_object_definition_path.clone(),
                 &one_of_object,
                 Some(&object_type_enum_name),
-                name_mapping,
-                config,
             ) {
                 Ok(type_definition) => EnumValue {
                     name: object_type_enum_name,
                     value_type: type_definition,
+                    description: enum_value_description(object_schema, index),
+                    deprecated: enum_value_deprecated(object_schema, index),
                 },
                 Err(err) => {
-                    info!("{} {}", name, err);
+                    context.report(Diagnostic::note(
+                        &definition_pointer(&definition_path, ""),
+                        format!("{} {}", name, err),
+                    ));
                     continue;
                 }
             },
@@ -433,18 +495,17 @@ pub fn generate_enum_from_one_of(
 }
 
 pub fn generate_struct(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     mut definition_path: Vec<String>,
     name: &str,
     object_schema: &ObjectSchema,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<ObjectDefinition, GeneratorError> {
-    let full_name = name_mapping.name_to_struct_name(&definition_path, name);
+    let full_name = context
+        .name_mapping
+        .name_to_struct_name(&definition_path, name);
     trace!("Generating struct: {}", full_name);
-    let struct_name = name_mapping.extract_struct_name(&full_name);
-    let package_name = name_mapping.extract_package_name(&full_name);
+    let struct_name = context.name_mapping.extract_struct_name(&full_name);
+    let package_name = context.name_mapping.extract_package_name(&full_name);
     let mut struct_definition = StructDefinition {
         name: struct_name,
         package: package_name,
@@ -461,31 +522,71 @@ pub fn generate_struct(
         ],
         local_objects: HashMap::new(),
         description: object_schema.description.clone(),
+        derive_copy: false,
+        example: object_schema.example.clone(),
     };
     definition_path.push(struct_definition.name.clone());
 
+    if object_schema
+        .extensions
+        .contains_key(PATTERN_PROPERTIES_EXTENSION)
+    {
+        context.report(Diagnostic::note(
+            &definition_pointer(&definition_path, ""),
+            format!(
+                "{} patternProperties next to properties are not typed",
+                name
+            ),
+        ));
+    }
+
     for (property_name, property_ref) in &object_schema.properties {
+        // `false` forbids the property
+        if let ObjectOrReference::Object(property_schema) = property_ref {
+            if property_schema.extensions.get(BOOLEAN_SCHEMA_EXTENSION)
+                == Some(&serde_json::Value::Bool(false))
+            {
+                context.report(Diagnostic::note(
+                    &definition_pointer(&definition_path, property_name),
+                    format!("{} property accepts no value and is not generated", name),
+                ));
+                continue;
+            }
+        }
+
         let property_required = object_schema
             .required
             .iter()
             .any(|property| property == property_name);
 
         let property_definition = match get_or_create_property(
-            spec,
+            context,
             definition_path.clone(),
             property_name,
             property_ref,
             property_required,
-            object_database,
-            name_mapping,
-            config,
         ) {
             Err(err) => {
-                info!("{} {}", name, err);
+                context.report(Diagnostic::note(
+                    &definition_pointer(&definition_path, property_name),
+                    format!("{} {}", name, err),
+                ));
                 continue;
             }
             Ok(property_definition) => property_definition,
         };
+        // all numbers of the decimal packages are arbitrary precision
+        let mut property_definition = property_definition;
+        let number_type = context
+            .config
+            .number_type(Some(&struct_definition.package), None);
+        if number_type != "f64" {
+            property_definition.type_name = match property_definition.type_name.as_str() {
+                "f64" => number_type.to_owned(),
+                "Vec<f64>" => format!("Vec<{}>", number_type),
+                type_name => type_name.to_owned(),
+            };
+        }
         struct_definition
             .properties
             .insert(property_definition.name.clone(), property_definition);
@@ -495,17 +596,14 @@ pub fn generate_struct(
 }
 
 fn get_or_create_property(
-    spec: &Spec,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     property_name: &String,
     property_ref: &ObjectOrReference<ObjectSchema>,
     required: bool,
-    object_database: &ObjectDatabase,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<PropertyDefinition, GeneratorError> {
     trace!("Creating property {}", property_name);
-    let property = match property_ref.resolve(spec) {
+    let property = match property_ref.resolve(context.spec) {
         Ok(property) => property,
         Err(err) => {
             return Err(GeneratorError::ResolveError(format!(
@@ -517,83 +615,102 @@ fn get_or_create_property(
     };
 
     let (property_type_definition_path, property_type_name, description, _example) =
-        get_object_or_ref_struct_name(spec, &definition_path, name_mapping, property_ref)?;
+        get_object_or_ref_struct_name(context, &definition_path, property_ref)?;
+    // the field doc explains why it is not typed
+    let keywords = unsupported_keywords(&property);
+    let description = match (description, keywords.is_empty()) {
+        (description, true) => description,
+        (Some(description), false) => Some(format!(
+            "{}\n\n{}",
+            description,
+            degradation_note(&keywords)
+        )),
+        (None, false) => Some(degradation_note(&keywords)),
+    };
 
     match get_type_from_schema(
-        spec,
-        object_database,
+        context,
         property_type_definition_path,
         &property,
         Some(&property_type_name),
-        name_mapping,
-        config,
     ) {
         Ok(property_type_definition) => Ok(PropertyDefinition {
-            type_name: name_mapping
+            type_name: context
+                .name_mapping
                 .type_to_property_type(property_name, &property_type_definition.name),
             module: property_type_definition.module,
-            name: name_mapping.name_to_property_name(&definition_path, property_name),
+            name: context
+                .name_mapping
+                .name_to_property_name(&definition_path, property_name),
             real_name: property_name.clone(),
             required,
             description,
             example: property.example.clone(),
+            deprecated: false,
+            allow_empty_value: false,
+            format: property.format.clone(),
+            external_docs: property
+                .external_docs
+                .as_ref()
+                .map(|external_docs| ExternalDocs {
+                    url: external_docs.url.to_string(),
+                    description: external_docs.description.clone(),
+                }),
         }),
         Err(err) => Err(err),
     }
 }
 
 pub fn get_or_create_object(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     name: &str,
     property_ref: &ObjectSchema,
-    name_mapping: &NameMapping,
-    config: &Config,
-) -> Result<ObjectDefinition, GeneratorError> {
-    if let Some(object_in_database) =
-        object_database.get(&name_mapping.name_to_struct_name(&definition_path, name))
-    {
+) -> Result<Arc<ObjectDefinition>, GeneratorError> {
+    if let Some(object_in_database) = context.object_database.get(
+        &context
+            .name_mapping
+            .name_to_struct_name(&definition_path, name),
+    ) {
         return Ok(object_in_database.clone());
     }
 
     // create shallow hull which will be filled in later
     // the hull is needed to reference for cyclic dependencies where we would
     // otherwise create the same object every time we want to resolve the current one
-    let struct_name = name_mapping.name_to_struct_name(&definition_path, name);
-    if object_database.contains_key(&struct_name) {
+    let struct_name = context
+        .name_mapping
+        .name_to_struct_name(&definition_path, name);
+    if context.object_database.contains_key(&struct_name) {
         return Err(GeneratorError::ObjectDatabaseDuplicateError(struct_name));
     }
 
     trace!("Adding struct {} to database", struct_name);
-    let package_name = name_mapping.extract_package_name(&struct_name);
-    let name = name_mapping.extract_struct_name(&struct_name);
+    let package_name = context.name_mapping.extract_package_name(&struct_name);
+    let name = context.name_mapping.extract_struct_name(&struct_name);
 
-    object_database.insert(
+    context.object_database.insert(
         struct_name.clone(),
-        ObjectDefinition::Struct(StructDefinition {
+        Arc::new(ObjectDefinition::Struct(StructDefinition {
             package: package_name,
             used_modules: vec![],
             name: name.clone(),
             properties: HashMap::new(),
             local_objects: HashMap::new(),
             description: property_ref.description.clone(),
-        }),
+            derive_copy: false,
+            example: None,
+        })),
     );
 
-    match generate_object(
-        spec,
-        object_database,
-        definition_path,
-        &struct_name,
-        property_ref,
-        name_mapping,
-        config,
-    ) {
+    match generate_object(context, definition_path, &struct_name, property_ref) {
         Ok(created_struct) => {
             let name = get_object_name(&created_struct);
             trace!("Updating struct {} in database", name);
-            object_database.insert(struct_name.clone(), created_struct.clone());
+            let created_struct = Arc::new(created_struct);
+            context
+                .object_database
+                .insert(struct_name.clone(), created_struct.clone());
             Ok(created_struct)
         }
         Err(err) => Err(err),
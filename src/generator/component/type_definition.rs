@@ -1,90 +1,176 @@
-use oas3::{
-    spec::{ObjectSchema, SchemaTypeSet},
-    Spec,
-};
+use oas3::spec::{ObjectOrReference, ObjectSchema, SchemaTypeSet};
 use tracing::trace;
 
 use crate::{
-    generator::types::{ModuleInfo, TypeDefinition},
-    utils::{config::Config, name_mapping::NameMapping},
+    generator::{
+        context::GenerationContext,
+        diagnostics::{definition_pointer, Diagnostic},
+        dialect::{
+            BOOLEAN_SCHEMA_EXTENSION, PATTERN_PROPERTIES_EXTENSION, UNSUPPORTED_KEYWORDS,
+            UNSUPPORTED_KEYWORDS_EXTENSION,
+        },
+        templates::rust::RUST_PRIMITIVE_TYPES,
+        types::{ModuleInfo, TypeDefinition},
+    },
+    utils::config::Config,
     GeneratorError,
 };
 
-use super::{
-    object_definition::{get_object_name, get_object_or_ref_struct_name, get_or_create_object},
-    ObjectDatabase,
+use super::object_definition::{
+    get_object_name, get_object_or_ref_struct_name, get_or_create_object,
 };
 
+/// Unsupported keywords (`not`, `if`/`then`/`else`) used by the schema, listed by the dialect pass
+pub fn unsupported_keywords(object_schema: &ObjectSchema) -> Vec<&'static str> {
+    let listed = match object_schema
+        .extensions
+        .get(UNSUPPORTED_KEYWORDS_EXTENSION)
+        .and_then(serde_json::Value::as_array)
+    {
+        Some(listed) => listed,
+        None => return vec![],
+    };
+    UNSUPPORTED_KEYWORDS
+        .into_iter()
+        .filter(|keyword| listed.iter().any(|value| value.as_str() == Some(*keyword)))
+        .collect()
+}
+
+/// Doc comment explaining why a field is a `serde_json::Value`
+pub fn degradation_note(keywords: &[&str]) -> String {
+    format!(
+        "Typed as `serde_json::Value` because the schema uses {} which is not supported.",
+        keywords
+            .iter()
+            .map(|keyword| format!("`{}`", keyword))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
 pub fn get_type_from_schema(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     object_schema: &ObjectSchema,
     object_variable_fallback_name: Option<&str>,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<TypeDefinition, GeneratorError> {
+    let keywords = unsupported_keywords(object_schema);
+    if !keywords.is_empty() {
+        let schema_name = format!(
+            "{}/{}",
+            definition_path.join("/"),
+            object_schema
+                .title
+                .as_deref()
+                .or(object_variable_fallback_name)
+                .unwrap_or_default()
+        );
+        context.report(Diagnostic::warning(
+            &definition_pointer(
+                &definition_path,
+                object_schema
+                    .title
+                    .as_deref()
+                    .or(object_variable_fallback_name)
+                    .unwrap_or_default(),
+            ),
+            degradation_note(&keywords),
+        ));
+        context.report_degraded_schema(schema_name);
+        return Ok(TypeDefinition {
+            name: "serde_json::Value".to_owned(),
+            module: None,
+            description: Some(degradation_note(&keywords)),
+            example: object_schema.example.clone(),
+        });
+    }
+
+    if let Some(accepts) = object_schema
+        .extensions
+        .get(BOOLEAN_SCHEMA_EXTENSION)
+        .and_then(serde_json::Value::as_bool)
+    {
+        return match accepts {
+            true => Ok(TypeDefinition {
+                name: "serde_json::Value".to_owned(),
+                module: None,
+                description: object_schema.description.clone(),
+                example: object_schema.example.clone(),
+            }),
+            // typed as an enum without variants, see `generate_never_enum`
+            false => get_type_from_any_type(
+                context,
+                definition_path,
+                object_schema,
+                object_variable_fallback_name,
+            ),
+        };
+    }
+
+    if let Some(pattern_properties) = object_schema.extensions.get(PATTERN_PROPERTIES_EXTENSION) {
+        let is_object = matches!(
+            object_schema.schema_type,
+            None | Some(SchemaTypeSet::Single(oas3::spec::SchemaType::Object))
+        );
+        if is_object && object_schema.properties.is_empty() {
+            return get_pattern_map_type(
+                context,
+                definition_path,
+                object_schema,
+                pattern_properties,
+                object_variable_fallback_name.unwrap_or_default(),
+            );
+        }
+    }
+
     if let Some(ref schema_type) = object_schema.schema_type {
         return get_type_from_schema_type(
-            spec,
-            object_database,
+            context,
             definition_path,
             schema_type,
             object_schema,
             object_variable_fallback_name,
-            name_mapping,
-            config,
         );
     }
 
     if object_schema.any_of.len() > 0 {
         return get_type_from_any_type(
-            spec,
-            object_database,
+            context,
             definition_path,
             object_schema,
             object_variable_fallback_name,
-            name_mapping,
-            config,
         );
     }
 
     if object_schema.one_of.len() > 0 {
         return get_type_from_any_type(
-            spec,
-            object_database,
+            context,
             definition_path,
             object_schema,
             object_variable_fallback_name,
-            name_mapping,
-            config,
         );
     }
 
     // Fallback to string if no type is set
     get_type_from_schema_type(
-        spec,
-        object_database,
+        context,
         definition_path,
         &SchemaTypeSet::Single(oas3::spec::SchemaType::String),
         object_schema,
         object_variable_fallback_name,
-        name_mapping,
-        config,
     )
 }
 
 pub fn get_type_from_any_type(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     object_schema: &ObjectSchema,
     object_variable_fallback_name: Option<&str>,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<TypeDefinition, GeneratorError> {
     let object_variable_name = match object_schema.title {
-        Some(ref title) => &name_mapping.name_to_struct_name(&definition_path, &title),
+        Some(ref title) => &context
+            .name_mapping
+            .name_to_struct_name(&definition_path, &title),
         None => match object_variable_fallback_name {
             Some(title_fallback) => title_fallback,
             None => {
@@ -98,20 +184,18 @@ pub fn get_type_from_any_type(
     trace!("Generating any_type {}", object_variable_name);
 
     let object_definition = get_or_create_object(
-        spec,
-        object_database,
+        context,
         definition_path,
         &object_variable_name,
         &object_schema,
-        name_mapping,
-        config,
     )?;
 
     let object_name = get_object_name(&object_definition);
-    let object_path = name_mapping.name_to_module_name(&object_name);
+    let object_path = context.name_mapping.name_to_module_name(&object_name);
 
-    let (object_name, object_path) =
-        name_mapping.validate_object_name_path(&object_name, &object_path);
+    let (object_name, object_path) = context
+        .name_mapping
+        .validate_object_name_path(&object_name, &object_path);
 
     Ok(TypeDefinition {
         name: object_name.clone(),
@@ -124,15 +208,155 @@ pub fn get_type_from_any_type(
     })
 }
 
+/// Prefixes the identifiers of `type_name` naming `module` with its path, e.g.
+/// `Vec<Pet>` becomes `Vec<crate::models::Pet>`; `PetTag` or `models::Pet` are left alone
+fn qualify_type_name(type_name: &str, module: &ModuleInfo) -> String {
+    let mut qualified = String::new();
+    let mut identifier = String::new();
+    for character in type_name.chars().chain(std::iter::once(' ')) {
+        if character.is_alphanumeric() || character == '_' {
+            identifier.push(character);
+            continue;
+        }
+        if identifier == module.name && !qualified.ends_with("::") {
+            qualified.push_str(&module.path);
+            qualified.push_str("::");
+        }
+        qualified.push_str(&identifier);
+        qualified.push(character);
+        identifier.clear();
+    }
+    qualified.pop();
+    qualified
+}
+
+/// `prefixItems` (tuple typing) become a Rust tuple, serde reads and writes them as JSON arrays
+fn get_tuple_type(
+    context: &GenerationContext,
+    definition_path: Vec<String>,
+    object_schema: &ObjectSchema,
+    object_variable_name: &str,
+) -> Result<TypeDefinition, GeneratorError> {
+    let mut item_type_names = vec![];
+    for (index, item_object_ref) in object_schema.prefix_items.iter().enumerate() {
+        let item_object = item_object_ref.resolve(context.spec).map_err(|err| {
+            GeneratorError::ResolveError(format!(
+                "Failed to resolve prefixItems {} of {}\n{}",
+                index,
+                object_variable_name,
+                err.to_string()
+            ))
+        })?;
+        let item_type = get_type_from_schema(
+            context,
+            definition_path.clone(),
+            &item_object,
+            Some(&format!("{}Item{}", object_variable_name, index)),
+        )?;
+        // a type definition has a single module, so objects are referenced by their full path
+        item_type_names.push(match item_type.module {
+            Some(module) if !module.path.is_empty() => qualify_type_name(&item_type.name, &module),
+            _ => item_type.name,
+        });
+    }
+    // single element tuples need the trailing comma
+    let name = match item_type_names.len() {
+        1 => format!("({},)", item_type_names[0]),
+        _ => format!("({})", item_type_names.join(", ")),
+    };
+    Ok(TypeDefinition {
+        name,
+        module: None,
+        description: object_schema.description.clone(),
+        example: object_schema.example.clone(),
+    })
+}
+
+/// `patternProperties` become a map, its values are typed by the pattern schemas if they agree
+fn get_pattern_map_type(
+    context: &GenerationContext,
+    definition_path: Vec<String>,
+    object_schema: &ObjectSchema,
+    pattern_properties: &serde_json::Value,
+    object_variable_name: &str,
+) -> Result<TypeDefinition, GeneratorError> {
+    let patterns = match pattern_properties.as_object() {
+        Some(patterns) => patterns,
+        None => {
+            return Err(GeneratorError::UnsupportedError(format!(
+                "patternProperties of {} is not an object",
+                object_variable_name
+            )))
+        }
+    };
+
+    let mut value_types: Vec<TypeDefinition> = vec![];
+    for (pattern, value_schema) in patterns {
+        let value_object_ref =
+            serde_json::from_value::<ObjectOrReference<ObjectSchema>>(value_schema.clone())
+                .map_err(|err| {
+                    GeneratorError::ParseError(format!(
+                        "patternProperties {} of {}: {}",
+                        pattern, object_variable_name, err
+                    ))
+                })?;
+        let (value_definition_path, value_type_name, _, _) =
+            get_object_or_ref_struct_name(context, &definition_path, &value_object_ref)?;
+        let value_object = value_object_ref.resolve(context.spec).map_err(|err| {
+            GeneratorError::ResolveError(format!(
+                "Failed to resolve patternProperties {} of {}\n{}",
+                pattern,
+                object_variable_name,
+                err.to_string()
+            ))
+        })?;
+        value_types.push(get_type_from_schema(
+            context,
+            value_definition_path,
+            &value_object,
+            Some(&value_type_name),
+        )?);
+    }
+
+    // values matching different patterns can only share a json value
+    let (value_type_name, module) = match value_types.split_first() {
+        Some((first, others)) if others.iter().all(|other| other.name == first.name) => {
+            (first.name.clone(), first.module.clone())
+        }
+        _ => ("serde_json::Value".to_owned(), None),
+    };
+    Ok(TypeDefinition {
+        name: format!("std::collections::HashMap<String, {}>", value_type_name),
+        module,
+        description: object_schema.description.clone(),
+        example: object_schema.example.clone(),
+    })
+}
+
+// serde implements arrays up to 32 items
+const MAX_FIXED_ARRAY_SIZE: u64 = 32;
+
+/// Size of arrays with `minItems == maxItems`, if fixed size arrays are enabled
+fn fixed_array_size(object_schema: &ObjectSchema, config: &Config) -> Option<u64> {
+    if !config.fixed_size_arrays {
+        return None;
+    }
+    match (object_schema.min_items, object_schema.max_items) {
+        (Some(min_items), Some(max_items))
+            if min_items == max_items && min_items > 0 && max_items <= MAX_FIXED_ARRAY_SIZE =>
+        {
+            Some(max_items)
+        }
+        _ => None,
+    }
+}
+
 pub fn get_type_from_schema_type(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: Vec<String>,
     schema_type: &SchemaTypeSet,
     object_schema: &ObjectSchema,
     object_variable_fallback_name: Option<&str>,
-    name_mapping: &NameMapping,
-    config: &Config,
 ) -> Result<TypeDefinition, GeneratorError> {
     let single_type = match schema_type {
         oas3::spec::SchemaTypeSet::Single(single_type) => single_type,
@@ -166,17 +390,29 @@ pub fn get_type_from_schema_type(
             example: object_schema.example.clone(),
         }),
         oas3::spec::SchemaType::Number => Ok(TypeDefinition {
-            name: "f64".to_owned(),
+            name: context
+                .config
+                .number_type(None, object_schema.format.as_deref())
+                .to_owned(),
             module: None,
             description: object_schema.description.clone(),
             example: object_schema.example.clone(),
         }),
         oas3::spec::SchemaType::Integer => Ok(TypeDefinition {
-            name: "i32".to_owned(),
+            name: context
+                .config
+                .integer_type(object_schema.format.as_deref())
+                .to_owned(),
             module: None,
             description: object_schema.description.clone(),
             example: object_schema.example.clone(),
         }),
+        oas3::spec::SchemaType::Array if !object_schema.prefix_items.is_empty() => get_tuple_type(
+            context,
+            definition_path,
+            object_schema,
+            object_variable_name,
+        ),
         oas3::spec::SchemaType::Array => {
             let item_object_ref = match object_schema.items {
                 Some(ref item_object) => item_object,
@@ -187,14 +423,10 @@ pub fn get_type_from_schema_type(
                 }
             };
 
-            let (item_type_definition_path, item_type_name, _, _) = get_object_or_ref_struct_name(
-                spec,
-                &definition_path,
-                name_mapping,
-                &item_object_ref,
-            )?;
+            let (item_type_definition_path, item_type_name, _, _) =
+                get_object_or_ref_struct_name(context, &definition_path, &item_object_ref)?;
 
-            let item_object = match item_object_ref.resolve(spec) {
+            let item_object = match item_object_ref.resolve(context.spec) {
                 Ok(item_object) => item_object,
                 Err(err) => {
                     return Err(GeneratorError::ResolveError(format!(
@@ -206,16 +438,20 @@ pub fn get_type_from_schema_type(
             };
 
             match get_type_from_schema(
-                spec,
-                object_database,
+                context,
                 item_type_definition_path,
                 &item_object,
                 Some(&item_type_name),
-                name_mapping,
-                config,
             ) {
                 Ok(mut type_definition) => {
-                    type_definition.name = format!("Vec<{}>", type_definition.name);
+                    type_definition.name = match fixed_array_size(object_schema, context.config) {
+                        Some(size)
+                            if RUST_PRIMITIVE_TYPES.contains(&type_definition.name.as_str()) =>
+                        {
+                            format!("[{}; {}]", type_definition.name, size)
+                        }
+                        _ => format!("Vec<{}>", type_definition.name),
+                    };
                     return Ok(type_definition);
                 }
                 Err(err) => Err(err),
@@ -223,13 +459,10 @@ pub fn get_type_from_schema_type(
         }
         oas3::spec::SchemaType::Object => {
             let object_definition = get_or_create_object(
-                spec,
-                object_database,
+                context,
                 definition_path,
                 &object_variable_name,
                 &object_schema,
-                name_mapping,
-                config,
             )?;
 
             let object_name = get_object_name(&object_definition);
@@ -242,10 +475,11 @@ pub fn get_type_from_schema_type(
                 });
             }
 
-            let object_path = name_mapping.name_to_module_name(&object_name);
+            let object_path = context.name_mapping.name_to_module_name(&object_name);
 
-            let (object_name, object_path) =
-                name_mapping.validate_object_name_path(&object_name, &object_path);
+            let (object_name, object_path) = context
+                .name_mapping
+                .validate_object_name_path(&object_name, &object_path);
 
             Ok(TypeDefinition {
                 name: object_name.clone(),
@@ -263,3 +497,75 @@ pub fn get_type_from_schema_type(
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use oas3::Spec;
+    use serde_json::json;
+
+    use super::*;
+    use crate::generator::{
+        component::object_definition::get_components_base_path, diagnostics::Diagnostics,
+        dialect::normalize_dialect, types::ObjectDatabase,
+    };
+
+    #[test]
+    fn test_not_schema_is_degraded() {
+        let mut document = json!({
+            "openapi": "3.1.0",
+            "info": {"title": "Pets", "version": "1.0.0"},
+            "components": {
+                "schemas": {
+                    "NotCat": {"type": "object", "not": {"$ref": "#/components/schemas/Cat"}},
+                    "Cat": {"type": "object"}
+                }
+            }
+        });
+        normalize_dialect(&mut document);
+        let spec = serde_json::from_value::<Spec>(document).unwrap();
+        let object_schema = spec.components.as_ref().unwrap().schemas["NotCat"]
+            .resolve(&spec)
+            .unwrap();
+        let config = Config::new();
+        let object_database = ObjectDatabase::new();
+        let diagnostics = Diagnostics::new();
+        let context = GenerationContext::new(&spec, &object_database, &config)
+            .with_diagnostics(&diagnostics, Path::new("pets.yaml"));
+
+        let type_definition = get_type_from_schema(
+            &context,
+            get_components_base_path(),
+            &object_schema,
+            Some("NotCat"),
+        )
+        .unwrap();
+        assert_eq!(type_definition.name, "serde_json::Value");
+        assert_eq!(
+            diagnostics.degraded_schemas(),
+            vec!["#/components/schemas/NotCat"]
+        );
+    }
+
+    #[test]
+    fn test_qualify_type_name() {
+        let module = ModuleInfo {
+            name: "Pet".to_owned(),
+            path: "crate::models".to_owned(),
+        };
+        assert_eq!(
+            qualify_type_name("Vec<Pet>", &module),
+            "Vec<crate::models::Pet>"
+        );
+        assert_eq!(qualify_type_name("PetTag", &module), "PetTag");
+        assert_eq!(
+            qualify_type_name("HashMap<String, Pet>", &module),
+            "HashMap<String, crate::models::Pet>"
+        );
+        assert_eq!(
+            qualify_type_name("crate::models::Pet", &module),
+            "crate::models::Pet"
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::{HashMap, HashSet};
+
+use tracing::info;
+
+use crate::generator::types::{ObjectDatabase, ObjectDefinition, PropertyDefinition};
+
+/// How a field of the target model is filled from the source model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldConversion {
+    /// Same type, moved as is
+    Move,
+    /// Model (or Vec of models) converted with `TryInto`
+    Convert { is_vec: bool },
+    /// Optional model converted with `TryInto`
+    ConvertOption,
+    /// Optional in the source, required in the target
+    Required(Box<FieldConversion>),
+    /// Required in the source, optional in the target
+    Optional(Box<FieldConversion>),
+    /// Missing in the source, optional in the target
+    Default,
+}
+
+impl FieldConversion {
+    /// Conversions which can fail (or convert nested models) need a `TryFrom`
+    pub fn is_fallible(&self) -> bool {
+        match self {
+            FieldConversion::Move | FieldConversion::Default => false,
+            FieldConversion::Optional(inner) => inner.is_fallible(),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMapping {
+    pub name: String,
+    pub real_name: String,
+    pub conversion: FieldConversion,
+}
+
+/// Conversion between two models with the same name in two packages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructConversion {
+    pub source: String,
+    pub target: String,
+    pub fields: Vec<FieldMapping>,
+}
+
+impl StructConversion {
+    pub fn is_fallible(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.conversion.is_fallible())
+    }
+}
+
+fn is_optional(property: &PropertyDefinition) -> bool {
+    !property.required
+        && !property.type_name.starts_with("Vec<")
+        && !property.type_name.starts_with("Map<")
+}
+
+fn structs_of_package(
+    object_database: &ObjectDatabase,
+    package: &str,
+) -> HashMap<String, HashMap<String, PropertyDefinition>> {
+    object_database
+        .iter()
+        .filter_map(|item| match item.value().as_ref() {
+            ObjectDefinition::Struct(struct_definition) if struct_definition.package == package => {
+                Some((
+                    struct_definition.name.clone(),
+                    struct_definition.properties.clone(),
+                ))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// conversion of a required value of `source_type` into `target_type`, None if incompatible
+fn type_conversion(
+    source_type: &str,
+    target_type: &str,
+    convertible: &HashSet<String>,
+    source_names: &HashSet<String>,
+    target_names: &HashSet<String>,
+) -> Option<FieldConversion> {
+    if source_type != target_type {
+        return None;
+    }
+    let (inner_type, is_vec) = match source_type
+        .strip_prefix("Vec<")
+        .and_then(|inner| inner.strip_suffix('>'))
+    {
+        Some(inner_type) => (inner_type, true),
+        None => (source_type, false),
+    };
+    match (
+        source_names.contains(inner_type),
+        target_names.contains(inner_type),
+    ) {
+        // same name, but different models
+        (true, true) => match convertible.contains(inner_type) {
+            true => Some(FieldConversion::Convert { is_vec }),
+            false => None,
+        },
+        (false, false) => Some(FieldConversion::Move),
+        _ => None,
+    }
+}
+
+fn field_mappings(
+    source: &HashMap<String, PropertyDefinition>,
+    target: &HashMap<String, PropertyDefinition>,
+    convertible: &HashSet<String>,
+    source_names: &HashSet<String>,
+    target_names: &HashSet<String>,
+) -> Result<Vec<FieldMapping>, String> {
+    let mut fields = vec![];
+    for (name, target_property) in target {
+        let conversion = match source.get(name) {
+            Some(source_property) => {
+                let conversion = type_conversion(
+                    &source_property.type_name,
+                    &target_property.type_name,
+                    convertible,
+                    source_names,
+                    target_names,
+                )
+                .ok_or(format!(
+                    "{} has the type {} instead of {}",
+                    name, source_property.type_name, target_property.type_name
+                ))?;
+                match (is_optional(source_property), is_optional(target_property)) {
+                    (true, true) => match conversion {
+                        FieldConversion::Move => FieldConversion::Move,
+                        _ => FieldConversion::ConvertOption,
+                    },
+                    (true, false) => FieldConversion::Required(Box::new(conversion)),
+                    (false, true) => FieldConversion::Optional(Box::new(conversion)),
+                    (false, false) => conversion,
+                }
+            }
+            None if target_property.required => {
+                return Err(format!("required {} is missing", name));
+            }
+            None => FieldConversion::Default,
+        };
+        fields.push(FieldMapping {
+            name: target_property.name.clone(),
+            real_name: target_property.real_name.clone(),
+            conversion,
+        });
+    }
+    fields.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(fields)
+}
+
+/// Finds the structs with the same name in `source_package` and `target_package`
+/// and maps the fields of the target to the fields of the source.
+///
+/// Structs with incompatible fields (or referencing such structs) are skipped.
+pub fn generate_version_conversions(
+    object_database: &ObjectDatabase,
+    source_package: &str,
+    target_package: &str,
+) -> Vec<StructConversion> {
+    let source_structs = structs_of_package(object_database, source_package);
+    let target_structs = structs_of_package(object_database, target_package);
+    let source_names = source_structs.keys().cloned().collect::<HashSet<String>>();
+    let target_names = target_structs.keys().cloned().collect::<HashSet<String>>();
+    // structs may reference each other, so we remove incompatible ones until nothing changes
+    let mut convertible = source_names
+        .intersection(&target_names)
+        .cloned()
+        .collect::<HashSet<String>>();
+    loop {
+        let incompatible = convertible
+            .iter()
+            .filter_map(|name| {
+                field_mappings(
+                    &source_structs[name],
+                    &target_structs[name],
+                    &convertible,
+                    &source_names,
+                    &target_names,
+                )
+                .err()
+                .map(|reason| (name.clone(), reason))
+            })
+            .collect::<Vec<(String, String)>>();
+        if incompatible.is_empty() {
+            break;
+        }
+        for (name, reason) in incompatible {
+            info!(
+                "No conversion {}::{} -> {}::{}: {}",
+                source_package, name, target_package, name, reason
+            );
+            convertible.remove(&name);
+        }
+    }
+
+    let mut conversions = convertible
+        .iter()
+        .map(|name| StructConversion {
+            source: format!("{}::{}", source_package, name),
+            target: format!("{}::{}", target_package, name),
+            fields: field_mappings(
+                &source_structs[name],
+                &target_structs[name],
+                &convertible,
+                &source_names,
+                &target_names,
+            )
+            .unwrap_or_default(),
+        })
+        .collect::<Vec<StructConversion>>();
+    conversions.sort_by(|a, b| a.target.cmp(&b.target));
+    conversions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::generator::types::StructDefinition;
+
+    fn property(name: &str, type_name: &str, required: bool) -> PropertyDefinition {
+        PropertyDefinition {
+            name: name.to_owned(),
+            real_name: name.to_owned(),
+            type_name: type_name.to_owned(),
+            required,
+            ..Default::default()
+        }
+    }
+
+    fn insert_struct(
+        object_database: &ObjectDatabase,
+        package: &str,
+        name: &str,
+        properties: Vec<PropertyDefinition>,
+    ) {
+        object_database.insert(
+            format!("{}::{}", package, name),
+            Arc::new(ObjectDefinition::Struct(StructDefinition {
+                package: package.to_owned(),
+                name: name.to_owned(),
+                properties: properties
+                    .into_iter()
+                    .map(|property| (property.name.clone(), property))
+                    .collect::<HashMap<_, _>>(),
+                ..Default::default()
+            })),
+        );
+    }
+
+    #[test]
+    fn test_generate_version_conversions() {
+        let object_database = ObjectDatabase::new();
+        insert_struct(
+            &object_database,
+            "models::v1",
+            "Tag",
+            vec![property("name", "String", true)],
+        );
+        insert_struct(
+            &object_database,
+            "models::v2",
+            "Tag",
+            vec![
+                property("name", "String", true),
+                property("color", "String", false),
+            ],
+        );
+        insert_struct(
+            &object_database,
+            "models::v1",
+            "Pet",
+            vec![
+                property("name", "String", false),
+                property("tags", "Vec<Tag>", false),
+            ],
+        );
+        insert_struct(
+            &object_database,
+            "models::v2",
+            "Pet",
+            vec![
+                property("name", "String", true),
+                property("tags", "Vec<Tag>", false),
+            ],
+        );
+        insert_struct(
+            &object_database,
+            "models::v1",
+            "Owner",
+            vec![property("age", "String", true)],
+        );
+        insert_struct(
+            &object_database,
+            "models::v2",
+            "Owner",
+            vec![property("age", "i64", true)],
+        );
+
+        let conversions =
+            generate_version_conversions(&object_database, "models::v1", "models::v2");
+        assert_eq!(conversions.len(), 2);
+
+        let pet = &conversions[0];
+        assert_eq!(pet.target, "models::v2::Pet");
+        assert!(pet.is_fallible());
+        assert_eq!(
+            pet.fields[0].conversion,
+            FieldConversion::Required(Box::new(FieldConversion::Move))
+        );
+        assert_eq!(
+            pet.fields[1].conversion,
+            FieldConversion::Convert { is_vec: true }
+        );
+
+        let tag = &conversions[1];
+        assert_eq!(tag.source, "models::v1::Tag");
+        assert!(!tag.is_fallible());
+        assert_eq!(tag.fields[0].conversion, FieldConversion::Default);
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::HashSet, path::Path};
+
+use oas3::Spec;
+
+use crate::utils::{config::Config, name_mapping::NameMapping};
+
+use super::{
+    diagnostics::{Diagnostic, Diagnostics},
+    types::ObjectDatabase,
+};
+
+/// State shared by the functions generating the objects and paths of a spec.
+///
+/// Passed by reference instead of the individual values, so a new cross-cutting
+/// input only needs a field here.
+#[derive(Clone, Copy)]
+pub struct GenerationContext<'a> {
+    pub spec: &'a Spec,
+    pub object_database: &'a ObjectDatabase,
+    pub name_mapping: &'a NameMapping,
+    pub config: &'a Config,
+    /// Sink of the problems found in the spec, without one they are only logged
+    pub diagnostics: Option<&'a Diagnostics>,
+    /// File the spec was read from, recorded in the diagnostics
+    pub spec_file: Option<&'a Path>,
+    /// Operation ids documented with `security: []`
+    pub anonymous_operations: Option<&'a HashSet<String>>,
+}
+
+impl<'a> GenerationContext<'a> {
+    /// Context of `spec`, names are mapped with the name mapping of `config`
+    pub fn new(spec: &'a Spec, object_database: &'a ObjectDatabase, config: &'a Config) -> Self {
+        GenerationContext {
+            spec,
+            object_database,
+            name_mapping: &config.name_mapping,
+            config,
+            diagnostics: None,
+            spec_file: None,
+            anonymous_operations: None,
+        }
+    }
+
+    /// Collects the diagnostics of the spec read from `spec_file` into `diagnostics`
+    pub fn with_diagnostics(self, diagnostics: &'a Diagnostics, spec_file: &'a Path) -> Self {
+        GenerationContext {
+            diagnostics: Some(diagnostics),
+            spec_file: Some(spec_file),
+            ..self
+        }
+    }
+
+    pub fn with_anonymous_operations(self, anonymous_operations: &'a HashSet<String>) -> Self {
+        GenerationContext {
+            anonymous_operations: Some(anonymous_operations),
+            ..self
+        }
+    }
+
+    /// Whether the operation is documented as callable without credentials
+    pub fn is_anonymous(&self, operation_id: &str) -> bool {
+        self.anonymous_operations
+            .is_some_and(|anonymous_operations| anonymous_operations.contains(operation_id))
+    }
+
+    /// Logs the diagnostic and adds it to the sink
+    pub fn report(&self, diagnostic: Diagnostic) {
+        let diagnostic = match self.spec_file {
+            Some(spec_file) => diagnostic.with_spec(spec_file),
+            None => diagnostic,
+        };
+        diagnostic.log();
+        if let Some(diagnostics) = self.diagnostics {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    /// Records a schema typed as `serde_json::Value`, counted in the statistics
+    pub fn report_degraded_schema(&self, schema_name: String) {
+        if let Some(diagnostics) = self.diagnostics {
+            diagnostics.push_degraded_schema(schema_name);
+        }
+    }
+}
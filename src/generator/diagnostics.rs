@@ -0,0 +1,218 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// Problem found in a spec, with the location it was found at
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// JSON Pointer into the spec, e.g. `#/components/schemas/Pet`
+    pub pointer: String,
+    pub spec: Option<PathBuf>,
+    pub operation: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, pointer: &str, message: impl Display) -> Self {
+        Diagnostic {
+            severity,
+            message: message.to_string(),
+            pointer: pointer.to_owned(),
+            spec: None,
+            operation: None,
+        }
+    }
+
+    pub fn error(pointer: &str, message: impl Display) -> Self {
+        Diagnostic::new(Severity::Error, pointer, message)
+    }
+
+    pub fn warning(pointer: &str, message: impl Display) -> Self {
+        Diagnostic::new(Severity::Warning, pointer, message)
+    }
+
+    pub fn note(pointer: &str, message: impl Display) -> Self {
+        Diagnostic::new(Severity::Note, pointer, message)
+    }
+
+    pub fn with_operation(mut self, operation: &str) -> Self {
+        self.operation = Some(operation.to_owned());
+        self
+    }
+
+    pub fn with_spec(mut self, spec: &Path) -> Self {
+        self.spec = Some(spec.to_path_buf());
+        self
+    }
+
+    /// Logs the diagnostic at the level of its severity
+    pub fn log(&self) {
+        match self.severity {
+            Severity::Error => error!("{}", self),
+            Severity::Warning => warn!("{}", self),
+            Severity::Note => info!("{}", self),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref spec) = self.spec {
+            write!(f, "{}", spec.display())?;
+        }
+        write!(f, "{}", self.pointer)?;
+        if let Some(ref operation) = self.operation {
+            write!(f, " ({})", operation)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// JSON Pointer of the given path in a spec, `~` and `/` of the segments are escaped
+pub fn json_pointer<S: AsRef<str>>(segments: &[S]) -> String {
+    segments.iter().fold("#".to_owned(), |pointer, segment| {
+        format!(
+            "{}/{}",
+            pointer,
+            segment.as_ref().replace('~', "~0").replace('/', "~1")
+        )
+    })
+}
+
+/// Pointer of the object generated for `name` at a definition path, component paths start
+/// with `#`, operation paths with the path of the operation
+pub fn definition_pointer(definition_path: &[String], name: &str) -> String {
+    let segments = match definition_path.split_first() {
+        Some((root, segments)) if root == "#" => segments.to_vec(),
+        _ => std::iter::once("paths".to_owned())
+            .chain(definition_path.iter().cloned())
+            .collect(),
+    };
+    match name.is_empty() {
+        true => json_pointer(&segments),
+        false => json_pointer(&[segments, vec![name.to_owned()]].concat()),
+    }
+}
+
+/// Diagnostics collected while generating, shared by all specs of a run
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    entries: Mutex<Vec<Diagnostic>>,
+    degraded_schemas: Mutex<Vec<String>>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics::default()
+    }
+
+    pub fn push(&self, diagnostic: Diagnostic) {
+        self.entries.lock().unwrap().push(diagnostic);
+    }
+
+    pub fn all(&self) -> Vec<Diagnostic> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().unwrap().is_empty()
+    }
+
+    /// Records a schema typed as `serde_json::Value` because of unsupported keywords
+    pub fn push_degraded_schema(&self, schema_name: String) {
+        self.degraded_schemas.lock().unwrap().push(schema_name);
+    }
+
+    /// Schemas typed as `serde_json::Value` because of unsupported keywords
+    pub fn degraded_schemas(&self) -> Vec<String> {
+        self.degraded_schemas.lock().unwrap().clone()
+    }
+}
+
+/// Renders the diagnostics grouped by severity, errors first
+impl Display for Diagnostics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.all();
+        for severity in [Severity::Error, Severity::Warning, Severity::Note] {
+            let diagnostics = entries
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == severity)
+                .collect::<Vec<&Diagnostic>>();
+            if diagnostics.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} ({}):", severity, diagnostics.len())?;
+            for diagnostic in diagnostics {
+                writeln!(f, "  {}", diagnostic)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_pointer() {
+        assert_eq!(
+            json_pointer(&["paths", "/pets/{id}", "get"]),
+            "#/paths/~1pets~1{id}/get"
+        );
+        assert_eq!(
+            json_pointer(&["components", "schemas", "a~b"]),
+            "#/components/schemas/a~0b"
+        );
+        assert_eq!(
+            definition_pointer(&["/pets".to_owned()], "PetBody"),
+            "#/paths/~1pets/PetBody"
+        );
+    }
+
+    #[test]
+    fn test_grouped_by_severity() {
+        let diagnostics = Diagnostics::new();
+        diagnostics.push(Diagnostic::warning("#/components/schemas/A", "first"));
+        diagnostics.push(Diagnostic::error("#/paths/~1a/get", "failed").with_operation("get_a"));
+        diagnostics.push(Diagnostic::warning("#/components/schemas/B", "second"));
+        assert_eq!(
+            diagnostics.to_string(),
+            "error (1):\n  #/paths/~1a/get (get_a): failed\n\
+             warning (2):\n  #/components/schemas/A: first\n  #/components/schemas/B: second\n"
+        );
+    }
+}
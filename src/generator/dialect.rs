@@ -0,0 +1,381 @@
+use std::{collections::BTreeMap, path::Path};
+
+use oas3::Spec;
+use serde_json::{Map, Value};
+
+use crate::{generator::path::security::mark_anonymous_operations, GeneratorError};
+
+/// Extension replacing a boolean schema, `true` accepts any value and `false` none
+pub const BOOLEAN_SCHEMA_EXTENSION: &str = "boolean-schema";
+/// Extension holding the `patternProperties` of a schema
+pub const PATTERN_PROPERTIES_EXTENSION: &str = "pattern-properties";
+/// Extension listing the keywords of a schema which can't be expressed by the generated types
+pub const UNSUPPORTED_KEYWORDS_EXTENSION: &str = "unsupported-keywords";
+
+/// Schema keywords which can't be expressed by the generated types, the parsed schema drops them
+pub const UNSUPPORTED_KEYWORDS: [&str; 4] = ["not", "if", "then", "else"];
+
+// keywords whose value is a schema
+const SCHEMA_KEYWORDS: [&str; 11] = [
+    "items",
+    "additionalProperties",
+    "unevaluatedProperties",
+    "unevaluatedItems",
+    "contains",
+    "propertyNames",
+    "not",
+    "if",
+    "then",
+    "else",
+    "schema",
+];
+// keywords whose value is a map of schemas, its keys are names and never keywords
+// (`patternProperties` are moved to the extension before their schemas are visited)
+const SCHEMA_MAP_KEYWORDS: [&str; 5] = [
+    "properties",
+    "patternProperties",
+    "x-pattern-properties",
+    "$defs",
+    "schemas",
+];
+// keywords whose value is a list of schemas
+const SCHEMA_LIST_KEYWORDS: [&str; 4] = ["allOf", "anyOf", "oneOf", "prefixItems"];
+// keywords of a schema holding instance values, which are never rewritten
+const VALUE_KEYWORDS: [&str; 5] = ["example", "examples", "default", "const", "enum"];
+// keywords holding instance values outside of schemas, `default` is also a response key there
+const EXAMPLE_KEYWORDS: [&str; 2] = ["example", "examples"];
+
+/// Reads a spec, written in JSON or YAML, with the JSON Schema 2020-12 keywords of
+/// OpenAPI 3.1 rewritten to the ones the generator understands
+pub fn read_spec(spec_file_path: &Path) -> Result<Spec, GeneratorError> {
+    let content = std::fs::read_to_string(spec_file_path).map_err(|err| {
+        GeneratorError::ParseError(format!("{}: {}", spec_file_path.display(), err))
+    })?;
+    // YAML is a superset of JSON, both spec formats are read the same way
+    let mut document = serde_yml::from_str::<Value>(&content).map_err(|err| {
+        GeneratorError::ParseError(format!("{}: {}", spec_file_path.display(), err))
+    })?;
+    normalize_dialect(&mut document);
+    mark_anonymous_operations(&mut document);
+    serde_json::from_value(document)
+        .map_err(|err| GeneratorError::ParseError(format!("{}: {}", spec_file_path.display(), err)))
+}
+
+/// Rewrites the 3.1 dialect keywords of a spec document:
+///
+/// - `$defs` are moved to `components/schemas` and their references updated
+/// - boolean schemas become `{"x-boolean-schema": true|false}`
+/// - `patternProperties` move to `x-pattern-properties`, typed as maps by the generator
+/// - `unevaluatedProperties` stands in for a missing `additionalProperties`
+/// - `not` and `if`/`then`/`else` are listed in `x-unsupported-keywords`
+pub fn normalize_dialect(document: &mut Value) {
+    hoist_defs(document);
+    normalize_schemas(document);
+}
+
+fn hoist_defs(document: &mut Value) {
+    loop {
+        let mut defs = vec![];
+        collect_defs(document, &mut defs);
+        if defs.is_empty() {
+            return;
+        }
+
+        let mut schemas = document
+            .pointer("/components/schemas")
+            .and_then(Value::as_object)
+            .map(|schemas| schemas.keys().cloned().collect::<Vec<String>>())
+            .unwrap_or_default();
+        // reference of the definition -> reference of the hoisted schema
+        let mut renames = BTreeMap::new();
+        let mut hoisted = Map::new();
+        for (pointer, name, schema) in defs {
+            let hoisted_name = hoisted_name(&pointer, &name, &schemas);
+            schemas.push(hoisted_name.clone());
+            renames.insert(
+                format!("#{}/$defs/{}", pointer, escape(&name)),
+                format!("#/components/schemas/{}", escape(&hoisted_name)),
+            );
+            hoisted.insert(hoisted_name, schema);
+        }
+
+        rewrite_refs(document, &renames);
+        if !document.is_object() {
+            return;
+        }
+        let components = document
+            .as_object_mut()
+            .unwrap()
+            .entry("components")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Some(components) = components.as_object_mut() {
+            let schemas = components
+                .entry("schemas")
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(schemas) = schemas.as_object_mut() {
+                schemas.extend(hoisted);
+            }
+        }
+    }
+}
+
+// removes the outermost `$defs`, nested ones are hoisted by the next pass
+fn collect_defs(document: &mut Value, defs: &mut Vec<(String, String, Value)>) {
+    walk(document, "", false, &mut |value, pointer, _| {
+        if let Some(Value::Object(object_defs)) = value
+            .as_object_mut()
+            .and_then(|object| object.remove("$defs"))
+        {
+            for (name, schema) in object_defs {
+                defs.push((pointer.to_owned(), name, schema));
+            }
+        }
+    });
+}
+
+/// Calls `visit` with every value of the document, with its JSON Pointer and whether it is
+/// in schema position, before visiting its children.
+///
+/// The document is walked by its structure: the entries of schema maps (`properties`,
+/// `$defs`, ...) are visited as schemas whatever their name, and the instance values of
+/// `VALUE_KEYWORDS` are skipped in schemas only.
+fn walk(
+    value: &mut Value,
+    pointer: &str,
+    in_schema: bool,
+    visit: &mut dyn FnMut(&mut Value, &str, bool),
+) {
+    visit(value, pointer, in_schema);
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                let key = key.as_str();
+                let child_pointer = format!("{}/{}", pointer, escape(key));
+                let value_keywords: &[&str] = match in_schema {
+                    true => &VALUE_KEYWORDS,
+                    false => &EXAMPLE_KEYWORDS,
+                };
+                if value_keywords.contains(&key) {
+                    continue;
+                }
+                if SCHEMA_MAP_KEYWORDS.contains(&key) && child.is_object() {
+                    for (name, schema) in child.as_object_mut().unwrap().iter_mut() {
+                        let schema_pointer = format!("{}/{}", child_pointer, escape(name));
+                        walk(schema, &schema_pointer, true, visit);
+                    }
+                } else if SCHEMA_LIST_KEYWORDS.contains(&key) && child.is_array() {
+                    for (index, schema) in child.as_array_mut().unwrap().iter_mut().enumerate() {
+                        walk(schema, &format!("{}/{}", child_pointer, index), true, visit);
+                    }
+                } else {
+                    let child_in_schema = SCHEMA_KEYWORDS.contains(&key);
+                    walk(child, &child_pointer, child_in_schema, visit);
+                }
+            }
+        }
+        Value::Array(array) => {
+            for (index, child) in array.iter_mut().enumerate() {
+                walk(child, &format!("{}/{}", pointer, index), false, visit);
+            }
+        }
+        _ => {}
+    }
+}
+
+// the name of the definition, prefixed with the schema defining it when taken
+fn hoisted_name(pointer: &str, name: &str, schemas: &[String]) -> String {
+    let parent = pointer
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .replace("~1", "/")
+        .replace("~0", "~");
+    let candidates = [name.to_owned(), format!("{}{}", parent, capitalize(name))];
+    if let Some(candidate) = candidates
+        .iter()
+        .find(|candidate| !schemas.contains(candidate))
+    {
+        return candidate.clone();
+    }
+    (2..)
+        .map(|index| format!("{}{}", candidates[1], index))
+        .find(|candidate| !schemas.contains(candidate))
+        .unwrap()
+}
+
+fn rewrite_refs(document: &mut Value, renames: &BTreeMap<String, String>) {
+    walk(document, "", false, &mut |value, _, _| {
+        if let Some(Value::String(reference)) = value.get_mut("$ref") {
+            let renamed =
+                renames
+                    .iter()
+                    .find_map(|(from, to)| match reference.strip_prefix(from.as_str()) {
+                        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                            Some(format!("{}{}", to, rest))
+                        }
+                        _ => None,
+                    });
+            if let Some(renamed) = renamed {
+                *reference = renamed;
+            }
+        }
+    });
+}
+
+fn normalize_schemas(document: &mut Value) {
+    walk(document, "", false, &mut |value, _, in_schema| {
+        if in_schema {
+            replace_boolean_schema(value);
+        }
+        let object = match value.as_object_mut() {
+            Some(object) => object,
+            None => return,
+        };
+        if let Some(pattern_properties) = object.remove("patternProperties") {
+            object.insert(
+                format!("x-{}", PATTERN_PROPERTIES_EXTENSION),
+                pattern_properties,
+            );
+        }
+        // without composition both keywords apply to the same properties
+        if let Some(unevaluated_properties) = object.remove("unevaluatedProperties") {
+            if !object.contains_key("additionalProperties") {
+                object.insert("additionalProperties".to_owned(), unevaluated_properties);
+            }
+        }
+        let unsupported_keywords = UNSUPPORTED_KEYWORDS
+            .into_iter()
+            .filter(|keyword| in_schema && object.contains_key(*keyword))
+            .map(|keyword| Value::String(keyword.to_owned()))
+            .collect::<Vec<Value>>();
+        if !unsupported_keywords.is_empty() {
+            object.insert(
+                format!("x-{}", UNSUPPORTED_KEYWORDS_EXTENSION),
+                Value::Array(unsupported_keywords),
+            );
+        }
+    });
+}
+
+fn replace_boolean_schema(schema: &mut Value) {
+    if let Value::Bool(accepts) = schema {
+        let mut object = Map::new();
+        object.insert(
+            format!("x-{}", BOOLEAN_SCHEMA_EXTENSION),
+            Value::Bool(*accepts),
+        );
+        *schema = Value::Object(object);
+    }
+}
+
+// JSON Pointer escaping of a segment
+fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_hoist_defs() {
+        let mut document = json!({
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "properties": {"tag": {"$ref": "#/components/schemas/Pet/$defs/Tag"}},
+                        "$defs": {"Tag": {"type": "string"}}
+                    },
+                    "Tag": {"type": "integer"}
+                }
+            }
+        });
+        normalize_dialect(&mut document);
+        assert_eq!(
+            document.pointer("/components/schemas/Pet/properties/tag/$ref"),
+            Some(&json!("#/components/schemas/PetTag"))
+        );
+        assert_eq!(
+            document.pointer("/components/schemas/PetTag"),
+            Some(&json!({"type": "string"}))
+        );
+        assert_eq!(document.pointer("/components/schemas/Pet/$defs"), None);
+    }
+
+    #[test]
+    fn test_boolean_and_pattern_schemas() {
+        let mut document = json!({
+            "type": "object",
+            "properties": {"any": true, "none": false},
+            "patternProperties": {"^x-": {"type": "string"}},
+            "unevaluatedProperties": false,
+            "example": {"properties": true}
+        });
+        normalize_dialect(&mut document);
+        assert_eq!(
+            document,
+            json!({
+                "type": "object",
+                "properties": {
+                    "any": {"x-boolean-schema": true},
+                    "none": {"x-boolean-schema": false}
+                },
+                "x-pattern-properties": {"^x-": {"type": "string"}},
+                "additionalProperties": {"x-boolean-schema": false},
+                "example": {"properties": true}
+            })
+        );
+    }
+
+    #[test]
+    fn test_names_are_not_keywords() {
+        let mut document = json!({
+            "paths": {"/pets": {"get": {"responses": {"default": {"content": {
+                "application/json": {"schema": {"items": false}}
+            }}}}}},
+            "components": {
+                "schemas": {
+                    "Settings": {
+                        "type": "object",
+                        "properties": {
+                            "default": false,
+                            "patternProperties": {"type": "string"},
+                            "$defs": {"type": "string"}
+                        },
+                        "default": {"unevaluatedProperties": true}
+                    }
+                }
+            }
+        });
+        normalize_dialect(&mut document);
+        assert_eq!(
+            document.pointer(
+                "/paths/~1pets/get/responses/default/content/application~1json/schema/items"
+            ),
+            Some(&json!({"x-boolean-schema": false}))
+        );
+        assert_eq!(
+            document.pointer("/components/schemas/Settings"),
+            Some(&json!({
+                "type": "object",
+                "properties": {
+                    "default": {"x-boolean-schema": false},
+                    "patternProperties": {"type": "string"},
+                    "$defs": {"type": "string"}
+                },
+                "default": {"unevaluatedProperties": true}
+            }))
+        );
+    }
+}
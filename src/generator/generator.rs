@@ -1,19 +1,55 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use crate::Language;
-use oas3::{spec::Operation, Spec};
+use oas3::{
+    spec::{ObjectOrReference, Operation, Parameter},
+    Spec,
+};
 use tracing::{error, info};
 
 use crate::{
     generator::{
-        path::{default_request, websocket_request},
-        types::{Method, ObjectDatabase, PathDatabase},
+        context::GenerationContext,
+        diagnostics::{json_pointer, Diagnostic, Diagnostics},
+        dialect::read_spec,
+        path::{
+            default_request, security::anonymous_operations, utils::generate_reusable_components,
+            websocket_request,
+        },
+        types::{Method, ObjectDatabase, PathDatabase, WebsocketDatabase},
+    },
+    utils::{
+        artifact::FileArtifact,
+        config::{Config, UnknownFields},
+        file::{
+            capture_files, fingerprint, set_file_header, start_run, take_artifacts,
+            update_manifest, write_duration, write_statistics,
+        },
     },
-    utils::config::Config,
     GeneratorError,
 };
 
-use super::{component::generate_components, templates::rust};
+use super::{
+    component::{copy_analysis::mark_copy_types, generate_components},
+    ir::IntermediateRepresentation,
+    statistics::GenerationStatistics,
+    templates::rust,
+};
+
+/// Options of a single `Generator::run`
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Load the databases from this IR instead of analysing the specs
+    pub from_ir: Option<PathBuf>,
+    /// Write the databases to this IR and stop before rendering
+    pub emit_ir: Option<PathBuf>,
+    /// Delete stale files listed in the manifest
+    pub prune: bool,
+}
 
 pub struct Generator {
     config: Config,
@@ -21,6 +57,9 @@ pub struct Generator {
     specs: Vec<PathBuf>,
     object_database: ObjectDatabase,
     path_database: PathDatabase,
+    websocket_database: WebsocketDatabase,
+    statistics: Mutex<GenerationStatistics>,
+    diagnostics: Diagnostics,
 }
 
 impl Generator {
@@ -31,27 +70,214 @@ impl Generator {
             specs,
             object_database: ObjectDatabase::new(),
             path_database: PathDatabase::new(),
+            websocket_database: WebsocketDatabase::new(),
+            statistics: Mutex::new(GenerationStatistics::default()),
+            diagnostics: Diagnostics::new(),
+        }
+    }
+
+    /// Runs all stages in order.
+    ///
+    /// Failing to load the specs (or IR) aborts the run, failures of the rendering stages
+    /// are logged and counted so the remaining stages still produce output.
+    pub fn run(&self, options: &RunOptions) -> Result<(), GeneratorError> {
+        start_run();
+        match options.from_ir {
+            Some(ref ir_file_path) => {
+                self.load_ir(ir_file_path)
+                    .inspect_err(|_| self.record_error("load ir"))?;
+                info!("Loaded IR {}", ir_file_path.display());
+            }
+            None => {
+                self.generate_paths()
+                    .inspect_err(|_| self.record_error("paths"))?;
+                info!("Generation paths completed");
+            }
+        }
+
+        if let Some(ref ir_file_path) = options.emit_ir {
+            self.emit_ir(ir_file_path)
+                .inspect_err(|_| self.record_error("emit ir"))?;
+            info!("IR written to {}", ir_file_path.display());
+            return Ok(());
+        }
+
+        set_file_header(self.file_header());
+        let stages = self.config.stages;
+        if stages.clients {
+            self.run_stage("clients", || self.generate_clients());
+        }
+        if stages.objects {
+            self.run_stage("objects", || self.generate_objects());
+        }
+        if stages.client_files {
+            self.run_stage("client files", || self.populate_client_files());
+        }
+
+        let started = Instant::now();
+        match update_manifest(&self.output_dir, options.prune) {
+            Ok(pruned_files) => info!("{} stale files pruned", pruned_files.len()),
+            Err(err) => {
+                error!("Updating manifest failed: {}", err);
+                self.record_error("manifest");
+            }
+        }
+        self.record_stage("manifest", started.elapsed());
+        set_file_header(None);
+        Ok(())
+    }
+
+    // header of the generated files, with the fingerprints of the specs they come from
+    fn file_header(&self) -> Option<String> {
+        if !self.config.generated_headers {
+            return None;
         }
+        let specs = self
+            .specs
+            .iter()
+            .map(|spec_file_path| {
+                let hash = std::fs::read(spec_file_path)
+                    .map(|content| fingerprint(&content))
+                    .unwrap_or_else(|_| "unknown".to_owned());
+                let name = spec_file_path
+                    .file_name()
+                    .unwrap_or(spec_file_path.as_os_str())
+                    .to_string_lossy();
+                format!("{} at hash {}", name, hash)
+            })
+            .collect::<Vec<String>>();
+        Some(match specs.is_empty() {
+            true => format!(
+                "Generated by opage v{} — do not edit",
+                env!("CARGO_PKG_VERSION")
+            ),
+            false => format!(
+                "Generated by opage v{} from spec {} — do not edit",
+                env!("CARGO_PKG_VERSION"),
+                specs.join(", ")
+            ),
+        })
+    }
+
+    /// Runs the generation in memory and returns the generated files, ordered by path,
+    /// for a writer of `utils::artifact`
+    pub fn artifacts(
+        &self,
+        options: &RunOptions,
+    ) -> Result<std::vec::IntoIter<FileArtifact>, GeneratorError> {
+        capture_files();
+        let result = self.run(options);
+        let artifacts = take_artifacts();
+        result?;
+        Ok(artifacts.into_iter())
+    }
+
+    fn run_stage(&self, stage: &str, run: impl FnOnce() -> Result<(), GeneratorError>) {
+        match run() {
+            Ok(_) => info!("Generation {} completed", stage),
+            Err(err) => {
+                error!("Generation {} failed: {}", stage, err);
+                self.record_error(stage);
+            }
+        }
+    }
+
+    /// Summary of all stages run so far
+    pub fn statistics(&self) -> GenerationStatistics {
+        let mut statistics = self.statistics.lock().unwrap().clone();
+        (statistics.files_written, statistics.files_unchanged) = write_statistics();
+        statistics.degraded_schemas = self.diagnostics.degraded_schemas();
+        for diagnostic in self.diagnostics.all() {
+            *statistics
+                .diagnostics
+                .entry(diagnostic.severity)
+                .or_default() += 1;
+        }
+        statistics
+    }
+
+    /// Problems found in the specs, by severity
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Counts a failed stage, see `GenerationStatistics::has_failures`
+    pub fn record_error(&self, stage: &str) {
+        self.statistics.lock().unwrap().add_stage_error(stage);
+    }
+
+    fn record_stage(&self, stage: &str, duration: Duration) {
+        self.statistics
+            .lock()
+            .unwrap()
+            .add_stage_duration(stage, duration);
+    }
+
+    // times a rendering stage, time spent writing files is recorded separately
+    fn timed_render<T>(&self, render: impl FnOnce() -> T) -> T {
+        let write_duration_before = write_duration();
+        let started = Instant::now();
+        let result = render();
+        let write_time = write_duration().saturating_sub(write_duration_before);
+        self.record_stage("render", started.elapsed().saturating_sub(write_time));
+        self.record_stage("write", write_time);
+        result
     }
 
     pub fn generate_paths(&self) -> Result<u32, GeneratorError> {
         let mut generated_paths = 0;
         for spec_file_path in self.specs.iter() {
-            let spec = oas3::from_path(spec_file_path).expect("Failed to read spec");
+            let started = Instant::now();
+            let spec = read_spec(spec_file_path)?;
+            self.record_stage("parse", started.elapsed());
+            let config = self.config.for_spec(spec_file_path);
+
             // Components and database for type referencing
-            generate_components(&spec, &self.config, &self.object_database).unwrap();
+            let started = Instant::now();
+            let anonymous_operations = anonymous_operations(&spec);
+            let context = GenerationContext::new(&spec, &self.object_database, &config)
+                .with_diagnostics(&self.diagnostics, spec_file_path)
+                .with_anonymous_operations(&anonymous_operations);
+            let component_statistics = generate_components(&context)?;
+            generate_reusable_components(&context);
+            self.record_stage("components", started.elapsed());
+            self.statistics
+                .lock()
+                .unwrap()
+                .add_components(component_statistics);
+
             // Generate paths requests
-            generated_paths += self
-                .generate_inner_paths(&spec)
-                .expect("Failed to generated paths");
+            let started = Instant::now();
+            generated_paths += self.generate_inner_paths(&context)?;
+            self.record_stage("paths", started.elapsed());
         }
         Ok(generated_paths)
     }
 
-    pub fn generate_inner_paths(&self, spec: &Spec) -> Result<u32, GeneratorError> {
+    /// Writes the object and path databases to a JSON file
+    pub fn emit_ir(&self, ir_file_path: &Path) -> Result<(), GeneratorError> {
+        IntermediateRepresentation::from_databases(
+            &self.object_database,
+            &self.path_database,
+            &self.websocket_database,
+        )
+        .write(ir_file_path)
+    }
+
+    /// Fills the object and path databases from a JSON file written by `emit_ir`
+    pub fn load_ir(&self, ir_file_path: &Path) -> Result<(), GeneratorError> {
+        IntermediateRepresentation::read(ir_file_path)?.into_databases(
+            &self.object_database,
+            &self.path_database,
+            &self.websocket_database,
+        );
+        Ok(())
+    }
+
+    pub fn generate_inner_paths(&self, context: &GenerationContext) -> Result<u32, GeneratorError> {
         let mut generated_path_count = 0;
 
-        let paths = match spec.paths {
+        let paths = match context.spec.paths {
             Some(ref paths) => paths,
             None => return Ok(generated_path_count),
         };
@@ -91,10 +317,44 @@ impl Generator {
             }
 
             for operation in operations {
-                match self.generate_path_code(spec, operation.0, &name, operation.1) {
-                    Ok(_) => (),
+                let pointer = json_pointer(&[
+                    "paths",
+                    name.as_str(),
+                    operation.0.to_string().to_lowercase().as_str(),
+                ]);
+                let http_method = match operation_method(operation.0, operation.1) {
+                    Ok(http_method) => http_method,
                     Err(err) => {
-                        error!("{}", err);
+                        context.report(Diagnostic::error(&pointer, err));
+                        self.statistics.lock().unwrap().operations_failed += 1;
+                        continue;
+                    }
+                };
+                let method = http_method.to_string();
+                if self.config.ignore.method_ignored(&method) {
+                    info!("{} {} ignored", method, name);
+                    continue;
+                }
+                let operation_definition =
+                    merge_path_item_parameters(context.spec, &path_item.parameters, operation.1);
+                match self.generate_path_code(context, http_method, &name, &operation_definition) {
+                    Ok(_) => self.statistics.lock().unwrap().add_operation(&method),
+                    // the objects of the operation were already generated by another one
+                    Err(err @ GeneratorError::ObjectDatabaseDuplicateError(_)) => {
+                        let diagnostic = Diagnostic::warning(&pointer, err);
+                        context.report(match operation_definition.operation_id {
+                            Some(ref operation_id) => diagnostic.with_operation(operation_id),
+                            None => diagnostic,
+                        });
+                        self.statistics.lock().unwrap().operations_skipped += 1;
+                    }
+                    Err(err) => {
+                        let diagnostic = Diagnostic::error(&pointer, err);
+                        context.report(match operation_definition.operation_id {
+                            Some(ref operation_id) => diagnostic.with_operation(operation_id),
+                            None => diagnostic,
+                        });
+                        self.statistics.lock().unwrap().operations_failed += 1;
                     }
                 }
                 generated_path_count += 1;
@@ -106,13 +366,13 @@ impl Generator {
 
     fn generate_path_code(
         &self,
-        spec: &Spec,
+        context: &GenerationContext,
         method: Method,
         path: &str,
         operation: &Operation,
     ) -> Result<String, GeneratorError> {
         let operation_id = match operation.operation_id {
-            Some(ref operation_id) => &self.config.name_mapping.name_to_module_name(operation_id),
+            Some(ref operation_id) => &context.name_mapping.name_to_operation_name(operation_id),
             None => {
                 return Err(GeneratorError::MissingIdError(
                     path.to_string(),
@@ -134,16 +394,17 @@ impl Generator {
         };
 
         match generate_websocket {
+            // written into the websocket module, only compiled with the `websocket` feature
             true => match websocket_request::generate_operation(
-                spec,
-                &self.config.name_mapping,
+                context,
                 &path,
                 &operation,
-                &self.object_database,
                 &self.path_database,
-                &self.config,
             ) {
-                Ok(request_code) => request_code,
+                Ok(request_code) => {
+                    self.websocket_database
+                        .insert(rust::websocket_module_name(operation_id), request_code);
+                }
                 Err(err) => {
                     return Err(GeneratorError::CodeGenerationError(
                         "websocket".to_owned(),
@@ -152,16 +413,13 @@ impl Generator {
                 }
             },
             _ => match default_request::generate_operation(
-                spec,
-                &self.config.name_mapping,
+                context,
                 method,
                 &path,
                 &operation,
-                &self.object_database,
                 &self.path_database,
-                &self.config,
             ) {
-                Ok(request_code) => request_code,
+                Ok(_) => (),
                 Err(err) => return Err(err),
             },
         };
@@ -189,37 +447,96 @@ impl Generator {
     }
 
     pub fn generate_objects(&self) -> Result<(), GeneratorError> {
+        // captured unknown fields are a HashMap, no struct can be Copy
+        if self.config.derive_copy && self.config.unknown_fields != UnknownFields::Capture {
+            let copy_count = mark_copy_types(
+                &self.object_database,
+                self.config.copy_max_fields,
+                self.config.unknown_enum_variant,
+            );
+            info!("{} objects derive Copy", copy_count);
+        }
+
         // Write all registered objects to individual type definitions
-        match self.config.language {
+        self.timed_render(|| match self.config.language {
             Language::Rust => {
-                rust::write_object_database(&self.output_dir, &self.object_database, &self.config)
+                rust::write_object_database(&self.output_dir, &self.object_database, &self.config)?;
+                if !self.config.version_conversions.is_empty() {
+                    rust::write_conversions(&self.output_dir, &self.object_database, &self.config)?;
+                }
+                Ok(())
             }
             _ => Err(GeneratorError::UnsupportedLanguageError(
                 self.config.language.to_string(),
             )),
-        }
+        })
     }
 
     pub fn generate_clients(&self) -> Result<(), GeneratorError> {
-        match self.config.language {
+        self.timed_render(|| match self.config.language {
             Language::Rust => rust::generate_clients(
                 &self.output_dir,
                 &self.path_database,
+                &self.websocket_database,
                 &self.config,
                 &self.object_database,
             ),
             _ => Err(GeneratorError::UnsupportedLanguageError(
                 self.config.language.to_string(),
             )),
-        }
+        })
     }
 
     pub fn populate_client_files(&self) -> Result<(), GeneratorError> {
-        match self.config.language {
-            Language::Rust => rust::populate_client_files(&self.output_dir, &self.config),
+        self.timed_render(|| match self.config.language {
+            Language::Rust => rust::populate_client_files(
+                &self.output_dir,
+                &self.config,
+                &self.object_database,
+                !self.websocket_database.is_empty(),
+            ),
             _ => Err(GeneratorError::UnsupportedLanguageError(
                 self.config.language.to_string(),
             )),
-        }
+        })
     }
 }
+
+/// Method of an operation, `x-http-method` replaces the method it is declared under so
+/// nonstandard verbs can be described (e.g. `x-http-method: PURGE` on a `post` operation)
+fn operation_method(declared: Method, operation: &Operation) -> Result<Method, GeneratorError> {
+    match operation.extensions.get("http-method") {
+        None => Ok(declared),
+        Some(serde_json::Value::String(method)) => method.parse(),
+        Some(_) => Err(GeneratorError::InvalidValueError(
+            "x-http-method".to_owned(),
+        )),
+    }
+}
+
+/// Copy of `operation` with the parameters of its path item, operation parameters
+/// with the same name and location take precedence
+fn merge_path_item_parameters(
+    spec: &Spec,
+    path_item_parameters: &[ObjectOrReference<Parameter>],
+    operation: &Operation,
+) -> Operation {
+    let mut operation = operation.clone();
+    let operation_keys = operation
+        .parameters
+        .iter()
+        .filter_map(|parameter_ref| parameter_ref.resolve(spec).ok())
+        .map(|parameter| (parameter.name, parameter.location))
+        .collect::<Vec<_>>();
+    let inherited = path_item_parameters
+        .iter()
+        .filter(|parameter_ref| match parameter_ref.resolve(spec) {
+            Ok(parameter) => !operation_keys.contains(&(parameter.name, parameter.location)),
+            // unresolvable parameters fail later like the operation ones
+            Err(_) => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    operation.parameters.splice(0..0, inherited);
+    operation
+}
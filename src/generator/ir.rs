@@ -0,0 +1,95 @@
+use std::{collections::BTreeMap, fs::File, io::BufWriter, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generator::types::{
+        ObjectDatabase, ObjectDefinition, PathDatabase, PathDefinition, WebsocketDatabase,
+    },
+    GeneratorError,
+};
+
+/// Serializable snapshot of the analysis phase (spec -> databases).
+///
+/// Written with `--emit-ir` and read back with `--from-ir`, so the databases can
+/// be post-processed by external tooling before rendering.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IntermediateRepresentation {
+    pub objects: BTreeMap<String, Arc<ObjectDefinition>>,
+    pub paths: BTreeMap<String, Arc<PathDefinition>>,
+    /// Rendered websocket modules, they are not described by the path definitions
+    #[serde(default)]
+    pub websockets: BTreeMap<String, String>,
+}
+
+impl IntermediateRepresentation {
+    pub fn from_databases(
+        object_database: &ObjectDatabase,
+        path_database: &PathDatabase,
+        websocket_database: &WebsocketDatabase,
+    ) -> Self {
+        IntermediateRepresentation {
+            objects: object_database
+                .iter()
+                .map(|item| (item.key().clone(), item.value().clone()))
+                .collect(),
+            paths: path_database
+                .iter()
+                .map(|item| (item.key().clone(), item.value().clone()))
+                .collect(),
+            websockets: websocket_database
+                .iter()
+                .map(|item| (item.key().clone(), item.value().clone()))
+                .collect(),
+        }
+    }
+
+    pub fn into_databases(
+        self,
+        object_database: &ObjectDatabase,
+        path_database: &PathDatabase,
+        websocket_database: &WebsocketDatabase,
+    ) {
+        for (name, object_definition) in self.objects {
+            object_database.insert(name, object_definition);
+        }
+        for (name, path_definition) in self.paths {
+            path_database.insert(name, path_definition);
+        }
+        for (name, code) in self.websockets {
+            websocket_database.insert(name, code);
+        }
+    }
+
+    pub fn write(&self, ir_file_path: &Path) -> Result<(), GeneratorError> {
+        let file = File::create(ir_file_path).map_err(|err| {
+            GeneratorError::FileCreationError(
+                ir_file_path.to_string_lossy().to_string(),
+                err.to_string(),
+            )
+        })?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self).map_err(|err| {
+            GeneratorError::FileCreationError(
+                ir_file_path.to_string_lossy().to_string(),
+                err.to_string(),
+            )
+        })
+    }
+
+    pub fn read(ir_file_path: &Path) -> Result<Self, GeneratorError> {
+        let file = File::open(ir_file_path).map_err(|err| {
+            GeneratorError::ParseError(format!(
+                "Unable to open IR {} {}",
+                ir_file_path.to_string_lossy(),
+                err
+            ))
+        })?;
+        serde_json::from_reader(file).map_err(|err| {
+            GeneratorError::ParseError(format!(
+                "Unable to parse IR {} {}",
+                ir_file_path.to_string_lossy(),
+                err
+            ))
+        })
+    }
+}
@@ -1,5 +1,17 @@
 pub mod component;
+pub mod context;
+pub mod diagnostics;
+pub mod dialect;
 pub mod generator;
+pub mod ir;
 pub mod path;
+pub mod statistics;
 pub mod templates;
 pub mod types;
+
+// `types` holds the single model shared by the analysis and rendering stages,
+// the main types are re-exported so library users have one path to import from
+pub use types::{
+    EnumDefinition, ModuleInfo, ObjectDatabase, ObjectDefinition, PathDatabase, PathDefinition,
+    PrimitiveDefinition, PropertyDefinition, StructDefinition, TypeDefinition,
+};
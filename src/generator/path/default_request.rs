@@ -1,39 +1,37 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use convert_case::Casing;
-use oas3::{
-    spec::{Operation, ParameterIn, SchemaTypeSet},
-    Spec,
-};
+use oas3::spec::{Operation, ParameterIn, ParameterStyle, SchemaTypeSet};
 use tracing::trace;
 
 use crate::{
     generator::{
         component::{
-            object_definition::oas3_type_to_string, type_definition::get_type_from_schema,
+            object_definition::{get_components_base_path, oas3_type_to_string},
+            type_definition::get_type_from_schema,
         },
+        context::GenerationContext,
         path::utils::generate_request_body,
         types::{
-            Method, ModuleInfo, ObjectDatabase, ObjectDefinition, PathDatabase, PathDefinition,
-            PathParameters, PropertyDefinition, QueryParameters, RequestEntity, StructDefinition,
-            TransferMediaType,
+            AsyncOperation, Method, ModuleInfo, ObjectDefinition, PathDatabase, PathDefinition,
+            PathParameters, PathStyle, PropertyDefinition, QueryParameters, QueryStyle,
+            RequestEntity, StructDefinition, TransferMediaType, TypeDefinition, UrlTemplate,
         },
     },
-    utils::{config::Config, name_mapping::NameMapping},
+    utils::{config::ContentChecksum, name_mapping::NameMapping},
     GeneratorError,
 };
 
-use super::utils::{generate_request_body_entity, generate_responses, is_path_parameter};
+use super::utils::{
+    component_ref_key, generate_request_body_entity, generate_responses, reusable_object_name,
+};
 
 pub fn generate_operation(
-    spec: &Spec,
-    name_mapping: &NameMapping,
+    context: &GenerationContext,
     method: Method,
     path: &str,
     operation: &Operation,
-    object_database: &ObjectDatabase,
     path_database: &PathDatabase,
-    config: &Config,
 ) -> Result<String, GeneratorError> {
     trace!("Generating {:?} {}", method, path);
     let operation_definition_path: Vec<String> = vec![path.to_owned()];
@@ -45,7 +43,7 @@ pub fn generate_operation(
         });
 
     let function_name = match operation.operation_id {
-        Some(ref operation_id) => name_mapping.name_to_module_name(operation_id),
+        Some(ref operation_id) => context.name_mapping.name_to_operation_name(operation_id),
         None => {
             return Err(GeneratorError::MissingIdError(
                 "operation_id".to_string(),
@@ -55,21 +53,17 @@ pub fn generate_operation(
     };
 
     let response_entities = generate_responses(
-        spec,
-        object_database,
+        context,
         &operation_definition_path,
-        name_mapping,
-        &operation.responses(spec),
+        operation,
         &function_name,
-        config,
     )?;
 
     // Path parameters
     let path_parameters = generate_path_parameters(
-        spec,
+        context,
         &operation,
         &operation_definition_path,
-        name_mapping,
         &function_name,
         path,
     )?;
@@ -85,11 +79,12 @@ pub fn generate_operation(
         .len()
         > 0;
 
-    let response_enum_name = name_mapping.name_to_struct_name(
+    let response_enum_name = context.name_mapping.name_to_struct_name(
         &operation_definition_path,
         &format!(
             "{}ResponseType",
-            &name_mapping
+            &context
+                .name_mapping
                 .extract_struct_name(&function_name)
                 .to_case(convert_case::Case::Pascal)
         ),
@@ -106,13 +101,10 @@ pub fn generate_operation(
 
     // Query params
     let query_parameter_code = generate_query_parameter_code(
-        spec,
+        context,
         operation,
         &operation_definition_path,
-        name_mapping,
-        object_database,
         &function_name,
-        config,
     )?;
 
     // Request Body
@@ -120,13 +112,10 @@ pub fn generate_operation(
     let request_entity = match operation.request_body {
         Some(ref request_body) => {
             match generate_request_body_entity(
-                spec,
-                object_database,
+                context,
                 &operation_definition_path,
-                name_mapping,
                 request_body,
                 &function_name,
-                config,
             ) {
                 Ok(request_body) => Some(request_body),
                 Err(err) => {
@@ -139,16 +128,24 @@ pub fn generate_operation(
         }
         None => None,
     };
-    let request_body: Option<ObjectDefinition> = match operation.request_body {
+    // ndjson bodies are sent as a sequence of lines and binary bodies are streamed,
+    // no body struct is generated
+    let is_streaming_request = request_entity.as_ref().map_or(false, |request_entity| {
+        request_entity.content.values().any(|content| {
+            matches!(
+                content,
+                TransferMediaType::ApplicationNdjson(_) | TransferMediaType::Binary
+            )
+        })
+    });
+    let request_body: Option<Arc<ObjectDefinition>> = match operation.request_body {
+        Some(_) if is_streaming_request => None,
         Some(ref request_body) => {
             match generate_request_body(
-                spec,
-                object_database,
+                context,
                 &operation_definition_path,
-                name_mapping,
                 request_body,
                 &function_name,
-                config,
             ) {
                 Ok(request_body) => Some(request_body),
                 Err(err) => {
@@ -162,6 +159,37 @@ pub fn generate_operation(
         None => None,
     };
 
+    let idempotent = match operation.extensions.get("idempotent") {
+        Some(serde_json::Value::Bool(idempotent)) => *idempotent,
+        Some(_) => return Err(GeneratorError::InvalidValueError("x-idempotent".to_owned())),
+        None => method.is_idempotent(),
+    };
+
+    let content_checksum = match operation.extensions.get("content-checksum") {
+        Some(value) => Some(
+            serde_json::from_value::<ContentChecksum>(value.clone())
+                .map_err(|_| GeneratorError::InvalidValueError("x-content-checksum".to_owned()))?,
+        ),
+        None => operation
+            .operation_id
+            .as_ref()
+            .and_then(|operation_id| context.config.content_checksums.get(operation_id))
+            .copied(),
+    };
+
+    // extensions are stored without their `x-` prefix
+    let beta_extension = &context.config.beta_extension;
+    let beta = match operation
+        .extensions
+        .get(beta_extension.strip_prefix("x-").unwrap_or(beta_extension))
+    {
+        Some(serde_json::Value::Bool(beta)) => *beta,
+        Some(_) => return Err(GeneratorError::InvalidValueError(beta_extension.clone())),
+        None => false,
+    };
+
+    let async_operation = generate_async_operation(context, operation)?;
+
     trace!("Generating source code");
     // function
     let path_definition = PathDefinition {
@@ -175,12 +203,65 @@ pub fn generate_operation(
         query_parameters: query_parameter_code,
         description: description.to_owned(),
         request_body: request_body,
+        idempotent,
+        content_checksum,
+        tag: operation.tags.first().cloned(),
+        beta,
+        anonymous: operation
+            .operation_id
+            .as_deref()
+            .is_some_and(|operation_id| context.is_anonymous(operation_id)),
+        async_operation,
+        cached: method == Method::GET
+            && operation
+                .operation_id
+                .as_deref()
+                .is_some_and(|operation_id| context.config.response_cache.caches(operation_id)),
+        circuit_breaker: operation
+            .operation_id
+            .as_ref()
+            .and_then(|operation_id| context.config.circuit_breaker.operations.get(operation_id))
+            .copied(),
         ..Default::default() // description,
     };
-    path_database.insert(function_name, path_definition);
+    path_database.insert(function_name, Arc::new(path_definition));
     Ok(String::new())
 }
 
+// operations answering `202 Accepted` with a `Location` header are polled, the
+// `x-async-operation` extension configures how and marks operations not documenting the header
+fn generate_async_operation(
+    context: &GenerationContext,
+    operation: &Operation,
+) -> Result<Option<AsyncOperation>, GeneratorError> {
+    let accepted = operation
+        .responses(context.spec)
+        .into_iter()
+        .find(|(status_code, _)| status_code == "202");
+    let has_location = accepted.as_ref().is_some_and(|(_, response)| {
+        response
+            .headers
+            .keys()
+            .any(|header| header.eq_ignore_ascii_case("location"))
+    });
+    match operation.extensions.get("async-operation") {
+        Some(_) if accepted.is_none() => Err(GeneratorError::InvalidValueError(
+            "x-async-operation on an operation without 202 response".to_owned(),
+        )),
+        Some(value) => {
+            let mut async_operation = serde_json::from_value::<AsyncOperation>(value.clone())
+                .map_err(|_| GeneratorError::InvalidValueError("x-async-operation".to_owned()))?;
+            async_operation.status_function = async_operation
+                .status_operation
+                .as_deref()
+                .map(|operation_id| context.name_mapping.name_to_operation_name(operation_id));
+            Ok(Some(async_operation))
+        }
+        None if has_location => Ok(Some(AsyncOperation::default())),
+        None => Ok(None),
+    }
+}
+
 fn media_type_enum_name(
     definition_path: &Vec<String>,
     name_mapping: &NameMapping,
@@ -189,20 +270,22 @@ fn media_type_enum_name(
     let name = match transfer_media_type {
         TransferMediaType::ApplicationJson(_) => "Json",
         TransferMediaType::TextPlain => "Text",
+        TransferMediaType::TextCsv(_) => "Csv",
+        TransferMediaType::ApplicationNdjson(_) => "Ndjson",
+        TransferMediaType::Binary => "Binary",
     };
     name_mapping.name_to_struct_name(definition_path, name)
 }
 
 fn generate_path_parameters(
-    spec: &Spec,
+    context: &GenerationContext,
     operation: &Operation,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
     function_name: &str,
     path: &str,
 ) -> Result<PathParameters, GeneratorError> {
     trace!("Generating path parameters");
-    let path_parameters_struct_name = name_mapping.name_to_struct_name(
+    let path_parameters_struct_name = context.name_mapping.name_to_struct_name(
         &definition_path,
         &format!("{}PathParameters", function_name),
     );
@@ -210,44 +293,110 @@ fn generate_path_parameters(
     let mut path_parameters_definition_path = definition_path.clone();
     path_parameters_definition_path.push(path_parameters_struct_name.clone());
 
-    let path_parameters_ordered = path
-        .split("/")
-        .filter(|&path_component| is_path_parameter(&path_component))
-        .map(|path_component| path_component.replace("{", "").replace("}", ""))
-        .map(|path_component| {
-            let mut description = None;
-            let mut example: Option<serde_json::Value> = None;
-            let type_name = "String".to_owned();
-            operation.parameters.iter().find(|f| match f {
-                oas3::spec::ObjectOrReference::Ref { ref_path } => false,
-                oas3::spec::ObjectOrReference::Object(parameter) => {
-                    if parameter.location != ParameterIn::Path {
-                        return false;
-                    }
-                    if parameter.name != path_component {
-                        return false;
-                    }
-                    description = parameter.description.clone();
-                    example = parameter.example.clone();
-                    true
+    // reusable parameters (components.parameters) are resolved like the query parameters
+    let parameters = operation
+        .parameters
+        .iter()
+        .map(|parameter_ref| {
+            parameter_ref
+                .resolve(context.spec)
+                .map(|parameter| (parameter_ref, parameter))
+                .map_err(|err| {
+                    GeneratorError::ParameterError(
+                        "Failed to resolve parameter".to_owned(),
+                        err.to_string(),
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, GeneratorError>>()?;
+
+    let mut url_template = UrlTemplate::parse(path);
+    let mut path_parameters_ordered = vec![];
+    let path_components = url_template
+        .parameters()
+        .map(|(name, _)| name.to_owned())
+        .collect::<Vec<String>>();
+    for path_component in path_components {
+        let mut description = None;
+        let mut example: Option<serde_json::Value> = None;
+        let mut deprecated = false;
+        let mut type_definition = TypeDefinition {
+            name: "String".to_owned(),
+            module: None,
+            description: None,
+            example: None,
+        };
+        let parameter = parameters.iter().find(|(_, parameter)| {
+            parameter.location == ParameterIn::Path && parameter.name == path_component
+        });
+        if let Some((parameter_ref, parameter)) = parameter {
+            description = parameter.description.clone();
+            example = parameter.example.clone();
+            deprecated = parameter.deprecated.unwrap_or(false);
+            let style = match parameter.style {
+                Some(ParameterStyle::Label) => PathStyle::Label,
+                Some(ParameterStyle::Matrix) => PathStyle::Matrix,
+                _ => PathStyle::Simple,
+            };
+            url_template.set_parameter_style(
+                &path_component,
+                style,
+                parameter.explode.unwrap_or(false),
+            );
+            if context.config.typed_parameters {
+                if let Some(ref schema) = parameter.schema {
+                    let object_schema = schema.resolve(context.spec).map_err(|err| {
+                        GeneratorError::ParameterError(
+                            format!("Failed to resolve parameter {}", parameter.name),
+                            err.to_string(),
+                        )
+                    })?;
+                    // object types of components.parameters are shared by all operations
+                    let (parameter_definition_path, parameter_type_name) =
+                        match component_ref_key(parameter_ref, "parameters") {
+                            Some(key) => (
+                                get_components_base_path(),
+                                reusable_object_name(&key, "Parameter"),
+                            ),
+                            None => (
+                                path_parameters_definition_path.clone(),
+                                parameter.name.clone(),
+                            ),
+                        };
+                    type_definition = get_type_from_schema(
+                        context,
+                        parameter_definition_path,
+                        &object_schema,
+                        Some(&parameter_type_name),
+                    )?;
                 }
-            });
-
-            PropertyDefinition {
-                module: None,
-                name: name_mapping
-                    .name_to_property_name(&path_parameters_definition_path, &path_component),
-                real_name: path_component,
-                required: true,
-                type_name,
-                description,
-                example,
             }
-        })
-        .collect::<Vec<PropertyDefinition>>();
-    let package_name = name_mapping.extract_package_name(&path_parameters_struct_name);
-    let path_parameters_struct_name =
-        name_mapping.extract_struct_name(&path_parameters_struct_name);
+        }
+
+        url_template.set_parameter_type(&path_component, &type_definition.name);
+        path_parameters_ordered.push(PropertyDefinition {
+            module: type_definition.module,
+            name: context
+                .name_mapping
+                .name_to_property_name(&path_parameters_definition_path, &path_component),
+            real_name: path_component,
+            // a path parameter is always required, whatever `required` says
+            required: true,
+            type_name: type_definition.name,
+            description,
+            example,
+            deprecated,
+            allow_empty_value: false,
+            format: None,
+            external_docs: None,
+        });
+    }
+    let package_name = context
+        .name_mapping
+        .extract_package_name(&path_parameters_struct_name);
+    let path_parameters_struct_name = context
+        .name_mapping
+        .extract_struct_name(&path_parameters_struct_name);
 
     let path_struct_definition = StructDefinition {
         package: package_name,
@@ -260,55 +409,48 @@ fn generate_path_parameters(
                 (
                     path_component.name.clone(),
                     PropertyDefinition {
-                        module: None,
+                        module: path_component.module.clone(),
                         name: path_component.name.clone(),
                         real_name: path_component.real_name.clone(),
                         required: path_component.required,
                         type_name: path_component.type_name.clone(),
                         description: path_component.description.clone(),
                         example: path_component.example.clone(),
+                        deprecated: path_component.deprecated,
+                        allow_empty_value: false,
+                        format: None,
+                        external_docs: None,
                     },
                 )
             })
             .collect::<HashMap<String, PropertyDefinition>>(),
         description: None,
+        derive_copy: false,
+        example: None,
     };
 
-    let path_format_string = path
-        .split("/")
-        .map(|path_component| {
-            return match is_path_parameter(path_component) {
-                true => String::from("{}"),
-                _ => path_component.to_owned(),
-            };
-        })
-        .collect::<Vec<String>>()
-        .join("/");
-
     Ok(PathParameters {
-        parameters_struct_variable_name: name_mapping
+        parameters_struct_variable_name: context
+            .name_mapping
             .name_to_property_name(definition_path, "path_parameters"),
         parameters_struct: path_struct_definition,
-        path_format_string: path_format_string,
+        url_template,
     })
 }
 
 fn generate_query_parameter_code(
-    spec: &Spec,
+    context: &GenerationContext,
     operation: &Operation,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
-    object_database: &ObjectDatabase,
     function_name: &str,
-    config: &Config,
 ) -> Result<QueryParameters, GeneratorError> {
     trace!("Generating query params");
-    let mapping_name = name_mapping.name_to_struct_name(
+    let mapping_name = context.name_mapping.name_to_struct_name(
         &definition_path,
         &format!("{}QueryParameters", function_name),
     );
-    let package_name = name_mapping.extract_package_name(&mapping_name);
-    let mapping_structure_name = name_mapping.extract_struct_name(&mapping_name);
+    let package_name = context.name_mapping.extract_package_name(&mapping_name);
+    let mapping_structure_name = context.name_mapping.extract_struct_name(&mapping_name);
 
     let mut query_struct = StructDefinition {
         package: package_name,
@@ -317,16 +459,21 @@ fn generate_query_parameter_code(
         used_modules: vec![],
         local_objects: HashMap::new(),
         description: None,
+        derive_copy: false,
+        example: None,
     };
 
-    let query_struct_variable_name =
-        name_mapping.name_to_property_name(&definition_path, "query_parameters");
+    let query_struct_variable_name = context
+        .name_mapping
+        .name_to_property_name(&definition_path, "query_parameters");
 
     let mut query_parameters_definition_path = definition_path.clone();
     query_parameters_definition_path.push(query_struct.name.clone());
+    // real name -> style and explode, applied once the parameters are collected
+    let mut styles = vec![];
 
     for parameter_ref in &operation.parameters {
-        let parameter = match parameter_ref.resolve(spec) {
+        let parameter = match parameter_ref.resolve(context.spec) {
             Ok(parameter) => parameter,
             Err(err) => {
                 return Err(GeneratorError::ParameterError(
@@ -338,17 +485,36 @@ fn generate_query_parameter_code(
         if parameter.location != ParameterIn::Query {
             continue;
         }
+        let style = match parameter.style {
+            Some(ParameterStyle::SpaceDelimited) => QueryStyle::SpaceDelimited,
+            Some(ParameterStyle::PipeDelimited) => QueryStyle::PipeDelimited,
+            Some(ParameterStyle::DeepObject) => QueryStyle::DeepObject,
+            _ => QueryStyle::Form,
+        };
+        // only the form style explodes by default
+        let explode = parameter.explode.unwrap_or(style == QueryStyle::Form);
+        styles.push((parameter.name.clone(), style, explode));
+
+        // object types of components.parameters are shared by all operations
+        let (parameter_definition_path, parameter_type_name) =
+            match component_ref_key(parameter_ref, "parameters") {
+                Some(key) => (
+                    get_components_base_path(),
+                    reusable_object_name(&key, "Parameter"),
+                ),
+                None => (
+                    query_parameters_definition_path.clone(),
+                    parameter.name.clone(),
+                ),
+            };
 
         let parameter_type = match parameter.schema {
-            Some(schema) => match schema.resolve(spec) {
+            Some(schema) => match schema.resolve(context.spec) {
                 Ok(object_schema) => get_type_from_schema(
-                    spec,
-                    object_database,
-                    query_parameters_definition_path.clone(),
+                    context,
+                    parameter_definition_path,
                     &object_schema,
-                    Some(&parameter.name),
-                    name_mapping,
-                    config,
+                    Some(&parameter_type_name),
                 ),
                 Err(err) => {
                     return Err(GeneratorError::ParameterError(
@@ -367,10 +533,12 @@ fn generate_query_parameter_code(
 
         let _ = match parameter_type {
             Ok(parameter_type) => query_struct.properties.insert(
-                name_mapping
+                context
+                    .name_mapping
                     .name_to_property_name(&query_parameters_definition_path, &parameter.name),
                 PropertyDefinition {
-                    name: name_mapping
+                    name: context
+                        .name_mapping
                         .name_to_property_name(&query_parameters_definition_path, &parameter.name),
                     module: parameter_type.module,
                     real_name: parameter.name,
@@ -381,80 +549,21 @@ fn generate_query_parameter_code(
                     type_name: parameter_type.name,
                     description: parameter_type.description.clone(),
                     example: parameter_type.example.clone(),
+                    deprecated: parameter.deprecated.unwrap_or(false),
+                    allow_empty_value: parameter.allow_empty_value.unwrap_or(false),
+                    format: None,
+                    external_docs: None,
                 },
             ),
             Err(err) => return Err(err),
         };
     }
 
-    let mut unroll_query_parameters_code = String::new();
-    unroll_query_parameters_code += &format!(
-        "  let {} request_query_parameters: Vec<(&str, String)> = vec![{}];\n",
-        match query_struct
-            .properties
-            .iter()
-            .filter(|(_, property)| !property.required || property.type_name.starts_with("Vec<"))
-            .collect::<Vec<(&String, &PropertyDefinition)>>()
-            .len()
-        {
-            0 => "",
-            _ => "mut",
-        },
-        query_struct
-            .properties
-            .iter()
-            .filter(|(_, property)| property.required && !property.type_name.starts_with("Vec<"))
-            .map(|(_, property)| format!(
-                "(\"{}\",{}.{}.to_string())",
-                property.real_name, query_struct_variable_name, property.name
-            ))
-            .collect::<Vec<String>>()
-            .join(",")
-    );
-
-    query_struct
-        .properties
-        .values()
-        .filter(|&property| property.required && property.type_name.starts_with("Vec<"))
-        .for_each(|vector_property|
-    {
-        unroll_query_parameters_code += &format!(
-                "{}.{}.iter().for_each(|query_parameter_item| request_query_parameters.push((\"{}\", query_parameter_item.to_string())));\n",
-                &query_struct_variable_name,
-                name_mapping.name_to_property_name(&definition_path, &vector_property.name),
-                vector_property.real_name
-            );
-    });
-
-    for optional_property in query_struct
-        .properties
-        .values()
-        .filter(|&property| !property.required)
-        .collect::<Vec<&PropertyDefinition>>()
-    {
-        unroll_query_parameters_code += &format!(
-            "  if let Some(ref query_parameter) = {}.{} {{\n",
-            query_struct_variable_name, optional_property.name
-        );
-        if optional_property.type_name.starts_with("Vec<") {
-            unroll_query_parameters_code += &format!(
-                "  query_parameter.iter().for_each(|query_parameter_item| request_query_parameters.push((\"{}\", query_parameter_item.to_string())));\n",
-                optional_property.real_name
-            );
-        } else {
-            unroll_query_parameters_code += &format!(
-                "  request_query_parameters.push((\"{}\", query_parameter.to_string()));\n",
-                optional_property.real_name
-            );
-        }
-        unroll_query_parameters_code += "}\n"
+    let mut query_parameters = QueryParameters::new(query_struct, query_struct_variable_name);
+    for (real_name, style, explode) in styles {
+        query_parameters.set_style(&real_name, style, explode);
     }
-
-    Ok(QueryParameters {
-        query_struct_variable_name,
-        query_struct,
-        unroll_query_parameters_code,
-    })
+    Ok(query_parameters)
 }
 
 fn generate_multi_request_type_functions(
@@ -528,6 +637,14 @@ fn generate_multi_request_type_functions(
                 request_content_variable_name,
                 oas3_type_to_string(&oas3::spec::SchemaType::String)
             )),
+            TransferMediaType::TextCsv(_) | TransferMediaType::ApplicationNdjson(_) => {
+                trace!("Row formats are only supported for responses");
+                continue;
+            }
+            TransferMediaType::Binary => {
+                trace!("Binary bodies are only streamed by the builders");
+                continue;
+            }
         }
 
         let function_name = name_mapping.extract_function_name(&content_function_name);
@@ -556,23 +673,26 @@ fn generate_multi_request_type_functions(
                 Some(_) => {
                     format!(".json(&{})", request_content_variable_name)
                 }
-                None => ".json(&serde_json::json!({}))".to_owned(),
+                // empty body, nothing is attached
+                None => String::new(),
             },
             TransferMediaType::TextPlain => ".body(body)".to_owned(),
+            TransferMediaType::TextCsv(_)
+            | TransferMediaType::ApplicationNdjson(_)
+            | TransferMediaType::Binary => continue,
         };
 
         request_source_code += &format!(
             "  let request_builder = client.{}(format!(\"{{server}}{}\", {})){};\n",
             method.to_string().to_lowercase(),
-            path_parameters.path_format_string,
+            path_parameters.url_template.format_string(),
             path_parameters
-                .parameters_struct
-                .properties
-                .iter()
-                .map(|(_, parameter)| format!(
+                .url_template
+                .parameters()
+                .map(|(name, _)| format!(
                     "{}.{}",
                     path_parameters.parameters_struct_variable_name,
-                    name_mapping.name_to_property_name(&definition_path, &parameter.name)
+                    name_mapping.name_to_property_name(&definition_path, name)
                 ))
                 .collect::<Vec<String>>()
                 .join(","),
@@ -604,3 +724,54 @@ fn generate_multi_request_type_functions(
 
     Some(request_source_code)
 }
+
+#[cfg(test)]
+mod tests {
+    use oas3::Spec;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{generator::types::ObjectDatabase, utils::config::Config};
+
+    #[test]
+    fn test_integer_path_parameter() {
+        let spec = serde_json::from_value::<Spec>(json!({
+            "openapi": "3.0.3",
+            "info": {"title": "Pets", "version": "1.0.0"},
+            "paths": {
+                "/pets/{petId}": {
+                    "get": {
+                        "operationId": "getPet",
+                        "parameters": [{
+                            "name": "petId",
+                            "in": "path",
+                            "required": false,
+                            "schema": {"type": "integer", "format": "int64"}
+                        }],
+                        "responses": {}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+        let operation = spec.paths.as_ref().unwrap()["/pets/{petId}"]
+            .get
+            .clone()
+            .unwrap();
+        let config = Config::new();
+        let object_database = ObjectDatabase::new();
+        let context = GenerationContext::new(&spec, &object_database, &config);
+
+        let path_parameters = generate_path_parameters(
+            &context,
+            &operation,
+            &vec!["get_pet".to_owned()],
+            "GetPet",
+            "/pets/{petId}",
+        )
+        .unwrap();
+        let pet_id = &path_parameters.parameters_struct.properties["pet_id"];
+        assert_eq!(pet_id.type_name, "i64");
+        assert!(pet_id.required);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod default_request;
+pub mod security;
 pub mod utils;
 pub mod websocket_request;
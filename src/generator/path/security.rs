@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+
+use oas3::Spec;
+use serde_json::Value;
+
+/// Extension marking the operations callable without credentials, set while the spec is read
+pub const ANONYMOUS_EXTENSION: &str = "anonymous";
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Marks the operations documented with `security: []` or only empty requirements
+/// (`security: [{}]`) with the anonymous extension.
+///
+/// Operations without `security` inherit the root `security` of the spec.
+///
+/// The parsed spec does not distinguish `security: []` from a missing `security`,
+/// so the operations are marked in the document before it is parsed. Requirements
+/// mixing `{}` with schemes make the credentials optional, they are still sent.
+pub fn mark_anonymous_operations(document: &mut Value) {
+    let root_anonymous = document.get("security").is_some_and(is_anonymous);
+    let paths = match document.get_mut("paths").and_then(Value::as_object_mut) {
+        Some(paths) => paths,
+        None => return,
+    };
+    for path_item in paths.values_mut().filter_map(Value::as_object_mut) {
+        for method in METHODS {
+            let operation = match path_item.get_mut(method).and_then(Value::as_object_mut) {
+                Some(operation) => operation,
+                None => continue,
+            };
+            let anonymous = match operation.get("security") {
+                Some(security) => is_anonymous(security),
+                None => root_anonymous,
+            };
+            if anonymous {
+                operation.insert(format!("x-{}", ANONYMOUS_EXTENSION), Value::Bool(true));
+            }
+        }
+    }
+}
+
+// `security` without requirements or with empty requirements only
+fn is_anonymous(security: &Value) -> bool {
+    match security {
+        Value::Array(requirements) => requirements
+            .iter()
+            .all(|requirement| requirement.as_object().is_some_and(|r| r.is_empty())),
+        _ => false,
+    }
+}
+
+/// Operation ids of the operations marked by `mark_anonymous_operations`
+pub fn anonymous_operations(spec: &Spec) -> HashSet<String> {
+    let paths = match spec.paths {
+        Some(ref paths) => paths,
+        None => return HashSet::new(),
+    };
+    paths
+        .values()
+        .flat_map(|path_item| {
+            [
+                &path_item.get,
+                &path_item.put,
+                &path_item.post,
+                &path_item.delete,
+                &path_item.options,
+                &path_item.head,
+                &path_item.patch,
+                &path_item.trace,
+            ]
+        })
+        .flatten()
+        .filter(|operation| {
+            operation.extensions.get(ANONYMOUS_EXTENSION) == Some(&Value::Bool(true))
+        })
+        .filter_map(|operation| operation.operation_id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_mark_anonymous_operations() {
+        let mut document = json!({
+            "paths": {
+                "/pets": {
+                    "get": {"operationId": "listPets", "security": []},
+                    "post": {"operationId": "addPet", "security": [{}, {"apiKey": []}]},
+                    "put": {"operationId": "updatePet", "security": [{}]},
+                    "delete": {"operationId": "deletePet"}
+                }
+            }
+        });
+        mark_anonymous_operations(&mut document);
+        let anonymous = |method: &str| {
+            document
+                .pointer(&format!("/paths/~1pets/{}/x-anonymous", method))
+                .is_some()
+        };
+        assert!(anonymous("get"));
+        assert!(!anonymous("post"));
+        assert!(anonymous("put"));
+        assert!(!anonymous("delete"));
+    }
+
+    #[test]
+    fn test_root_security() {
+        let mut document = json!({
+            "security": [],
+            "paths": {
+                "/pets": {
+                    "get": {"operationId": "listPets"},
+                    "post": {"operationId": "addPet", "security": [{"apiKey": []}]}
+                }
+            }
+        });
+        mark_anonymous_operations(&mut document);
+        assert!(document.pointer("/paths/~1pets/get/x-anonymous").is_some());
+        assert!(document.pointer("/paths/~1pets/post/x-anonymous").is_none());
+    }
+}
@@ -1,43 +1,209 @@
-use std::collections::{BTreeMap, HashMap};
-
-use oas3::{
-    spec::{MediaType, ObjectOrReference, ObjectSchema, RequestBody, Response},
-    Spec,
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
 };
+
+use oas3::spec::{MediaType, ObjectOrReference, ObjectSchema, Operation, RequestBody, Response};
 use reqwest::StatusCode;
-use tracing::{error, trace};
+use tracing::{error, info, trace};
 
 use crate::{
     generator::{
         component::{
             object_definition::{
-                get_object_or_ref_struct_name, get_or_create_object, is_object_empty,
+                get_components_base_path, get_object_or_ref_struct_name, get_or_create_object,
+                is_object_empty,
             },
             type_definition::get_type_from_schema,
         },
+        context::GenerationContext,
+        diagnostics::{definition_pointer, json_pointer, Diagnostic},
         types::{
-            ContentTypeValue, ModuleInfo, ObjectDatabase, ObjectDefinition, RequestEntity,
+            ContentTypeValue, ModuleInfo, ObjectDefinition, PrimitiveDefinition, RequestEntity,
             ResponseEntities, ResponseEntity, StructDefinition, TransferMediaType, TypeDefinition,
+            DEFAULT_RESPONSE_KEY,
         },
     },
-    utils::{config::Config, name_mapping::NameMapping},
+    utils::name_mapping::convert_name,
     GeneratorError,
 };
 
-pub fn is_path_parameter(path_component: &str) -> bool {
-    path_component.starts_with("{") && path_component.ends_with("}")
+/// Key of `#/components/{section}/{key}` references
+pub fn component_ref_key<T>(object_or_ref: &ObjectOrReference<T>, section: &str) -> Option<String> {
+    match object_or_ref {
+        ObjectOrReference::Ref { ref_path } => ref_path
+            .strip_prefix(&format!("#/components/{}/", section))
+            .map(|key| key.to_owned()),
+        ObjectOrReference::Object(_) => None,
+    }
+}
+
+/// Name of the objects generated for the reusable component `key`, e.g. `NotFound` becomes
+/// `NotFoundResponse` for `components.responses`
+pub fn reusable_object_name(key: &str, suffix: &str) -> String {
+    let name = convert_name(key);
+    match name.ends_with(suffix) {
+        true => name,
+        false => format!("{}{}", name, suffix),
+    }
+}
+
+/// Generates the bodies of `components.requestBodies` and `components.responses` once, operations
+/// referencing them reuse the objects instead of generating their own
+pub fn generate_reusable_components(context: &GenerationContext) -> usize {
+    let components = match context.spec.components {
+        Some(ref components) => components,
+        None => return 0,
+    };
+    let definition_path = get_components_base_path();
+    let mut generated_count = 0;
+
+    for (key, request_body_ref) in &components.request_bodies {
+        match request_body_ref.resolve(context.spec) {
+            Ok(request_body) => {
+                generated_count += generated_content_types_from_content_map(
+                    context,
+                    &definition_path,
+                    &request_body.content,
+                    &reusable_object_name(key, "RequestBody"),
+                )
+                .len()
+            }
+            Err(err) => context.report(Diagnostic::error(
+                &json_pointer(&["components", "requestBodies", key.as_str()]),
+                format!("Unable to resolve request body: {}", err),
+            )),
+        }
+    }
+
+    for (key, response_ref) in &components.responses {
+        match response_ref.resolve(context.spec) {
+            Ok(response) => {
+                generated_count += generated_content_types_from_content_map(
+                    context,
+                    &definition_path,
+                    &response.content,
+                    &reusable_object_name(key, "Response"),
+                )
+                .len()
+            }
+            Err(err) => context.report(Diagnostic::error(
+                &json_pointer(&["components", "responses", key.as_str()]),
+                format!("Unable to resolve response: {}", err),
+            )),
+        }
+    }
+
+    // only object schemas need a type, primitives are used as they are
+    let parameter_schemas = components
+        .parameters
+        .iter()
+        .filter_map(
+            |(key, parameter_ref)| match parameter_ref.resolve(context.spec) {
+                Ok(parameter) => parameter
+                    .schema
+                    .map(|schema| (key, schema, "Parameter", "parameters")),
+                Err(err) => {
+                    context.report(Diagnostic::error(
+                        &json_pointer(&["components", "parameters", key.as_str()]),
+                        format!("Unable to resolve parameter: {}", err),
+                    ));
+                    None
+                }
+            },
+        )
+        .collect::<Vec<_>>();
+    // headers nothing references would only add unused types
+    let referenced_headers = referenced_headers(context);
+    let header_schemas = components
+        .headers
+        .iter()
+        .filter(|(key, _)| referenced_headers.contains(key.as_str()))
+        .filter_map(|(key, header_ref)| match header_ref.resolve(context.spec) {
+            Ok(header) => header
+                .schema
+                .map(|schema| (key, schema, "Header", "headers")),
+            Err(err) => {
+                context.report(Diagnostic::error(
+                    &json_pointer(&["components", "headers", key.as_str()]),
+                    format!("Unable to resolve header: {}", err),
+                ));
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    for (key, schema, suffix, section) in parameter_schemas.into_iter().chain(header_schemas) {
+        if let ObjectOrReference::Object(ref object_schema) = schema {
+            if !object_schema.properties.is_empty() {
+                match get_or_create_object(
+                    context,
+                    definition_path.clone(),
+                    &reusable_object_name(key, suffix),
+                    object_schema,
+                ) {
+                    Ok(_) => generated_count += 1,
+                    Err(err) => context.report(Diagnostic::error(
+                        &json_pointer(&["components", section, key.as_str()]),
+                        err,
+                    )),
+                }
+            }
+        }
+    }
+
+    info!(
+        "{} reusable bodies and parameters generated",
+        generated_count
+    );
+    generated_count
+}
+
+/// Keys of the `components.headers` referenced by the headers of the responses
+fn referenced_headers(context: &GenerationContext) -> HashSet<String> {
+    let component_responses = context
+        .spec
+        .components
+        .iter()
+        .flat_map(|components| components.responses.values())
+        .filter_map(|response_ref| response_ref.resolve(context.spec).ok());
+    let operation_responses = context
+        .spec
+        .paths
+        .iter()
+        .flat_map(|paths| paths.values())
+        .flat_map(|path_item| {
+            [
+                &path_item.get,
+                &path_item.put,
+                &path_item.post,
+                &path_item.delete,
+                &path_item.options,
+                &path_item.head,
+                &path_item.patch,
+                &path_item.trace,
+            ]
+        })
+        .flatten()
+        .flat_map(|operation| operation.responses(context.spec).into_values());
+    component_responses
+        .chain(operation_responses)
+        .flat_map(|response: Response| {
+            response
+                .headers
+                .values()
+                .filter_map(|header_ref| component_ref_key(header_ref, "headers"))
+                .collect::<Vec<String>>()
+        })
+        .collect()
 }
 
 fn parse_json_data(
-    spec: &Spec,
+    context: &GenerationContext,
     definition_path: Vec<String>,
-    name_mapping: &NameMapping,
     new_object_name: &str,
-    object_database: &ObjectDatabase,
     json_schema_object_or_ref: &ObjectOrReference<ObjectSchema>,
-    config: &Config,
 ) -> Result<Option<TypeDefinition>, GeneratorError> {
-    let is_json_object_empty = match json_schema_object_or_ref.resolve(spec) {
+    let is_json_object_empty = match json_schema_object_or_ref.resolve(context.spec) {
         Ok(schema_object) => is_object_empty(&schema_object),
         Err(err) => {
             return Err(GeneratorError::ResolveError(format!(
@@ -53,16 +219,15 @@ fn parse_json_data(
 
     let json_object_definition_opt = match json_schema_object_or_ref {
         ObjectOrReference::Ref { ref_path: _ } => match get_object_or_ref_struct_name(
-            spec,
+            context,
             &definition_path,
-            name_mapping,
             &json_schema_object_or_ref,
         ) {
             Ok((_, object_name, description, example)) => Some(TypeDefinition {
                 module: Some(ModuleInfo {
                     path: format!(
                         "crate::objects::{}",
-                        name_mapping.name_to_module_name(&object_name)
+                        context.name_mapping.name_to_module_name(&object_name)
                     ),
                     name: object_name.clone(),
                 }),
@@ -73,13 +238,10 @@ fn parse_json_data(
             Err(err) => return Err(err),
         },
         ObjectOrReference::Object(object_schema) => match get_type_from_schema(
-            spec,
-            object_database,
+            context,
             definition_path.clone(),
             &object_schema,
             Some(new_object_name),
-            name_mapping,
-            config,
         ) {
             Ok(type_definition) => Some(type_definition),
             Err(err) => return Err(err),
@@ -98,13 +260,10 @@ fn parse_json_data(
 }
 
 fn generate_json_content(
-    spec: &Spec,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
-    object_database: &ObjectDatabase,
     json_media_type: &MediaType,
     content_object_name: &str,
-    config: &Config,
 ) -> Result<TransferMediaType, GeneratorError> {
     let json_schema_object_or_ref = match json_media_type.schema {
         Some(ref schema) => schema,
@@ -116,13 +275,12 @@ fn generate_json_content(
     };
 
     let json_object = parse_json_data(
-        spec,
+        context,
         definition_path.clone(),
-        name_mapping,
-        &name_mapping.name_to_struct_name(&definition_path, content_object_name),
-        object_database,
+        &context
+            .name_mapping
+            .name_to_struct_name(&definition_path, content_object_name),
         json_schema_object_or_ref,
-        config,
     )?;
 
     let json_object_type_definition = match json_object {
@@ -141,27 +299,126 @@ fn generate_json_content(
     )))
 }
 
+// strips parameters like `; charset=utf-8`
+fn essence(content_type: &str) -> String {
+    content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// `application/json` and vendor types with a `+json` suffix (e.g. `application/vnd.api+json`)
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    essence == "application/json"
+        || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// `application/json-patch+json`, RFC 6902 patch documents
+pub fn is_json_patch_content_type(content_type: &str) -> bool {
+    essence(content_type) == "application/json-patch+json"
+}
+
+// patches are typed by the runtime, whatever the schema documents
+fn json_patch_type() -> TypeDefinition {
+    TypeDefinition {
+        name: "Vec<PatchOperation>".to_owned(),
+        module: Some(ModuleInfo::new("crate::json_patch", "PatchOperation")),
+        description: Some("JSON Patch (RFC 6902) operations".to_owned()),
+        example: None,
+    }
+}
+
+/// `application/problem+json`, RFC 7807 problem details
+pub fn is_problem_json_content_type(content_type: &str) -> bool {
+    essence(content_type) == "application/problem+json"
+}
+
+// problem details share the type of the runtime, members beyond the standard ones are extensions
+fn problem_type() -> TypeDefinition {
+    TypeDefinition {
+        name: "Problem".to_owned(),
+        module: Some(ModuleInfo::new("crate::client", "Problem")),
+        description: Some("Problem details (RFC 7807)".to_owned()),
+        example: None,
+    }
+}
+
+pub fn is_text_content_type(content_type: &str) -> bool {
+    essence(content_type) == "text/plain"
+}
+
+pub fn is_csv_content_type(content_type: &str) -> bool {
+    essence(content_type) == "text/csv"
+}
+
+pub fn is_ndjson_content_type(content_type: &str) -> bool {
+    matches!(
+        essence(content_type).as_str(),
+        "application/x-ndjson" | "application/ndjson" | "application/jsonl"
+    )
+}
+
+/// `application/octet-stream` and media types which are never text (images, audio, video)
+pub fn is_binary_content_type(content_type: &str) -> bool {
+    let essence = essence(content_type);
+    essence == "application/octet-stream"
+        || ["image/", "audio/", "video/"]
+            .iter()
+            .any(|prefix| essence.starts_with(prefix))
+}
+
+// row formats may document the whole document (an array) or a single row
+fn row_type(json_content: TransferMediaType) -> Option<TypeDefinition> {
+    let mut type_definition = json_content.type_definition()?.clone();
+    if let Some(item_type) = type_definition
+        .name
+        .strip_prefix("Vec<")
+        .and_then(|name| name.strip_suffix(">"))
+    {
+        type_definition.name = item_type.to_owned();
+    }
+    Some(type_definition)
+}
+
 fn generate_content_type(
-    spec: &Spec,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
-    object_database: &ObjectDatabase,
     content_type: &str,
     media_type: &MediaType,
     content_object_name: &str,
-    config: &Config,
 ) -> Result<TransferMediaType, GeneratorError> {
     match content_type {
-        "text/plain" => Ok(TransferMediaType::TextPlain),
-        "application/json" => generate_json_content(
-            spec,
+        content_type if is_text_content_type(content_type) => Ok(TransferMediaType::TextPlain),
+        content_type if is_binary_content_type(content_type) => Ok(TransferMediaType::Binary),
+        content_type if is_problem_json_content_type(content_type) => {
+            Ok(TransferMediaType::ApplicationJson(Some(problem_type())))
+        }
+        content_type if is_json_patch_content_type(content_type) => {
+            Ok(TransferMediaType::ApplicationJson(Some(json_patch_type())))
+        }
+        content_type if is_json_content_type(content_type) => generate_json_content(
+            context,
             definition_path,
-            name_mapping,
-            object_database,
             media_type,
             &format!("{}Json", content_object_name),
-            config,
         ),
+        content_type if is_csv_content_type(content_type) => generate_json_content(
+            context,
+            definition_path,
+            media_type,
+            &format!("{}Row", content_object_name),
+        )
+        .map(|json_content| TransferMediaType::TextCsv(row_type(json_content))),
+        content_type if is_ndjson_content_type(content_type) => generate_json_content(
+            context,
+            definition_path,
+            media_type,
+            &format!("{}Row", content_object_name),
+        )
+        .map(|json_content| TransferMediaType::ApplicationNdjson(row_type(json_content))),
         _ => Err(GeneratorError::UnsupportedError(format!(
             "Content-Type {}",
             content_type
@@ -170,35 +427,35 @@ fn generate_content_type(
 }
 
 fn generated_content_types_from_content_map(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
     content: &BTreeMap<String, MediaType>,
     content_object_name: &str,
-    config: &Config,
 ) -> HashMap<ContentTypeValue, TransferMediaType> {
     let mut content_map = HashMap::new();
 
     for (content_type, media_type) in content {
         match generate_content_type(
-            spec,
+            context,
             definition_path,
-            name_mapping,
-            object_database,
             content_type,
             media_type,
             content_object_name,
-            config,
         ) {
             Ok(transfer_media_type) => {
                 if content_map.contains_key(content_type) {
-                    error!("Content-Type {} is already in content map", content_type);
+                    context.report(Diagnostic::warning(
+                        &definition_pointer(definition_path, content_object_name),
+                        format!("Content-Type {} is already in content map", content_type),
+                    ));
                     continue;
                 }
                 content_map.insert(content_type.clone(), transfer_media_type);
             }
-            Err(err) => error!("{} failed: {}", content_type, err),
+            Err(err) => context.report(Diagnostic::error(
+                &definition_pointer(definition_path, content_object_name),
+                format!("{} failed: {}", content_type, err),
+            )),
         }
     }
 
@@ -206,15 +463,22 @@ fn generated_content_types_from_content_map(
 }
 
 pub fn generate_request_body(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
     request_body: &ObjectOrReference<RequestBody>,
     function_name: &str,
-    config: &Config,
-) -> Result<ObjectDefinition, GeneratorError> {
-    let request = match request_body.resolve(spec) {
+) -> Result<Arc<ObjectDefinition>, GeneratorError> {
+    // request bodies of components.requestBodies are shared by all operations
+    let components_base_path = get_components_base_path();
+    let (definition_path, function_name) = match component_ref_key(request_body, "requestBodies") {
+        Some(key) => (
+            &components_base_path,
+            reusable_object_name(&key, "RequestBody"),
+        ),
+        None => (definition_path, function_name.to_owned()),
+    };
+    let function_name = function_name.as_str();
+    let request = match request_body.resolve(context.spec) {
         Ok(request) => request,
         Err(err) => {
             return Err(GeneratorError::ResolveError(format!(
@@ -223,19 +487,28 @@ pub fn generate_request_body(
             )))
         }
     };
+    if request
+        .content
+        .keys()
+        .next()
+        .is_some_and(|content_type| is_json_patch_content_type(content_type))
+    {
+        return Ok(get_or_create_json_patch_body(
+            context,
+            definition_path,
+            function_name,
+        ));
+    }
     for (_, media_type) in &request.content {
         // we skipping content type for now
         match media_type.schema {
-            Some(ref schema) => match schema.resolve(spec) {
+            Some(ref schema) => match schema.resolve(context.spec) {
                 Ok(schema) => {
                     return get_or_create_object(
-                        spec,
-                        object_database,
+                        context,
                         definition_path.clone(),
                         function_name,
                         &schema,
-                        name_mapping,
-                        config,
                     )
                 }
                 Err(err) => {
@@ -261,16 +534,35 @@ pub fn generate_request_body(
     )))
 }
 
+// `Vec<PatchOperation>` alias of a patch request body
+fn get_or_create_json_patch_body(
+    context: &GenerationContext,
+    definition_path: &Vec<String>,
+    function_name: &str,
+) -> Arc<ObjectDefinition> {
+    let struct_name = context
+        .name_mapping
+        .name_to_struct_name(definition_path, function_name);
+    if let Some(object_in_database) = context.object_database.get(&struct_name) {
+        return object_in_database.clone();
+    }
+    let primitive_type = json_patch_type();
+    let body = Arc::new(ObjectDefinition::Primitive(PrimitiveDefinition {
+        name: context.name_mapping.extract_struct_name(&struct_name),
+        description: primitive_type.description.clone(),
+        primitive_type,
+    }));
+    context.object_database.insert(struct_name, body.clone());
+    body
+}
+
 pub fn generate_request_body_entity(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
     request_body: &ObjectOrReference<RequestBody>,
     function_name: &str,
-    config: &Config,
 ) -> Result<RequestEntity, GeneratorError> {
-    let request = match request_body.resolve(spec) {
+    let request = match request_body.resolve(context.spec) {
         Ok(request) => request,
         Err(err) => {
             return Err(GeneratorError::ResolveError(format!(
@@ -279,61 +571,83 @@ pub fn generate_request_body_entity(
             )))
         }
     };
+    let components_base_path = get_components_base_path();
+    let (definition_path, content_object_name) =
+        match component_ref_key(request_body, "requestBodies") {
+            Some(key) => (
+                &components_base_path,
+                reusable_object_name(&key, "RequestBody"),
+            ),
+            None => (definition_path, format!("{}RequestBody", function_name)),
+        };
 
     Ok(RequestEntity {
         content: generated_content_types_from_content_map(
-            spec,
-            object_database,
+            context,
             definition_path,
-            name_mapping,
             &request.content,
-            &format!("{}RequestBody", function_name),
-            config,
+            &content_object_name,
         ),
     })
 }
 
 pub fn generate_responses(
-    spec: &Spec,
-    object_database: &ObjectDatabase,
+    context: &GenerationContext,
     definition_path: &Vec<String>,
-    name_mapping: &NameMapping,
-    responses: &BTreeMap<String, Response>,
+    operation: &Operation,
     function_name: &str,
-    config: &Config,
 ) -> Result<ResponseEntities, GeneratorError> {
     let mut response_entities = ResponseEntities::new();
-    for (response_key, response) in responses {
+    let response_refs = operation.responses.clone().unwrap_or_default();
+    for (response_key, response) in operation.responses(context.spec) {
         trace!("Generate response {}", response_key);
-        if response_key == "default" {
-            continue;
-        }
 
-        let canonical_status_code = match StatusCode::from_bytes(response_key.as_bytes()) {
-            Ok(status_code) => match name_mapping.status_code_to_canonical_name(status_code) {
-                Ok(canonical_status_code) => canonical_status_code,
-                Err(err) => return Err(err),
-            },
-            Err(err) => {
-                return Err(GeneratorError::StatusCodeError(
-                    response_key.to_string(),
-                    err.to_string(),
-                ))
+        // catch-all for undocumented status codes, mostly used for errors
+        let canonical_status_code = if response_key == DEFAULT_RESPONSE_KEY {
+            "Default".to_owned()
+        } else {
+            match StatusCode::from_bytes(response_key.as_bytes()) {
+                Ok(status_code) => match context
+                    .name_mapping
+                    .status_code_to_canonical_name(status_code)
+                {
+                    Ok(canonical_status_code) => canonical_status_code,
+                    Err(err) => return Err(err),
+                },
+                Err(err) => {
+                    return Err(GeneratorError::StatusCodeError(
+                        response_key.to_string(),
+                        err.to_string(),
+                    ))
+                }
             }
         };
 
+        // responses of components.responses are shared by all operations
+        let components_base_path = get_components_base_path();
+        let (content_definition_path, content_object_name) = match response_refs
+            .get(&response_key)
+            .and_then(|response_ref| component_ref_key(response_ref, "responses"))
+        {
+            Some(key) => (
+                &components_base_path,
+                reusable_object_name(&key, "Response"),
+            ),
+            None => (
+                definition_path,
+                format!("{}{}", &function_name, &canonical_status_code),
+            ),
+        };
+
         response_entities.insert(
             response_key.clone(),
             ResponseEntity {
-                canonical_status_code: canonical_status_code.to_owned(),
+                canonical_status_code: canonical_status_code.clone(),
                 content: generated_content_types_from_content_map(
-                    spec,
-                    object_database,
-                    definition_path,
-                    name_mapping,
+                    context,
+                    content_definition_path,
                     &response.content,
-                    &format!("{}{}", &function_name, &canonical_status_code),
-                    config,
+                    &content_object_name,
                 ),
             },
         );
@@ -1,73 +1,32 @@
-use super::utils::{
-    generate_request_body, generate_request_body_entity, generate_responses, is_path_parameter,
-};
+use super::utils::{generate_request_body, generate_request_body_entity, generate_responses};
 use crate::{
     generator::component::{
         object_definition::oas3_type_to_string, type_definition::get_type_from_schema,
     },
+    generator::context::GenerationContext,
+    generator::templates::imports::ImportCollector,
+    generator::templates::rust::{RustWebsocketFunctionTemplate, RustWebsocketStreamTemplate},
     generator::types::{
-        ModuleInfo, ObjectDatabase, PathDatabase, PropertyDefinition, StructDefinition,
-        TransferMediaType, TypeDefinition,
+        ModuleInfo, PathDatabase, PropertyDefinition, QueryParameters, StructDefinition,
+        TransferMediaType, TypeDefinition, UrlTemplate,
     },
-    utils::name_mapping::NameMapping,
     GeneratorError,
 };
-use oas3::{
-    spec::{FromRef, ObjectOrReference, ObjectSchema, Operation, ParameterIn},
-    Spec,
-};
+use askama::Template;
+use oas3::spec::{FromRef, ObjectOrReference, ObjectSchema, Operation, ParameterIn};
 use std::collections::HashMap;
 use tracing::error;
 
-fn read_websocket_stream_to_string(struct_name: &str, response_type_name: &str) -> String {
-    return format!(
-        "pub struct {struct_name} {{
-    socket: WebSocket<MaybeTlsStream<TcpStream>>,
-    }}
-
-impl {struct_name} {{
-    pub fn from(socket: WebSocket<MaybeTlsStream<TcpStream>>) -> Self {{
-        {struct_name} {{ socket: socket }}
-    }}
-
-    pub fn close(&mut self, code: Option<CloseFrame>) -> Result<(), Error> {{
-        self.socket.close(code)
-    }}
-
-    pub fn read(&mut self) -> Result<{response_type_name}, String> {{
-        let response = match self.socket.read() {{
-            Ok(response) => response,
-            Err(err) => return Err(err.to_string()),
-        }};
-
-        let response_text = match response.into_text() {{
-            Ok(response) => response,
-            Err(err) => return Err(err.to_string()),
-        }};
-
-        match serde_json::from_str::<{response_type_name}>(&response_text) {{
-            Ok(response_json_object) => Ok(response_json_object),
-            Err(err) => Err(err.to_string()),
-        }}
-    }}
-}}
-"
-    );
-}
-
 pub fn generate_operation(
-    spec: &Spec,
-    name_mapping: &NameMapping,
+    context: &GenerationContext,
     path: &str,
     operation: &Operation,
-    object_database: &ObjectDatabase,
     path_database: &PathDatabase,
-    config: &crate::utils::config::Config,
 ) -> Result<String, GeneratorError> {
     let operation_definition_path: Vec<String> = vec![path.to_owned()];
 
     let function_name = match operation.operation_id {
-        Some(ref operation_id) => name_mapping.name_to_module_name(operation_id),
+        Some(ref operation_id) => context.name_mapping.name_to_operation_name(operation_id),
         None => {
             return Err(GeneratorError::ParseError(
                 "No operation_id found".to_owned(),
@@ -76,13 +35,10 @@ pub fn generate_operation(
     };
 
     let response_entities = generate_responses(
-        spec,
-        object_database,
+        context,
         &operation_definition_path,
-        name_mapping,
-        &operation.responses(spec),
+        operation,
         &function_name,
-        config,
     )?;
 
     let socket_transferred_media_type = match response_entities.get("200") {
@@ -124,33 +80,53 @@ pub fn generate_operation(
             description: None,
             example: None,
         },
+        TransferMediaType::TextCsv(_) | TransferMediaType::ApplicationNdjson(_) => {
+            return Err(GeneratorError::UnsupportedError(
+                "Websocket with row formatted response".to_owned(),
+            ))
+        }
+        TransferMediaType::Binary => {
+            return Err(GeneratorError::UnsupportedError(
+                "Websocket with binary response".to_owned(),
+            ))
+        }
     };
 
     let path_parameters_struct_name = format!(
         "{}PathParameters",
-        name_mapping.name_to_struct_name(&operation_definition_path, &function_name)
+        context
+            .name_mapping
+            .name_to_struct_name(&operation_definition_path, &function_name)
     );
     let mut path_parameters_definition_path = operation_definition_path.clone();
     path_parameters_definition_path.push(path_parameters_struct_name.clone());
 
-    let path_parameters_ordered = path
-        .split("/")
-        .filter(|&path_component| is_path_parameter(&path_component))
-        .map(|path_component| path_component.replace("{", "").replace("}", ""))
+    let url_template = UrlTemplate::parse(path);
+    let path_parameters_ordered = url_template
+        .parameters()
+        .map(|(name, _)| name.to_owned())
         .map(|path_component| PropertyDefinition {
             module: None,
-            name: name_mapping
+            name: context
+                .name_mapping
                 .name_to_property_name(&path_parameters_definition_path, &path_component),
             real_name: path_component,
             required: true,
             type_name: "&str".to_owned(),
             description: None,
             example: None,
+            deprecated: false,
+            allow_empty_value: false,
+            format: None,
+            external_docs: None,
         })
         .collect::<Vec<PropertyDefinition>>();
-    let package_name = name_mapping.extract_package_name(&path_parameters_struct_name);
-    let path_parameters_struct_name =
-        name_mapping.extract_struct_name(&path_parameters_struct_name);
+    let package_name = context
+        .name_mapping
+        .extract_package_name(&path_parameters_struct_name);
+    let path_parameters_struct_name = context
+        .name_mapping
+        .extract_struct_name(&path_parameters_struct_name);
 
     let path_struct_definition = StructDefinition {
         package: package_name,
@@ -169,24 +145,21 @@ pub fn generate_operation(
                         type_name: "String".to_owned(),
                         description: path_component.description.clone(),
                         example: path_component.example.clone(),
+                        deprecated: false,
+                        allow_empty_value: false,
+                        format: None,
+                        external_docs: None,
                     },
                 )
             })
             .collect::<HashMap<String, PropertyDefinition>>(),
         local_objects: HashMap::new(),
         description: operation.description.clone(),
+        derive_copy: false,
+        example: None,
     };
 
-    let path_format_string = path
-        .split("/")
-        .map(|path_component| {
-            return match is_path_parameter(path_component) {
-                true => String::from("{}"),
-                _ => path_component.to_owned(),
-            };
-        })
-        .collect::<Vec<String>>()
-        .join("/");
+    let path_format_string = url_template.format_string();
 
     let mut request_source_code = String::new();
 
@@ -195,48 +168,31 @@ pub fn generate_operation(
     if !path_struct_definition.properties.is_empty() {
         function_parameters.push(format!(
             "{}: &{}",
-            name_mapping
+            context
+                .name_mapping
                 .name_to_property_name(&operation_definition_path, &path_struct_definition.name),
             path_struct_definition.name
         ));
     }
 
-    let mut module_imports = vec![
-        ModuleInfo {
-            name: "TcpStream".to_owned(),
-            path: "std::net".to_owned(),
-        },
-        ModuleInfo {
-            name: "connect".to_owned(),
-            path: "tungstenite".to_owned(),
-        },
-        ModuleInfo {
-            name: "Error".to_owned(),
-            path: "tungstenite".to_owned(),
-        },
-        ModuleInfo {
-            name: "WebSocket".to_owned(),
-            path: "tungstenite".to_owned(),
-        },
-        ModuleInfo {
-            name: "CloseFrame".to_owned(),
-            path: "tungstenite::protocol".to_owned(),
-        },
-        ModuleInfo {
-            name: "MaybeTlsStream".to_owned(),
-            path: "tungstenite::stream".to_owned(),
-        },
-    ];
+    // the socket types are written with their full path, they differ between native and wasm builds
+    let mut module_imports: Vec<ModuleInfo> = vec![];
 
     if let Some(ref socket_transfer_type_module) = socket_transfer_type_definition.module {
         module_imports.push(socket_transfer_type_module.clone());
     }
     let query_parameter_name = format!(
         "{}QueryParameters",
-        name_mapping.name_to_struct_name(&operation_definition_path, &function_name)
+        context
+            .name_mapping
+            .name_to_struct_name(&operation_definition_path, &function_name)
     );
-    let package_name = name_mapping.extract_package_name(&query_parameter_name);
-    let query_parameter_name = name_mapping.extract_struct_name(&query_parameter_name);
+    let package_name = context
+        .name_mapping
+        .extract_package_name(&query_parameter_name);
+    let query_parameter_name = context
+        .name_mapping
+        .extract_struct_name(&query_parameter_name);
 
     // Query params
     let mut query_struct = StructDefinition {
@@ -246,12 +202,14 @@ pub fn generate_operation(
         used_modules: vec![],
         local_objects: HashMap::new(),
         description: operation.description.clone(),
+        derive_copy: false,
+        example: None,
     };
     let mut query_operation_definition_path = operation_definition_path.clone();
     query_operation_definition_path.push(query_struct.name.clone());
 
     for parameter_ref in &operation.parameters {
-        let parameter = match parameter_ref.resolve(spec) {
+        let parameter = match parameter_ref.resolve(context.spec) {
             Ok(parameter) => parameter,
             Err(err) => {
                 return Err(GeneratorError::ResolveError(format!(
@@ -267,24 +225,18 @@ pub fn generate_operation(
         let parameter_type = match parameter.schema {
             Some(schema) => match schema {
                 ObjectOrReference::Object(object_schema) => get_type_from_schema(
-                    spec,
-                    object_database,
+                    context,
                     query_operation_definition_path.clone(),
                     &object_schema,
                     Some(&parameter.name),
-                    name_mapping,
-                    config,
                 ),
                 ObjectOrReference::Ref { ref_path } => {
-                    match ObjectSchema::from_ref(spec, &ref_path) {
+                    match ObjectSchema::from_ref(context.spec, &ref_path) {
                         Ok(object_schema) => get_type_from_schema(
-                            spec,
-                            object_database,
+                            context,
                             vec![],
                             &object_schema,
                             Some(&parameter.name),
-                            name_mapping,
-                            config,
                         ),
                         Err(err) => {
                             return Err(GeneratorError::ResolveError(format!(
@@ -306,10 +258,12 @@ pub fn generate_operation(
 
         let _ = match parameter_type {
             Ok(parameter_type) => query_struct.properties.insert(
-                name_mapping
+                context
+                    .name_mapping
                     .name_to_property_name(&query_operation_definition_path, &parameter.name),
                 PropertyDefinition {
-                    name: name_mapping
+                    name: context
+                        .name_mapping
                         .name_to_property_name(&query_operation_definition_path, &parameter.name),
                     module: parameter_type.module,
                     real_name: parameter.name,
@@ -320,6 +274,10 @@ pub fn generate_operation(
                     type_name: parameter_type.name,
                     description: parameter_type.description.clone(),
                     example: parameter_type.example.clone(),
+                    deprecated: parameter.deprecated.unwrap_or(false),
+                    allow_empty_value: parameter.allow_empty_value.unwrap_or(false),
+                    format: None,
+                    external_docs: None,
                 },
             ),
             Err(err) => return Err(err),
@@ -330,10 +288,12 @@ pub fn generate_operation(
     if query_struct.properties.len() > 0 {
         function_parameters.push(format!(
             "{}: &{}",
-            name_mapping.name_to_property_name(&operation_definition_path, &query_struct.name),
+            context
+                .name_mapping
+                .name_to_property_name(&operation_definition_path, &query_struct.name),
             query_struct.name
         ));
-        query_struct_source_code += &query_struct.to_string(false, config)?;
+        query_struct_source_code += &query_struct.to_string(false, context.config)?;
         query_struct_source_code += "\n\n";
     }
 
@@ -341,13 +301,10 @@ pub fn generate_operation(
     let request_body = match operation.request_body {
         Some(ref request_body) => {
             match generate_request_body_entity(
-                spec,
-                object_database,
+                context,
                 &operation_definition_path,
-                name_mapping,
                 request_body,
                 &function_name,
-                config,
             ) {
                 Ok(request_body) => Some(request_body),
                 Err(err) => {
@@ -377,7 +334,7 @@ pub fn generate_operation(
                         }
                         function_parameters.push(format!(
                             "{}: {}",
-                            name_mapping.name_to_property_name(
+                            context.name_mapping.name_to_property_name(
                                 &operation_definition_path,
                                 &type_definition.name
                             ),
@@ -390,6 +347,12 @@ pub fn generate_operation(
                     "request_string: &{}",
                     oas3_type_to_string(&oas3::spec::SchemaType::String)
                 )),
+                TransferMediaType::TextCsv(_) | TransferMediaType::ApplicationNdjson(_) => {
+                    error!("Row formatted websocket requests are not supported")
+                }
+                TransferMediaType::Binary => {
+                    error!("Binary websocket requests are not supported")
+                }
             }
             break;
         }
@@ -397,141 +360,68 @@ pub fn generate_operation(
 
     let socket_stream_struct_name = format!(
         "{}Stream",
-        name_mapping.name_to_struct_name(&operation_definition_path, &function_name)
+        context
+            .name_mapping
+            .name_to_struct_name(&operation_definition_path, &function_name)
     );
 
-    request_source_code += &module_imports
-        .iter()
-        .map(|m| m.to_use())
-        .collect::<Vec<String>>()
-        .join("\n");
-    request_source_code += "\n\n";
-    request_source_code += &read_websocket_stream_to_string(
-        &socket_stream_struct_name,
-        &socket_transfer_type_definition.name,
-    );
+    let mut imports = ImportCollector::new();
+    for module in module_imports.iter() {
+        imports.add_module(module);
+    }
+    request_source_code += &imports.render();
+    request_source_code += "\n";
+    request_source_code += &RustWebsocketStreamTemplate {
+        name: &socket_stream_struct_name,
+        response_type: &socket_transfer_type_definition.name,
+    }
+    .render()
+    .unwrap();
     request_source_code += "\n";
     if !path_struct_definition.properties.is_empty() {
-        request_source_code += &path_struct_definition.to_string(false, config)?;
+        request_source_code += &path_struct_definition.to_string(false, context.config)?;
         request_source_code += "\n";
     }
 
     request_source_code += &query_struct_source_code;
 
-    // Function signature
-    request_source_code += &format!(
-        "pub async fn {}(host: &str, {}) -> Result<{}, tungstenite::Error> {{\n",
-        name_mapping.extract_function_name(&function_name),
-        function_parameters.join(", "),
-        socket_stream_struct_name,
-    );
-
-    request_source_code += &format!(
-        "let {} query_parameters: Vec<(&str, String)> = vec![{}];\n",
-        match query_struct
-            .properties
-            .iter()
-            .filter(|(_, property)| !property.required || property.type_name.starts_with("Vec<"))
-            .collect::<Vec<(&String, &PropertyDefinition)>>()
-            .len()
-        {
-            0 => "",
-            _ => "mut",
-        },
-        query_struct
-            .properties
-            .iter()
-            .filter(|(_, property)| property.required && !property.type_name.starts_with("Vec<"))
-            .map(|(_, property)| format!(
-                "(\"{}\",{}.{}.to_string())",
-                property.real_name,
-                name_mapping.name_to_property_name(&operation_definition_path, &query_struct.name),
-                property.name
-            ))
-            .collect::<Vec<String>>()
-            .join(",")
-    );
-
-    query_struct
-        .properties
-        .values()
-        .filter(|&property| property.required && property.type_name.starts_with("Vec<"))
-        .for_each(|vector_property|
-    {
-        request_source_code += &format!(
-                "{}.{}.iter().for_each(|query_parameter_item| query_parameters.push((\"{}\", query_parameter_item.to_string())));\n",
-                name_mapping.name_to_property_name(&operation_definition_path, &query_struct.name),
-                name_mapping.name_to_property_name(&operation_definition_path, &vector_property.name),
-                vector_property.real_name
-            );
-    });
-
-    for optional_property in query_struct
-        .properties
-        .values()
-        .filter(|&property| !property.required)
-        .collect::<Vec<&PropertyDefinition>>()
-    {
-        request_source_code += &format!(
-            "if let Some(ref query_parameter) = {}.{} {{\n",
-            name_mapping.name_to_property_name(&operation_definition_path, &query_struct.name),
-            optional_property.name
-        );
-        if optional_property.type_name.starts_with("Vec<") {
-            request_source_code += &format!(
-                "query_parameter.iter().for_each(|query_parameter_item| query_parameters.push((\"{}\", query_parameter_item.to_string())));\n",
-                optional_property.real_name
-            );
-        } else {
-            request_source_code += &format!(
-                "query_parameters.push((\"{}\", query_parameter.to_string()));\n",
-                optional_property.real_name
-            );
-        }
-        request_source_code += "}\n"
-    }
+    let query_struct_variable_name = context
+        .name_mapping
+        .name_to_property_name(&operation_definition_path, &query_struct.name);
+    let query_parameters_code = QueryParameters::new(query_struct, query_struct_variable_name)
+        .to_string("query_parameters", context.config)?;
 
-    let mut path_parameter_arguments = path_parameters_ordered
+    let path_struct_variable_name = context
+        .name_mapping
+        .name_to_property_name(&operation_definition_path, &path_struct_definition.name);
+    let path_arguments = path_parameters_ordered
         .iter()
         .map(|parameter| {
             format!(
                 "{}.{}",
-                name_mapping.name_to_property_name(
-                    &operation_definition_path,
-                    &path_struct_definition.name
-                ),
-                name_mapping.name_to_property_name(&operation_definition_path, &parameter.name)
+                path_struct_variable_name,
+                context
+                    .name_mapping
+                    .name_to_property_name(&operation_definition_path, &parameter.name)
             )
         })
-        .collect::<Vec<String>>()
-        .join(",");
-    if path_parameter_arguments.len() > 0 {
-        path_parameter_arguments += ","
-    }
+        .collect::<Vec<String>>();
 
-    // create query parameter string
-    request_source_code += "let mut query_string = query_parameters
+    let argument_names = function_parameters
         .iter()
-        .map(|(name, value)| format!(\"{}={}\", name, value))
-        .collect::<Vec<String>>()
-        .join(\"&\");
-    if query_string.len() > 0 {
-        query_string.insert_str(0, \"?\");
-    }";
-
-    request_source_code += &format!(
-        "let (socket, _) = match connect(format!(
-        \"{{}}{}{{}}\",
-        host,
-        {}
-        query_string
-    )) {{
-        Ok(connection) => connection,
-        Err(err) => return Err(err),
-}};",
-        path_format_string, path_parameter_arguments
-    );
-    request_source_code += &format!("Ok({}::from(socket))", socket_stream_struct_name);
-    request_source_code += "}";
+        .filter_map(|parameter| parameter.split(':').next())
+        .map(|name| name.trim().to_owned())
+        .collect::<Vec<String>>();
+    request_source_code += &RustWebsocketFunctionTemplate {
+        name: &context.name_mapping.extract_function_name(&function_name),
+        parameters: function_parameters,
+        argument_names,
+        stream_name: &socket_stream_struct_name,
+        query_parameters_code: &query_parameters_code,
+        path_format_string: &path_format_string,
+        path_arguments,
+    }
+    .render()
+    .unwrap();
     Ok(request_source_code)
 }
@@ -0,0 +1,138 @@
+use std::{collections::BTreeMap, fmt::Display, time::Duration};
+
+use serde::Serialize;
+
+use super::diagnostics::Severity;
+
+/// Result of generating the components of a spec
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ComponentStatistics {
+    pub generated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageTiming {
+    pub stage: String,
+    pub millis: f64,
+}
+
+/// Summary of a generator run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GenerationStatistics {
+    pub components_generated: usize,
+    pub components_skipped: usize,
+    pub components_failed: usize,
+    pub operations_generated: BTreeMap<String, usize>,
+    pub operations_skipped: usize,
+    pub operations_failed: usize,
+    pub files_written: usize,
+    pub files_unchanged: usize,
+    pub stages: Vec<StageTiming>,
+    /// Stages which returned an error, with the number of errors
+    pub stage_errors: BTreeMap<String, usize>,
+    /// Schemas typed as `serde_json::Value` because of `not` or `if`/`then`/`else`
+    pub degraded_schemas: Vec<String>,
+    /// Number of diagnostics by severity, see `Generator::diagnostics`
+    pub diagnostics: BTreeMap<Severity, usize>,
+}
+
+impl GenerationStatistics {
+    pub fn add_components(&mut self, component_statistics: ComponentStatistics) {
+        self.components_generated += component_statistics.generated;
+        self.components_skipped += component_statistics.skipped;
+        self.components_failed += component_statistics.failed;
+    }
+
+    pub fn add_operation(&mut self, method: &str) {
+        *self
+            .operations_generated
+            .entry(method.to_owned())
+            .or_default() += 1;
+    }
+
+    pub fn add_stage_error(&mut self, stage: &str) {
+        *self.stage_errors.entry(stage.to_owned()).or_default() += 1;
+    }
+
+    /// True if any component, operation or stage failed
+    pub fn has_failures(&self) -> bool {
+        self.components_failed > 0 || self.operations_failed > 0 || !self.stage_errors.is_empty()
+    }
+
+    /// Adds `duration` to the timing of `stage`, stages keep their first-seen order
+    pub fn add_stage_duration(&mut self, stage: &str, duration: Duration) {
+        let millis = duration.as_secs_f64() * 1000.0;
+        match self.stages.iter_mut().find(|timing| timing.stage == stage) {
+            Some(timing) => timing.millis += millis,
+            None => self.stages.push(StageTiming {
+                stage: stage.to_owned(),
+                millis,
+            }),
+        }
+    }
+}
+
+impl Display for GenerationStatistics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Generation summary")?;
+        writeln!(
+            f,
+            "  components: {} generated, {} skipped, {} failed",
+            self.components_generated, self.components_skipped, self.components_failed
+        )?;
+        writeln!(
+            f,
+            "  operations: {} generated ({}), {} skipped, {} failed",
+            self.operations_generated.values().sum::<usize>(),
+            self.operations_generated
+                .iter()
+                .map(|(method, count)| format!("{}: {}", method, count))
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.operations_skipped,
+            self.operations_failed
+        )?;
+        writeln!(
+            f,
+            "  files: {} written, {} unchanged",
+            self.files_written, self.files_unchanged
+        )?;
+        write!(
+            f,
+            "  stages: {}",
+            self.stages
+                .iter()
+                .map(|timing| format!("{} {:.1}ms", timing.stage, timing.millis))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )?;
+        if !self.degraded_schemas.is_empty() {
+            write!(
+                f,
+                "\n  degraded to serde_json::Value: {}",
+                self.degraded_schemas.join(", ")
+            )?;
+        }
+        if !self.diagnostics.is_empty() {
+            write!(
+                f,
+                "\n  diagnostics: {}",
+                self.diagnostics
+                    .iter()
+                    .map(|(severity, count)| format!("{} {}", count, severity))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?;
+        }
+        if self.stage_errors.is_empty() {
+            return Ok(());
+        }
+        write!(f, "\n  errors:")?;
+        for (stage, count) in &self.stage_errors {
+            write!(f, "\n    {:<14} {}", stage, count)?;
+        }
+        Ok(())
+    }
+}
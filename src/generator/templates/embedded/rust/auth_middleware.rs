@@ -10,23 +10,36 @@ use crate::credentials::Credentials;
 #[derive(Debug, Clone)]
 pub(crate) struct AuthMiddleware(pub(crate) Arc<HashMap<String, Credentials>>);
 
+/// Authentication of a single request, replacing the credentials and the auth provider of the client
+#[derive(Debug, Clone)]
+pub enum AuthOverride {
+  /// Sent without authentication
+  Skip,
+  /// Sent with these credentials only
+  Credentials(Credentials),
+}
+
 #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
 #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
 impl Middleware for AuthMiddleware {
   async fn handle(&self, mut req: Request, extensions: &mut Extensions, next: Next<'_>) -> Result<Response> {
     let reg = req.url().clone();
     let to_match = nerf_dart(&reg);
-    let credentials = self.0.get(&to_match);
+    let credentials = match extensions.get::<AuthOverride>() {
+      Some(AuthOverride::Skip) => None,
+      Some(AuthOverride::Credentials(credentials)) => Some(credentials),
+      None => self.0.get(&to_match),
+    };
     if let Some(cred) = credentials {
       let auth_header = match cred {
         Credentials::Basic { username, password } => basic_auth(username, password.as_ref()),
         Credentials::EncodedBasic(auth) => {
-          let mut val = HeaderValue::from_str(&format!("Basic {auth}")).map_err(|e| anyhow::anyhow!(e))?;
+          let mut val = HeaderValue::from_str(&format!("Basic {}", auth)).map_err(|e| anyhow::anyhow!(e))?;
           val.set_sensitive(true);
           val
         }
         Credentials::Token(token) => {
-          let mut val = HeaderValue::from_str(&format!("Bearer {token}")).map_err(|e| anyhow::anyhow!(e))?;
+          let mut val = HeaderValue::from_str(&format!("Bearer {}", token)).map_err(|e| anyhow::anyhow!(e))?;
           val.set_sensitive(true);
           val
         }
@@ -37,8 +50,45 @@ impl Middleware for AuthMiddleware {
   }
 }
 
+/// Overrides whether a request may be retried, requests without it are retried
+/// when their method is idempotent.
+#[derive(Debug, Clone, Copy)]
+pub struct Idempotent(pub bool);
+
+pub fn is_idempotent_method(method: &reqwest::Method) -> bool {
+  matches!(
+    *method,
+    reqwest::Method::GET
+      | reqwest::Method::HEAD
+      | reqwest::Method::PUT
+      | reqwest::Method::DELETE
+      | reqwest::Method::OPTIONS
+      | reqwest::Method::TRACE
+  )
+}
+
+/// Runs the wrapped retry middleware only for idempotent requests, all others are sent once.
+#[derive(Debug, Clone)]
+pub(crate) struct IdempotentRetryMiddleware<M>(pub(crate) M);
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+impl<M: Middleware> Middleware for IdempotentRetryMiddleware<M> {
+  async fn handle(&self, req: Request, extensions: &mut Extensions, next: Next<'_>) -> Result<Response> {
+    let idempotent = match extensions.get::<Idempotent>() {
+      Some(idempotent) => idempotent.0,
+      None => is_idempotent_method(req.method()),
+    };
+    if idempotent {
+      self.0.handle(req, extensions, next).await
+    } else {
+      next.run(req, extensions).await
+    }
+  }
+}
+
 // From reqwest utils.
-fn basic_auth<U, P>(username: U, password: Option<P>) -> HeaderValue
+pub(crate) fn basic_auth<U, P>(username: U, password: Option<P>) -> HeaderValue
 where
   U: std::fmt::Display,
   P: std::fmt::Display, {
@@ -0,0 +1,111 @@
+//! Circuit breakers of the operations, built with the `circuit-breaker` feature.
+//!
+//! After `failure_threshold` consecutive failures (transport errors, timeouts and 5xx responses) the
+//! circuit of an operation opens and its requests fail with `Error::CircuitOpen` without being sent.
+//! Once `reset_timeout` elapsed a single request is let through, its outcome closes or reopens the circuit.
+
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+use crate::client::{Error, ResponseValue};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerSettings {
+  /// Consecutive failures opening the circuit
+  pub failure_threshold: u32,
+  /// Time an open circuit rejects requests
+  pub reset_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+  Closed { failures: u32 },
+  Open { since: Instant },
+  /// A probe request is in flight, the others are rejected
+  HalfOpen { since: Instant },
+}
+
+/// State of the circuit of every operation, shared by the clones of a client
+#[derive(Debug)]
+pub struct CircuitBreakers {
+  settings: CircuitBreakerSettings,
+  states: Mutex<HashMap<&'static str, CircuitState>>,
+}
+
+impl CircuitBreakers {
+  /// Breakers using `settings` for the operations without settings of their own
+  pub fn new(settings: CircuitBreakerSettings) -> Self {
+    CircuitBreakers {
+      settings,
+      states: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Fails with `Error::CircuitOpen` if the request of `operation_id` must not be sent
+  pub fn acquire(&self, operation_id: &'static str, settings: Option<CircuitBreakerSettings>) -> Result<(), Error> {
+    let settings = settings.unwrap_or(self.settings);
+    let mut states = self.states.lock().unwrap();
+    let state = states
+      .entry(operation_id)
+      .or_insert(CircuitState::Closed { failures: 0 });
+    match *state {
+      CircuitState::Closed { .. } => Ok(()),
+      // a probe that was dropped before completing doesn't keep the circuit half open
+      CircuitState::Open { since } | CircuitState::HalfOpen { since } if since.elapsed() >= settings.reset_timeout => {
+        *state = CircuitState::HalfOpen { since: Instant::now() };
+        Ok(())
+      }
+      _ => Err(Error::CircuitOpen(operation_id.to_owned())),
+    }
+  }
+
+  /// Records the outcome of a request of `operation_id`
+  pub fn record<T>(
+    &self,
+    operation_id: &'static str,
+    settings: Option<CircuitBreakerSettings>,
+    result: &Result<ResponseValue<T>, Error>,
+  ) {
+    let settings = settings.unwrap_or(self.settings);
+    let mut states = self.states.lock().unwrap();
+    let state = states
+      .entry(operation_id)
+      .or_insert(CircuitState::Closed { failures: 0 });
+    *state = match (*state, is_failure(result)) {
+      (_, false) => CircuitState::Closed { failures: 0 },
+      (CircuitState::Closed { failures }, true) if failures + 1 < settings.failure_threshold => {
+        CircuitState::Closed { failures: failures + 1 }
+      }
+      (_, true) => CircuitState::Open { since: Instant::now() },
+    };
+  }
+
+  /// Current state of the circuit of `operation_id`
+  pub fn state(&self, operation_id: &str) -> CircuitState {
+    self
+      .states
+      .lock()
+      .unwrap()
+      .get(operation_id)
+      .copied()
+      .unwrap_or(CircuitState::Closed { failures: 0 })
+  }
+
+  /// Closes all circuits
+  pub fn reset(&self) {
+    self.states.lock().unwrap().clear();
+  }
+}
+
+// errors of the client (4xx, invalid requests) don't count against the service
+fn is_failure<T>(result: &Result<ResponseValue<T>, Error>) -> bool {
+  match result {
+    Ok(response) => response.status().is_server_error(),
+    Err(Error::CommunicationError(_)) | Err(Error::Timeout(_)) => true,
+    Err(Error::UnexpectedResponse(response)) => response.status.is_server_error(),
+    Err(_) => false,
+  }
+}
@@ -64,10 +64,11 @@ impl<T: DeserializeOwned> ResponseValue<T> {
     pub async fn from_response(response: reqwest::Response) -> Result<Self, Error> {
         let status = response.status();
         let headers = response.headers().clone();
-        let inner = response
-            .json()
+        let body = response
+            .bytes()
             .await
             .map_err(Error::InvalidResponsePayload)?;
+        let inner = decode_body(&headers, &body)?;
 
         Ok(Self {
             inner,
@@ -77,6 +78,45 @@ impl<T: DeserializeOwned> ResponseValue<T> {
     }
 }
 
+/// Deserializes a response body according to its `Content-Type`, the one negotiated with `Accept`.
+///
+/// JSON types (`application/json`, `+json` vendor types) and responses without content type are
+/// parsed as JSON, `text/*` bodies are only read into `String` responses.
+pub fn decode_body<T: DeserializeOwned>(
+    headers: &reqwest::header::HeaderMap,
+    body: &[u8],
+) -> Result<T, Error> {
+    let content_type = match headers.get(reqwest::header::CONTENT_TYPE) {
+        Some(content_type) => content_type
+            .to_str()
+            .unwrap_or_default()
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase(),
+        None => return Ok(serde_json::from_slice(body)?),
+    };
+    if content_type == "application/json"
+        || (content_type.starts_with("application/") && content_type.ends_with("+json"))
+    {
+        Ok(serde_json::from_slice(body)?)
+    } else if content_type.starts_with("text/") {
+        let text = String::from_utf8_lossy(body).into_owned();
+        serde_json::from_value(serde_json::Value::String(text)).map_err(|_| {
+            Error::InvalidResponse(format!(
+                "{} response can't be deserialized, send it with `send_raw`",
+                content_type
+            ))
+        })
+    } else {
+        Err(Error::InvalidResponse(format!(
+            "unsupported response content type {}, send it with `send_raw`",
+            content_type
+        )))
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 impl ResponseValue<reqwest::Upgraded> {
     #[doc(hidden)]
@@ -149,6 +189,34 @@ impl ResponseValue<String> {
     }
 }
 
+impl ResponseValue<bytes::Bytes> {
+    #[doc(hidden)]
+    pub async fn bytes(response: reqwest::Response) -> Result<Self, Error> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let inner = response
+            .bytes()
+            .await
+            .map_err(Error::InvalidResponsePayload)?;
+        Ok(Self {
+            inner,
+            status,
+            headers,
+        })
+    }
+}
+
+/// Response as received, its body is not read: for payloads the generated types can't handle
+impl From<reqwest::Response> for ResponseValue<reqwest::Response> {
+    fn from(response: reqwest::Response) -> Self {
+        Self {
+            status: response.status(),
+            headers: response.headers().clone(),
+            inner: response,
+        }
+    }
+}
+
 impl<T> ResponseValue<T> {
     /// Creates a [`ResponseValue`] from the inner type, status, and headers.
     ///
@@ -328,6 +396,549 @@ pub enum Error {
     /// success or failure response; check `status().is_success()`.
     #[error("UnexpectedResponse: {0}")]
     UnexpectedResponse(ReqwestResponse),
+
+    /// No response was received before the request deadline.
+    #[error("Request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    /// The request was cancelled before a response was received.
+    #[error("Request cancelled")]
+    Cancelled,
+
+    /// The circuit breaker of the operation is open, the request was not sent.
+    #[error("Circuit open for {0}")]
+    CircuitOpen(String),
+}
+
+impl Error {
+    /// Problem details of an `application/problem+json` error response, the same for all operations.
+    ///
+    /// Returns `None` for errors without a response or with another content type.
+    pub fn problem(&self) -> Option<Problem> {
+        let response = match self {
+            Error::UnexpectedResponse(response) => response,
+            _ => return None,
+        };
+        let is_problem = response
+            .headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| {
+                content_type
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .eq_ignore_ascii_case("application/problem+json")
+            });
+        match is_problem {
+            true => serde_json::from_str(&response.value).ok(),
+            false => None,
+        }
+    }
+}
+
+/// Problem details (RFC 7807) of `application/problem+json` responses
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize, Serialize)]
+pub struct Problem {
+    /// URI identifying the problem type, `about:blank` if missing
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub problem_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// URI identifying this occurrence of the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension members of the problem type
+    #[serde(flatten)]
+    pub extensions: std::collections::HashMap<String, serde_json::Value>,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let title = self.title.as_deref().unwrap_or("Problem");
+        match (self.status, &self.detail) {
+            (Some(status), Some(detail)) => write!(f, "{} ({}): {}", title, status, detail),
+            (Some(status), None) => write!(f, "{} ({})", title, status),
+            (None, Some(detail)) => write!(f, "{}: {}", title, detail),
+            (None, None) => f.write_str(title),
+        }
+    }
+}
+
+/// Records request count, latency and errors of an operation.
+///
+/// Labels: `operation_id` and `status` (the HTTP status code or `error` when
+/// no response was received).
+#[cfg(feature = "metrics")]
+#[doc(hidden)]
+pub fn record_metrics<T>(
+    operation_id: &'static str,
+    started: std::time::Instant,
+    result: &Result<ResponseValue<T>, Error>,
+) {
+    let status = match result {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(Error::UnexpectedResponse(response)) => response.status.as_u16().to_string(),
+        Err(_) => "error".to_string(),
+    };
+    metrics::counter!(
+        "api_client_requests_total",
+        "operation_id" => operation_id,
+        "status" => status.clone()
+    )
+    .increment(1);
+    metrics::histogram!(
+        "api_client_request_duration_seconds",
+        "operation_id" => operation_id,
+        "status" => status.clone()
+    )
+    .record(started.elapsed().as_secs_f64());
+    if result.is_err() {
+        metrics::counter!(
+            "api_client_errors_total",
+            "operation_id" => operation_id,
+            "status" => status
+        )
+        .increment(1);
+    }
+}
+
+/// Query parameter from its Display value. Without a value (`None`) only the name
+/// is sent (`?name`), which is the case for empty values of parameters allowing them.
+#[doc(hidden)]
+pub fn query_pair(
+    name: &str,
+    value: impl ToString,
+    allow_empty_value: bool,
+) -> (String, Option<String>) {
+    let value = value.to_string();
+    match allow_empty_value && value.is_empty() {
+        true => (name.to_string(), None),
+        false => (name.to_string(), Some(value)),
+    }
+}
+
+/// Query parameters of a value serialized with serde, in the `style` of the spec
+/// (`form`, `spaceDelimited`, `pipeDelimited` or `deepObject`).
+///
+/// Arrays are repeated when exploded and joined otherwise, objects are sent as one
+/// parameter per property (`deepObject` as `name[property]`). `None` sends nothing.
+#[doc(hidden)]
+pub fn query_pairs<T: Serialize>(
+    name: &str,
+    value: &T,
+    style: &str,
+    explode: bool,
+    allow_empty_value: bool,
+) -> Result<Vec<(String, Option<String>)>, Error> {
+    fn scalar(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::String(value) => value.clone(),
+            serde_json::Value::Null => String::new(),
+            value => value.to_string(),
+        }
+    }
+
+    let separator = match style {
+        "spaceDelimited" => " ",
+        "pipeDelimited" => "|",
+        _ => ",",
+    };
+    let pairs = match serde_json::to_value(value)? {
+        serde_json::Value::Null => vec![],
+        serde_json::Value::Array(items) if explode && style == "form" => items
+            .iter()
+            .map(|item| (name.to_string(), scalar(item)))
+            .collect(),
+        serde_json::Value::Array(items) => vec![(
+            name.to_string(),
+            items.iter().map(scalar).collect::<Vec<String>>().join(separator),
+        )],
+        serde_json::Value::Object(properties) if style == "deepObject" => properties
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(property, value)| (format!("{}[{}]", name, property), scalar(value)))
+            .collect(),
+        serde_json::Value::Object(properties) if explode => properties
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(property, value)| (property.clone(), scalar(value)))
+            .collect(),
+        serde_json::Value::Object(properties) => vec![(
+            name.to_string(),
+            properties
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .flat_map(|(property, value)| [property.clone(), scalar(value)])
+                .collect::<Vec<String>>()
+                .join(","),
+        )],
+        value => vec![(name.to_string(), scalar(&value))],
+    };
+    Ok(pairs
+        .into_iter()
+        .map(|(name, value)| match allow_empty_value && value.is_empty() {
+            true => (name, None),
+            false => (name, Some(value)),
+        })
+        .collect())
+}
+
+/// Serializable description of a request built with `dry_run`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestDescription {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    /// Body, if any and valid UTF-8
+    pub body: Option<String>,
+}
+
+impl From<&reqwest::Request> for RequestDescription {
+    fn from(request: &reqwest::Request) -> Self {
+        RequestDescription {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: request
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .and_then(|body| std::str::from_utf8(body).ok())
+                .map(|body| body.to_owned()),
+        }
+    }
+}
+
+// headers replaced by `REDACTED` in curl commands
+const SECRET_HEADERS: [&str; 5] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "x-api-key",
+    "x-auth-token",
+];
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+impl RequestDescription {
+    /// Equivalent curl command, with `redact_secrets` the values of credential headers are hidden
+    pub fn to_curl(&self, redact_secrets: bool) -> String {
+        let mut command = format!("curl -X {} {}", self.method, shell_quote(&self.url));
+        for (name, value) in self.headers.iter() {
+            let value = match redact_secrets && SECRET_HEADERS.contains(&name.as_str()) {
+                true => "REDACTED",
+                false => value.as_str(),
+            };
+            command.push_str(&format!(
+                " -H {}",
+                shell_quote(&format!("{}: {}", name, value))
+            ));
+        }
+        if let Some(body) = &self.body {
+            command.push_str(&format!(" --data-raw {}", shell_quote(body)));
+        }
+        command
+    }
+}
+
+/// Hash of the serialized body sent in a header, required by some storage APIs for uploads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentChecksum {
+    /// Base64 md5 digest in `Content-MD5`
+    Md5,
+    /// Hex sha256 digest in `x-amz-content-sha256`
+    Sha256,
+}
+
+impl ContentChecksum {
+    /// Header name and value for `body`
+    pub fn header(&self, body: &[u8]) -> (&'static str, String) {
+        use base64::Engine;
+        use sha2::Digest;
+
+        match self {
+            ContentChecksum::Md5 => (
+                "Content-MD5",
+                base64::engine::general_purpose::STANDARD.encode(md5::Md5::digest(body)),
+            ),
+            ContentChecksum::Sha256 => (
+                "x-amz-content-sha256",
+                sha2::Sha256::digest(body)
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Serializes bulk items as newline delimited JSON: each action line is followed
+/// by its document line, if any.
+#[doc(hidden)]
+pub fn to_ndjson<A: Serialize, D: Serialize>(
+    items: impl IntoIterator<Item = (A, Option<D>)>,
+) -> Result<String, Error> {
+    let mut body = String::new();
+    for (action, document) in items {
+        body.push_str(&serde_json::to_string(&action)?);
+        body.push('\n');
+        if let Some(document) = document {
+            body.push_str(&serde_json::to_string(&document)?);
+            body.push('\n');
+        }
+    }
+    Ok(body)
+}
+
+/// Request body read from a stream while it is sent, uploads are never buffered in memory.
+///
+/// The body is taken by the first request built with it, cloning the builder shares it.
+/// Requests with a streamed body are not retried.
+#[derive(Clone, Default)]
+pub struct StreamingBody(std::sync::Arc<std::sync::Mutex<Option<reqwest::Body>>>);
+
+impl StreamingBody {
+    /// Body sent chunk by chunk as the stream yields them
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: futures::TryStream + Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        Self::from(reqwest::Body::wrap_stream(stream))
+    }
+
+    /// Body read from `reader`, e.g. a `tokio::fs::File`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_reader<R: tokio::io::AsyncRead + Send + 'static>(reader: R) -> Self {
+        Self::from_stream(tokio_util::io::ReaderStream::new(reader))
+    }
+
+    /// Takes the body, `None` once it was sent
+    pub fn take(&self) -> Option<reqwest::Body> {
+        self.0.lock().unwrap().take()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.lock().unwrap().is_none()
+    }
+}
+
+impl From<reqwest::Body> for StreamingBody {
+    fn from(body: reqwest::Body) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(Some(body))))
+    }
+}
+
+impl std::fmt::Debug for StreamingBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.is_empty() {
+            true => f.write_str("StreamingBody(None)"),
+            false => f.write_str("StreamingBody(..)"),
+        }
+    }
+}
+
+/// Request of an operation, filled by `build_request` of the builders and sent by the client
+#[derive(Debug, Default)]
+pub struct Request {
+    method: reqwest::Method,
+    path: String,
+    query: Vec<(String, Option<String>)>,
+    headers: Vec<(reqwest::header::HeaderName, reqwest::header::HeaderValue)>,
+    body: Option<String>,
+    body_stream: StreamingBody,
+    idempotent: bool,
+    auth: Option<crate::auth_middleware::AuthOverride>,
+    content_checksum: Option<ContentChecksum>,
+}
+
+impl Request {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_method(&mut self, method: reqwest::Method) {
+        self.method = method;
+    }
+
+    pub fn set_path(&mut self, path: impl Into<String>) {
+        self.path = path.into();
+    }
+
+    /// Query parameters in the order they are sent, `None` sends only the name
+    pub fn set_query_params(&mut self, query: Vec<(String, Option<String>)>) {
+        self.query = query;
+    }
+
+    /// Adds a header, failing for names and values which are not valid in HTTP
+    pub fn add_header(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| Error::InvalidRequest(format!("invalid header name {}", name)))?;
+        let header_value = reqwest::header::HeaderValue::from_str(value)
+            .map_err(|_| Error::InvalidRequest(format!("invalid value of header {}", name)))?;
+        self.headers.push((header_name, header_value));
+        Ok(())
+    }
+
+    /// Whether a header `name` was added, names are compared ignoring case
+    pub fn has_header(&self, name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(header_name, _)| header_name.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Body serialized as JSON, sent as `application/json` unless another Content-Type was added
+    pub fn set_body<T: Serialize>(&mut self, body: T) -> Result<(), Error> {
+        self.body = Some(serde_json::to_string(&body)?);
+        if !self.has_header("content-type") {
+            self.add_header("content-type", "application/json")?;
+        }
+        Ok(())
+    }
+
+    /// Body sent as is, its Content-Type is added by the caller
+    pub fn set_raw_body(&mut self, body: String) {
+        self.body = Some(body);
+    }
+
+    pub fn set_body_stream(&mut self, body: StreamingBody) {
+        self.body_stream = body;
+    }
+
+    pub fn set_idempotent(&mut self, idempotent: bool) {
+        self.idempotent = idempotent;
+    }
+
+    pub fn set_auth(&mut self, auth: Option<crate::auth_middleware::AuthOverride>) {
+        self.auth = auth;
+    }
+
+    pub fn set_content_checksum(&mut self, content_checksum: ContentChecksum) {
+        self.content_checksum = Some(content_checksum);
+    }
+}
+
+impl crate::Request for Request {
+    type Response = serde_json::Value;
+
+    fn method(&self) -> reqwest::Method {
+        self.method.clone()
+    }
+
+    fn path(&self) -> Result<String, Error> {
+        Ok(self.path.clone())
+    }
+
+    fn body(&self) -> Result<Option<String>, Error> {
+        Ok(self.body.clone())
+    }
+
+    fn body_stream(&self) -> Option<reqwest::Body> {
+        self.body_stream.take()
+    }
+
+    fn headers(&self) -> Vec<(reqwest::header::HeaderName, reqwest::header::HeaderValue)> {
+        self.headers.clone()
+    }
+
+    fn query_args(&self) -> Result<Vec<(String, Option<String>)>, Error> {
+        Ok(self.query.clone())
+    }
+
+    fn idempotent(&self) -> bool {
+        self.idempotent
+    }
+
+    fn content_checksum(&self) -> Option<ContentChecksum> {
+        self.content_checksum
+    }
+
+    fn auth(&self) -> Option<crate::auth_middleware::AuthOverride> {
+        self.auth.clone()
+    }
+}
+
+/// Deserializes each non empty line of a `application/x-ndjson` body while it is received.
+#[doc(hidden)]
+pub fn ndjson_rows<T: DeserializeOwned>(
+    stream: ByteStream,
+) -> impl Stream<Item = Result<T, Error>> {
+    use futures::StreamExt;
+
+    futures::stream::unfold(
+        (stream.into_inner(), Vec::<u8>::new(), false),
+        |(mut stream, mut buffer, mut finished)| async move {
+            loop {
+                if let Some(position) = buffer.iter().position(|byte| *byte == b'\n') {
+                    let line = buffer.drain(..=position).collect::<Vec<u8>>();
+                    if line.iter().all(|byte| byte.is_ascii_whitespace()) {
+                        continue;
+                    }
+                    let row = serde_json::from_slice(&line).map_err(Error::from);
+                    return Some((row, (stream, buffer, finished)));
+                }
+                if finished {
+                    // last line without trailing newline
+                    if buffer.iter().all(|byte| byte.is_ascii_whitespace()) {
+                        return None;
+                    }
+                    let row = serde_json::from_slice(&buffer).map_err(Error::from);
+                    buffer.clear();
+                    return Some((row, (stream, buffer, finished)));
+                }
+                match stream.next().await {
+                    Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                    Some(Err(err)) => {
+                        finished = true;
+                        buffer.clear();
+                        return Some((
+                            Err(Error::InvalidResponsePayload(err)),
+                            (stream, buffer, finished),
+                        ));
+                    }
+                    None => finished = true,
+                }
+            }
+        },
+    )
+}
+
+/// Reads a `text/csv` body with a header line and deserializes each record.
+#[doc(hidden)]
+pub async fn csv_rows<T: DeserializeOwned>(
+    stream: ByteStream,
+) -> Result<impl Iterator<Item = Result<T, Error>>, Error> {
+    use futures::TryStreamExt;
+
+    let body = stream
+        .into_inner()
+        .try_fold(Vec::new(), |mut body, bytes| async move {
+            body.extend_from_slice(&bytes);
+            Ok(body)
+        })
+        .await
+        .map_err(Error::InvalidResponsePayload)?;
+    Ok(csv::Reader::from_reader(std::io::Cursor::new(body))
+        .into_deserialize::<T>()
+        .map(|row| row.map_err(|err| Error::InvalidResponse(err.to_string()))))
 }
 
 trait ErrorFormat {
@@ -371,6 +982,26 @@ const PATH_SET: &percent_encoding::AsciiSet = &percent_encoding::CONTROLS
     .add(b'/')
     .add(b'%');
 
+/// Path parameter in the `label` or `matrix` style of the spec, other styles are
+/// written as `simple` (values separated by a comma).
+#[doc(hidden)]
+pub fn styled_path_value(style: &str, name: &str, values: &[String], explode: bool) -> String {
+    match (style, explode) {
+        ("label", false) => format!(".{}", values.join(",")),
+        ("label", true) => values.iter().map(|value| format!(".{}", value)).collect(),
+        ("matrix", false) if values.iter().all(|value| value.is_empty()) => format!(";{}", name),
+        ("matrix", false) => format!(";{}={}", name, values.join(",")),
+        ("matrix", true) => values
+            .iter()
+            .map(|value| match value.is_empty() {
+                true => format!(";{}", name),
+                false => format!(";{}={}", name, value),
+            })
+            .collect(),
+        _ => values.join(","),
+    }
+}
+
 #[doc(hidden)]
 pub fn encode_path(pc: &str) -> String {
     percent_encoding::utf8_percent_encode(pc, PATH_SET).to_string()
@@ -0,0 +1,57 @@
+//! JSON Patch (RFC 6902) documents, sent as `application/json-patch+json` request bodies
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Operation of a JSON Patch, `path` and `from` are JSON Pointers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+  Add { path: String, value: Value },
+  Remove { path: String },
+  Replace { path: String, value: Value },
+  Move { from: String, path: String },
+  Copy { from: String, path: String },
+  Test { path: String, value: Value },
+}
+
+/// Patch turning `from` into `to`.
+///
+/// Objects are compared field by field, arrays and other values which differ are replaced as a whole.
+pub fn diff<T: Serialize>(from: &T, to: &T) -> Result<Vec<PatchOperation>, serde_json::Error> {
+  let from = serde_json::to_value(from)?;
+  let to = serde_json::to_value(to)?;
+  let mut operations = vec![];
+  diff_values("", &from, &to, &mut operations);
+  Ok(operations)
+}
+
+fn diff_values(path: &str, from: &Value, to: &Value, operations: &mut Vec<PatchOperation>) {
+  match (from, to) {
+    (Value::Object(from), Value::Object(to)) => {
+      for (key, from_value) in from.iter() {
+        let field_path = format!("{}/{}", path, escape(key));
+        match to.get(key) {
+          Some(to_value) => diff_values(&field_path, from_value, to_value, operations),
+          None => operations.push(PatchOperation::Remove { path: field_path }),
+        }
+      }
+      for (key, to_value) in to.iter().filter(|(key, _)| !from.contains_key(*key)) {
+        operations.push(PatchOperation::Add {
+          path: format!("{}/{}", path, escape(key)),
+          value: to_value.clone(),
+        });
+      }
+    }
+    (from, to) if from != to => operations.push(PatchOperation::Replace {
+      path: path.to_owned(),
+      value: to.clone(),
+    }),
+    _ => {}
+  }
+}
+
+// JSON Pointer escaping of a segment
+fn escape(segment: &str) -> String {
+  segment.replace('~', "~0").replace('/', "~1")
+}
@@ -0,0 +1,144 @@
+//! Hypermedia links of responses (HAL style `_links`), followed without building URLs by hand
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use url::Url;
+
+use crate::client::{encode_path, Error};
+
+/// Link to a related resource, `href` may be relative to the base url of the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+  pub href: String,
+  /// `href` is a URI template (`/pets{?page}`), expanded with `expand`
+  pub templated: bool,
+  pub title: Option<String>,
+}
+
+impl Link {
+  /// Replaces the `{name}` variables of a templated link, query expressions (`{?a,b}`)
+  /// are expanded to the given variables and dropped otherwise
+  pub fn expand(&self, variables: &[(&str, &str)]) -> Link {
+    let mut href = String::new();
+    let mut rest = self.href.as_str();
+    while let Some(start) = rest.find('{') {
+      let end = match rest[start..].find('}') {
+        Some(end) => start + end,
+        None => break,
+      };
+      href.push_str(&rest[..start]);
+      let expression = &rest[start + 1..end];
+      let value = |name: &str| {
+        variables
+          .iter()
+          .find(|(variable, _)| *variable == name)
+          .map(|(_, value)| encode_path(value))
+      };
+      match expression.strip_prefix('?') {
+        Some(names) => {
+          let pairs = names
+            .split(',')
+            .filter_map(|name| value(name).map(|value| format!("{}={}", name, value)))
+            .collect::<Vec<String>>();
+          if !pairs.is_empty() {
+            href.push('?');
+            href.push_str(&pairs.join("&"));
+          }
+        }
+        None => href.push_str(&value(expression).unwrap_or_default()),
+      }
+      rest = &rest[end + 1..];
+    }
+    href.push_str(rest);
+    Link {
+      href,
+      templated: false,
+      title: self.title.clone(),
+    }
+  }
+
+  /// Absolute URL of the link, relative links are resolved against `base_url`
+  pub fn url(&self, base_url: &Url) -> Result<Url, Error> {
+    if self.templated {
+      return Err(Error::InvalidRequest(format!(
+        "link {} is templated, expand it first",
+        self.href
+      )));
+    }
+    Ok(base_url.join(&self.href)?)
+  }
+}
+
+/// Link of the relation `rel` in a links object.
+///
+/// Accepts link objects (`{"href": ..}`), plain URLs and arrays of them, of which the first is used.
+pub fn find_link(links: &Value, rel: &str) -> Option<Link> {
+  let link = match links.get(rel)? {
+    Value::Array(links) => links.first()?,
+    link => link,
+  };
+  match link {
+    Value::String(href) => Some(Link {
+      href: href.clone(),
+      templated: false,
+      title: None,
+    }),
+    Value::Object(link) => Some(Link {
+      href: link.get("href")?.as_str()?.to_owned(),
+      templated: link.get("templated").and_then(Value::as_bool).unwrap_or(false),
+      title: link.get("title").and_then(Value::as_str).map(str::to_owned),
+    }),
+    _ => None,
+  }
+}
+
+/// Relations of a links object
+pub fn link_relations(links: &Value) -> Vec<String> {
+  match links.as_object() {
+    Some(links) => links.keys().cloned().collect(),
+    None => vec![],
+  }
+}
+
+/// GET request of a link, sent by the client like the requests of the builders
+#[derive(Debug, Clone)]
+pub struct LinkRequest<T = Value> {
+  link: Link,
+  response: PhantomData<T>,
+}
+
+impl<T> LinkRequest<T> {
+  pub fn new(link: Link) -> Self {
+    LinkRequest {
+      link,
+      response: PhantomData,
+    }
+  }
+}
+
+impl<T: DeserializeOwned + crate::MaybeSendSync> crate::Request for LinkRequest<T> {
+  type Response = T;
+
+  fn method(&self) -> reqwest::Method {
+    reqwest::Method::GET
+  }
+
+  fn path(&self) -> Result<String, Error> {
+    Ok(self.link.href.clone())
+  }
+
+  fn body(&self) -> Result<Option<String>, Error> {
+    Ok(None)
+  }
+
+  fn query_args(&self) -> Result<Vec<(String, Option<String>)>, Error> {
+    Ok(vec![])
+  }
+
+  // the href carries its own path and query
+  fn url(&self, base_url: &Url) -> Result<Url, Error> {
+    self.link.url(base_url)
+  }
+}
@@ -0,0 +1,68 @@
+//! Asynchronous operations, answered with `202 Accepted` and the `Location` of a status endpoint
+
+use std::time::Duration;
+
+use serde_json::Value;
+
+use crate::client::{Error, ReqwestResponse};
+
+/// How the status endpoint of an asynchronous operation is polled
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollOptions {
+  /// Field or JSON Pointer (`/job/state`) of the state in the status response
+  pub status_field: String,
+  /// States ending the polling
+  pub terminal_states: Vec<String>,
+  /// Time between two polls
+  pub interval: Duration,
+  /// Polls before giving up with `Error::Timeout`, unlimited with `None`
+  pub max_attempts: Option<u32>,
+}
+
+impl PollOptions {
+  pub fn interval(mut self, interval: Duration) -> Self {
+    self.interval = interval;
+    self
+  }
+
+  pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+    self.max_attempts = Some(max_attempts);
+    self
+  }
+
+  /// State of a status response, `None` if it has none
+  pub fn status_of(&self, body: &Value) -> Option<String> {
+    let status = match self.status_field.starts_with('/') {
+      true => body.pointer(&self.status_field)?,
+      false => body.get(&self.status_field)?,
+    };
+    match status {
+      Value::String(status) => Some(status.clone()),
+      Value::Null => None,
+      status => Some(status.to_string()),
+    }
+  }
+
+  /// Whether the status response reports a terminal state, states are compared ignoring case
+  pub fn is_terminal(&self, body: &Value) -> bool {
+    self.status_of(body).is_some_and(|status| {
+      self
+        .terminal_states
+        .iter()
+        .any(|terminal_state| terminal_state.eq_ignore_ascii_case(&status))
+    })
+  }
+}
+
+/// `Location` of a `202 Accepted` response, the status endpoint to poll
+pub fn accepted_location(response: ReqwestResponse) -> Result<String, Error> {
+  if response.status != reqwest::StatusCode::ACCEPTED {
+    return Err(Error::UnexpectedResponse(response));
+  }
+  response
+    .headers
+    .get(reqwest::header::LOCATION)
+    .and_then(|location| location.to_str().ok())
+    .map(str::to_owned)
+    .ok_or_else(|| Error::InvalidResponse("202 Accepted without Location header".to_owned()))
+}
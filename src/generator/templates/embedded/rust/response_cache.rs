@@ -0,0 +1,159 @@
+//! In-memory cache of GET responses, keyed by url (path and query parameters), Accept header
+//! and authentication
+
+use std::time::{Duration, Instant};
+
+use http::Extensions;
+use moka::{future::Cache, Expiry};
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use url::Url;
+
+/// Successful response kept by the cache, its body is deserialized again on every hit
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+  pub status: reqwest::StatusCode,
+  pub headers: reqwest::header::HeaderMap,
+  pub body: bytes::Bytes,
+  ttl: Duration,
+}
+
+/// Cache of the operations enabled in the generator config, set with `ClientBuilder::response_cache`.
+///
+/// `Cache-Control: max-age` of the responses wins over `default_ttl`, responses with `no-store`,
+/// `no-cache` or `private` are not cached.
+#[derive(Clone)]
+pub struct ResponseCache {
+  cache: Cache<String, CachedResponse>,
+  default_ttl: Duration,
+}
+
+impl std::fmt::Debug for ResponseCache {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("ResponseCache")
+      .field("entries", &self.cache.entry_count())
+      .field("default_ttl", &self.default_ttl)
+      .finish()
+  }
+}
+
+struct ResponseExpiry;
+
+impl Expiry<String, CachedResponse> for ResponseExpiry {
+  fn expire_after_create(
+    &self,
+    _key: &String,
+    value: &CachedResponse,
+    _created_at: Instant,
+  ) -> Option<Duration> {
+    Some(value.ttl)
+  }
+}
+
+impl ResponseCache {
+  /// Least recently used responses are evicted beyond `max_entries`
+  pub fn new(max_entries: u64, default_ttl: Duration) -> Self {
+    ResponseCache {
+      cache: Cache::builder()
+        .max_capacity(max_entries)
+        .expire_after(ResponseExpiry)
+        .support_invalidation_closures()
+        .build(),
+      default_ttl,
+    }
+  }
+
+  pub async fn get(&self, key: &str) -> Option<CachedResponse> {
+    self.cache.get(key).await
+  }
+
+  /// Keeps the response if its `Cache-Control` allows it
+  pub async fn insert(
+    &self,
+    key: String,
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: bytes::Bytes,
+  ) {
+    if let Some(ttl) = cache_ttl(&headers, self.default_ttl) {
+      let response = CachedResponse {
+        status,
+        headers,
+        body,
+        ttl,
+      };
+      self.cache.insert(key, response).await;
+    }
+  }
+
+  /// Drops the response kept under `key`, see `cache_key`
+  pub async fn invalidate(&self, key: &str) {
+    self.cache.invalidate(key).await;
+  }
+
+  /// Drops the responses of the resource at `url`, whatever their query, Accept header and authentication
+  pub fn invalidate_resource(&self, url: &Url) {
+    let resource = resource_of(url.as_str()).to_owned();
+    // only fails without `support_invalidation_closures`, the cache is built with it
+    let _ = self
+      .cache
+      .invalidate_entries_if(move |key, _| resource_of(key) == resource);
+  }
+
+  pub fn invalidate_all(&self) {
+    self.cache.invalidate_all();
+  }
+}
+
+/// Key of a response: its url, the Accept header it was negotiated with and whether it was requested
+/// without credentials. Requests with credentials of their own (`with_auth`) are not cached.
+pub fn cache_key(url: &Url, accept: Option<&str>, anonymous: bool) -> String {
+  format!("{} accept={} anonymous={}", url, accept.unwrap_or_default(), anonymous)
+}
+
+// url of a key without its query, serialized urls contain no spaces
+fn resource_of(key: &str) -> &str {
+  key.split(['?', ' ']).next().unwrap_or_default()
+}
+
+/// Drops the cached responses of a resource when another request than GET or HEAD is sent to it
+#[derive(Debug, Clone)]
+pub(crate) struct InvalidationMiddleware(pub(crate) ResponseCache);
+
+#[async_trait::async_trait]
+impl Middleware for InvalidationMiddleware {
+  async fn handle(
+    &self,
+    req: Request,
+    extensions: &mut Extensions,
+    next: Next<'_>,
+  ) -> reqwest_middleware::Result<Response> {
+    if req.method() != reqwest::Method::GET && req.method() != reqwest::Method::HEAD {
+      self.0.invalidate_resource(req.url());
+    }
+    next.run(req, extensions).await
+  }
+}
+
+/// Time a response may be cached according to its `Cache-Control`, `None` if it must not be cached
+pub fn cache_ttl(headers: &reqwest::header::HeaderMap, default_ttl: Duration) -> Option<Duration> {
+  let cache_control = match headers.get(reqwest::header::CACHE_CONTROL) {
+    Some(cache_control) => cache_control.to_str().ok()?.to_ascii_lowercase(),
+    None => return Some(default_ttl),
+  };
+  let mut ttl = default_ttl;
+  for directive in cache_control.split(',').map(str::trim) {
+    match directive {
+      "no-store" | "no-cache" | "private" => return None,
+      _ => {
+        if let Some(max_age) = directive.strip_prefix("max-age=") {
+          ttl = Duration::from_secs(max_age.trim_matches('"').parse().ok()?);
+        }
+      }
+    }
+  }
+  match ttl.is_zero() {
+    true => None,
+    false => Some(ttl),
+  }
+}
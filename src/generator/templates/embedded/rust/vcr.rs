@@ -0,0 +1,158 @@
+//! Recording of request/response pairs into cassettes (VCR-style) and their replay in tests
+
+use std::{path::PathBuf, sync::Mutex};
+
+use http::Extensions;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Error, Middleware, Next, Result};
+use serde::{Deserialize, Serialize};
+
+/// Request/response pair stored in a cassette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub response_body: String,
+}
+
+/// Whether the client records interactions into a cassette or replays them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Requests are sent and every interaction is written to the file
+    Record(PathBuf),
+    /// Requests are answered from the file, nothing is sent
+    Replay(PathBuf),
+}
+
+#[derive(Debug)]
+pub(crate) struct CassetteMiddleware {
+    mode: CassetteMode,
+    // recorded interactions, or the ones not replayed yet (loaded on the first request)
+    interactions: Mutex<Option<Vec<Interaction>>>,
+}
+
+impl CassetteMiddleware {
+    pub(crate) fn new(mode: CassetteMode) -> Self {
+        CassetteMiddleware {
+            mode,
+            interactions: Mutex::new(None),
+        }
+    }
+
+    async fn record(
+        &self,
+        path: &PathBuf,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        let method = req.method().to_string();
+        let url = req.url().to_string();
+        let request_body = body_string(&req);
+        let response = next.run(req, extensions).await?;
+
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        let interaction = Interaction {
+            method,
+            url,
+            request_body,
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+            response_body: String::from_utf8_lossy(&body).into_owned(),
+        };
+        {
+            let mut interactions = self.interactions.lock().unwrap();
+            let interactions = interactions.get_or_insert_with(Vec::new);
+            interactions.push(interaction);
+            let content = serde_json::to_vec_pretty(interactions)
+                .map_err(|err| Error::Middleware(err.into()))?;
+            std::fs::write(path, content).map_err(|err| Error::Middleware(err.into()))?;
+        }
+
+        let mut response = http::Response::builder().status(status).version(version);
+        for (name, value) in headers.iter() {
+            response = response.header(name, value);
+        }
+        let response = response
+            .body(body)
+            .map_err(|err| Error::Middleware(err.into()))?;
+        Ok(Response::from(response))
+    }
+
+    fn replay(&self, path: &PathBuf, req: Request) -> Result<Response> {
+        let method = req.method().to_string();
+        let url = req.url().to_string();
+        let request_body = body_string(&req);
+
+        let mut interactions = self.interactions.lock().unwrap();
+        if interactions.is_none() {
+            let content = std::fs::read(path).map_err(|err| Error::Middleware(err.into()))?;
+            let cassette = serde_json::from_slice::<Vec<Interaction>>(&content)
+                .map_err(|err| Error::Middleware(err.into()))?;
+            *interactions = Some(cassette);
+        }
+        let interactions = interactions.get_or_insert_with(Vec::new);
+        // each interaction is replayed once, in recording order
+        let position = interactions
+            .iter()
+            .position(|interaction| {
+                interaction.method == method
+                    && interaction.url == url
+                    && interaction.request_body == request_body
+            })
+            .ok_or_else(|| {
+                Error::Middleware(anyhow::anyhow!(
+                    "no recorded interaction for {} {} in {}",
+                    method,
+                    url,
+                    path.display()
+                ))
+            })?;
+        let interaction = interactions.remove(position);
+
+        let mut response = http::Response::builder().status(interaction.status);
+        for (name, value) in interaction.headers.iter() {
+            response = response.header(name, value);
+        }
+        let response = response
+            .body(interaction.response_body)
+            .map_err(|err| Error::Middleware(err.into()))?;
+        Ok(Response::from(response))
+    }
+}
+
+fn body_string(req: &Request) -> Option<String> {
+    req.body()
+        .and_then(|body| body.as_bytes())
+        .map(|body| String::from_utf8_lossy(body).into_owned())
+}
+
+#[async_trait::async_trait]
+impl Middleware for CassetteMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        match &self.mode {
+            CassetteMode::Record(path) => self.record(path, req, extensions, next).await,
+            CassetteMode::Replay(path) => self.replay(path, req),
+        }
+    }
+}
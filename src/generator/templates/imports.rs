@@ -0,0 +1,146 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::generator::types::ModuleInfo;
+
+/// `use` declarations of a generated file.
+///
+/// Imports are deduplicated, the names imported from the same path are merged
+/// (`use serde::{Deserialize, Serialize};`) and the declarations are grouped
+/// as std, external crates and local modules, separated by a blank line.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportCollector {
+    // path -> imported names, an empty path imports a crate (`use serde_json;`)
+    paths: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ImportCollector {
+    pub fn new() -> Self {
+        ImportCollector::default()
+    }
+
+    /// Adds an import given as `use a::b::C;`, `a::b::C` or `use a::{B, C};`
+    pub fn add(&mut self, import: &str) {
+        let import = import.trim();
+        let import = import.strip_prefix("use ").unwrap_or(import);
+        let import = import.strip_suffix(';').unwrap_or(import).trim();
+        if import.is_empty() {
+            return;
+        }
+
+        if let Some((path, names)) = import.split_once("::{") {
+            let names = names.strip_suffix('}').unwrap_or(names);
+            // nested groups are kept as written
+            if !names.contains('{') {
+                let entry = self.paths.entry(path.to_owned()).or_default();
+                for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                    entry.insert(name.to_owned());
+                }
+                return;
+            }
+        }
+
+        let (path, name) = match import.split_once('{') {
+            Some(_) => ("", import),
+            None => import.rsplit_once("::").unwrap_or(("", import)),
+        };
+        self.paths
+            .entry(path.to_owned())
+            .or_default()
+            .insert(name.to_owned());
+    }
+
+    pub fn add_module(&mut self, module: &ModuleInfo) {
+        self.add(&module.to_use());
+    }
+
+    pub fn extend<I: IntoIterator<Item = S>, S: AsRef<str>>(&mut self, imports: I) {
+        for import in imports {
+            self.add(import.as_ref());
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Renders the declarations, one line each and a blank line between groups
+    pub fn render(&self) -> String {
+        let mut groups: [Vec<String>; 3] = Default::default();
+        for (path, names) in self.paths.iter() {
+            if path.is_empty() {
+                for name in names.iter() {
+                    groups[import_group(name)].push(format!("use {};", name));
+                }
+                continue;
+            }
+            let group = &mut groups[import_group(path)];
+            // `self` goes first, as rustfmt orders it
+            let names = names
+                .iter()
+                .filter(|name| name.as_str() == "self")
+                .chain(names.iter().filter(|name| name.as_str() != "self"))
+                .map(|name| name.as_str())
+                .collect::<Vec<&str>>();
+            match names.as_slice() {
+                [name] => group.push(format!("use {}::{};", path, name)),
+                _ => group.push(format!("use {}::{{{}}};", path, names.join(", "))),
+            }
+        }
+
+        groups
+            .iter()
+            .filter(|group| !group.is_empty())
+            .map(|group| {
+                let mut lines = group.clone();
+                lines.sort();
+                lines.join("\n") + "\n"
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+// 0: std, 1: external crates, 2: local modules
+fn import_group(path: &str) -> usize {
+    let root = path
+        .trim_start_matches("::")
+        .split("::")
+        .next()
+        .unwrap_or("");
+    match root {
+        "std" | "core" | "alloc" => 0,
+        "crate" | "self" | "super" => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_and_dedup() {
+        let mut imports = ImportCollector::new();
+        imports.add("use serde::Serialize;");
+        imports.add("use serde::Serialize;");
+        imports.add("use serde::{Deserialize, Serialize};");
+        assert_eq!(imports.render(), "use serde::{Deserialize, Serialize};\n");
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut imports = ImportCollector::new();
+        imports.extend([
+            "use crate::models::Pet;",
+            "use std::collections::HashMap;",
+            "use serde_json;",
+            "use chrono::DateTime;",
+            "use std::fmt::{self, Display};",
+        ]);
+        assert_eq!(
+            imports.render(),
+            "use std::collections::HashMap;\nuse std::fmt::{self, Display};\n\n\
+             use chrono::DateTime;\nuse serde_json;\n\nuse crate::models::Pet;\n"
+        );
+    }
+}
@@ -1,19 +1,32 @@
-use crate::generator::component::object_definition::get_object_name;
+use crate::generator::component::version_conversion::{
+    generate_version_conversions, FieldConversion,
+};
+use crate::generator::path::utils::is_json_content_type;
+use crate::generator::templates::imports::ImportCollector;
 use crate::generator::types::{
-    ModuleInfo, ObjectDatabase, ObjectDefinition, PathDatabase, PropertyDefinition, TypeDefinition,
+    AsyncOperation, ExternalDocs, Method, ModuleInfo, ObjectDatabase, ObjectDefinition,
+    PathDatabase, PathDefinition, PathStyle, PropertyDefinition, QueryParameterDefinition,
+    StructDefinition, TransferMediaType, TypeDefinition, WebsocketDatabase,
+};
+use crate::utils::config::{
+    CircuitBreakerOperation, Config, FallbackResponseType, OutputLayout, SerdeWithAdapter,
+    UnknownFields,
 };
-use crate::utils::config::Config;
 use crate::utils::file::write_filename;
 use crate::utils::name_mapping::convert_name;
 use crate::GeneratorError;
 use askama::Template;
+use convert_case::Casing;
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{debug, warn};
 
 // list of primitive types of Rust language
-pub const RUST_PRIMITIVE_TYPES: [&str; 13] = [
-    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "String",
+pub const RUST_PRIMITIVE_TYPES: [&str; 15] = [
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64",
+    "u128", "String",
 ];
 
 #[derive(Template)]
@@ -38,6 +51,77 @@ pub struct RustTypeTemplate<'a> {
     pub description: &'a str,
 }
 
+#[derive(Debug, Clone)]
+pub struct ConstantDefinition {
+    pub name: String,
+    pub value: String,
+    pub description: String,
+}
+
+#[derive(Template)]
+#[template(path = "rust/consts.j2", escape = "none")]
+pub struct RustConstsTemplate {
+    pub content_types: Vec<ConstantDefinition>,
+    pub paths: Vec<ConstantDefinition>,
+}
+
+pub struct ConversionField {
+    pub name: String,
+    pub expression: String,
+}
+
+pub struct ConversionDefinition {
+    pub source: String,
+    pub target: String,
+    pub fallible: bool,
+    pub fields: Vec<ConversionField>,
+}
+
+#[derive(Template)]
+#[template(path = "rust/conversions.j2", escape = "none")]
+pub struct RustConversionsTemplate {
+    pub conversions: Vec<ConversionDefinition>,
+    // TryFrom and TryInto are only in the prelude since 2021
+    pub edition_2018: bool,
+}
+
+#[derive(Template)]
+#[template(path = "rust/example_test.j2", escape = "none")]
+pub struct RustExampleTestTemplate<'a> {
+    pub module_name: &'a str,
+    pub name: &'a str,
+    pub example: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "rust/query_parameters.j2", escape = "none")]
+pub struct RustQueryParametersTemplate<'a> {
+    pub struct_variable_name: &'a str,
+    pub vector_name: &'a str,
+    pub parameters: &'a [QueryParameterDefinition],
+    pub has_repeated_parameters: bool,
+}
+
+#[derive(Template)]
+#[template(path = "rust/websocket_stream.j2", escape = "none")]
+pub struct RustWebsocketStreamTemplate<'a> {
+    pub name: &'a str,
+    pub response_type: &'a str,
+}
+
+#[derive(Template)]
+#[template(path = "rust/websocket_function.j2", escape = "none")]
+pub struct RustWebsocketFunctionTemplate<'a> {
+    pub name: &'a str,
+    pub parameters: Vec<String>,
+    // names of `parameters`, passed on to the url helper
+    pub argument_names: Vec<String>,
+    pub stream_name: &'a str,
+    pub query_parameters_code: &'a str,
+    pub path_format_string: &'a str,
+    pub path_arguments: Vec<String>,
+}
+
 #[derive(Debug, PartialOrd, PartialEq, Eq, Clone)]
 pub struct Field {
     pub annotations: Vec<String>,
@@ -56,6 +140,8 @@ impl Ord for Field {
 #[template(path = "rust/struct.j2", escape = "none")]
 pub struct RustStructTemplate<'a> {
     pub imports: Vec<String>,
+    pub serde_as: bool,
+    pub deny_unknown_fields: bool,
     pub derivations: Vec<&'a str>,
     pub description: &'a str,
     pub name: &'a str,
@@ -70,14 +156,45 @@ pub struct RustBuilderStructTemplate<'a> {
     pub description: &'a str,
     pub name: &'a str,
     pub response_type: &'a str,
+    pub default_response_type: Option<String>,
+    pub response_content_types: Vec<String>,
+    pub default_accept: Option<String>,
+    pub is_head: bool,
+    pub no_content: bool,
+    pub fallback_reader: Option<&'a str>,
+    pub row_format: Option<&'a str>,
+    pub row_type: Option<String>,
+    pub ndjson_request: bool,
+    // binary and ndjson bodies, the builder accepts a stream
+    pub streaming_request: bool,
+    pub streaming_content_type: Option<String>,
+    // constant of the content type of JSON Patch bodies, the builder collects operations
+    pub json_patch_content_type: Option<String>,
+    pub idempotent: bool,
+    pub anonymous: bool,
     pub builder_name: &'a str,
+    pub operation_id: &'a str,
     pub fields: Vec<Field>,
+    pub typestate: bool,
+    pub send_futures: bool,
+    pub content_checksum: Option<&'a str>,
+    pub client_name: &'a str,
+    pub required_fields: Vec<Field>,
     pub method: &'a str,
+    pub method_expression: &'a str,
     pub path: &'a str,
-    pub path_fields: Vec<Field>,
-    pub query_fields: Vec<Field>,
+    pub path_constant: &'a str,
+    // `{name}` placeholders of the path and the expressions replacing them
+    pub path_fields: Vec<(String, String)>,
+    pub query_parameters: &'a [QueryParameterDefinition],
     pub body_fields: Vec<Field>,
     pub body_request: Option<TypeDefinition>,
+    pub body_unknown_fields: bool,
+    pub async_operation: Option<AsyncOperationTemplate>,
+    // responses are looked up in and stored into the response cache of the client
+    pub cached: bool,
+    // `Option<CircuitBreakerSettings>` expression of the operation, no circuit breaker without
+    pub circuit_breaker: Option<String>,
 }
 
 #[derive(Template)]
@@ -85,18 +202,186 @@ pub struct RustBuilderStructTemplate<'a> {
 pub struct CargoTemplate<'a> {
     pub name: &'a str,
     pub version: &'a str,
+    pub edition: &'a str,
+    pub rust_version: Option<&'a str>,
+    pub tls_feature: Option<&'a str>,
+    pub workspace_member: bool,
+    pub serde_with: bool,
+    pub decimal_dependency: Option<&'a str>,
+    pub arbitrary_precision: bool,
+    // websocket operations were generated, their dependencies are optional
+    pub websocket: bool,
+    pub response_cache: bool,
+}
+
+// replaces the version of a dependency, keeping its other keys
+fn with_dependency_version(specification: &str, version: &str) -> String {
+    if version.starts_with('{') {
+        return version.to_owned();
+    }
+    match specification.find("version = \"") {
+        Some(start) => {
+            let version_start = start + "version = \"".len();
+            match specification[version_start..].find('"') {
+                Some(length) => format!(
+                    "{}{}{}",
+                    &specification[..version_start],
+                    version,
+                    &specification[version_start + length..]
+                ),
+                None => format!("\"{}\"", version),
+            }
+        }
+        None => format!("\"{}\"", version),
+    }
+}
+
+/// Rewrites the entries of all dependency sections of a Cargo.toml, `rewrite` gets the name
+/// and the specification and returns the new entry or None to remove it
+fn rewrite_dependencies(cargo: &str, rewrite: impl Fn(&str, &str) -> Option<String>) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut in_dependencies = false;
+    // entries may span several lines until their brackets are closed
+    let mut entry: Option<(String, String)> = None;
+    let is_complete = |specification: &str| {
+        specification.matches(['[', '{']).count() == specification.matches([']', '}']).count()
+    };
+
+    for line in cargo.lines() {
+        if let Some((name, mut specification)) = entry.take() {
+            specification.push('\n');
+            specification.push_str(line);
+            match is_complete(&specification) {
+                true => lines.extend(rewrite(&name, &specification)),
+                false => entry = Some((name, specification)),
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            in_dependencies = line.ends_with("dependencies]");
+        }
+        match line.split_once(" = ") {
+            Some((name, specification)) if in_dependencies && !name.starts_with('#') => {
+                match is_complete(specification) {
+                    true => lines.extend(rewrite(name, specification)),
+                    false => entry = Some((name.to_owned(), specification.to_owned())),
+                }
+            }
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines.join("\n") + "\n"
 }
 
-pub fn populate_client_files(output_dir: &PathBuf, config: &Config) -> Result<(), GeneratorError> {
+fn has_dependency(cargo: &str, name: &str) -> bool {
+    cargo.contains(&format!("\n{} = ", name))
+}
+
+/// Applies the `dependencies` of the config to the rendered Cargo.toml: existing entries
+/// (of all dependency sections) get the new version or are removed, others are added
+/// to `[dependencies]`
+fn apply_dependency_overrides(cargo: &str, overrides: &BTreeMap<String, Option<String>>) -> String {
+    if overrides.is_empty() {
+        return cargo.to_owned();
+    }
+    for (name, _) in overrides.iter().filter(|(_, version)| version.is_none()) {
+        if !has_dependency(cargo, name) {
+            warn!("Dependency {} is not in the generated Cargo.toml", name);
+        }
+    }
+    let added = overrides
+        .iter()
+        .filter(|(name, _)| !has_dependency(cargo, name))
+        .filter_map(|(name, version)| {
+            version
+                .as_ref()
+                .map(|version| format!("{} = {}\n", name, with_dependency_version("", version)))
+        })
+        .collect::<String>();
+    let cargo = rewrite_dependencies(cargo, |name, specification| match overrides.get(name) {
+        Some(Some(version)) => Some(format!(
+            "{} = {}",
+            name,
+            with_dependency_version(specification, version)
+        )),
+        Some(None) => None,
+        None => Some(format!("{} = {}", name, specification)),
+    });
+    // added dependencies are listed first
+    cargo.replacen("[dependencies]\n", &format!("[dependencies]\n{}", added), 1)
+}
+
+// part of an inline table starting with `key = `, up to the matching bracket for arrays
+fn table_value<'a>(specification: &'a str, key: &str) -> Option<&'a str> {
+    // the leading space skips `default-features` when looking for `features`
+    let start = specification.find(&format!(" {} = ", key))? + 1;
+    let value = &specification[start..];
+    match value.find('[') {
+        Some(open) if open == key.len() + 3 => Some(&value[..=open + value[open..].find(']')?]),
+        _ => Some(value.split([',', '}']).next()?.trim_end()),
+    }
+}
+
+/// Dependencies inherited from the workspace, only features and optional are kept
+fn inherit_workspace_dependencies(cargo: &str) -> String {
+    rewrite_dependencies(cargo, |name, specification| {
+        let keys = ["features", "optional"]
+            .iter()
+            .filter(|key| specification.starts_with('{'))
+            .filter_map(|key| table_value(specification, key))
+            .collect::<Vec<&str>>();
+        Some(match keys.is_empty() {
+            true => format!("{}.workspace = true", name),
+            false => format!("{} = {{ workspace = true, {} }}", name, keys.join(", ")),
+        })
+    })
+}
+
+// generated files are collapsed in diffs and left out of the language statistics
+const GIT_ATTRIBUTES: &str = "* linguist-generated=true\n";
+
+const EDITOR_CONFIG: &str = "root = true
+
+[*]
+charset = utf-8
+end_of_line = lf
+insert_final_newline = true
+indent_style = space
+
+[*.rs]
+indent_size = 4
+";
+
+pub fn populate_client_files(
+    output_dir: &PathBuf,
+    config: &Config,
+    object_database: &ObjectDatabase,
+    websocket: bool,
+) -> Result<(), GeneratorError> {
     // producing Cargo.toml
     let cargo_target_file = output_dir.join("Cargo.toml");
 
     let template = CargoTemplate {
         name: config.project_metadata.name.as_str(),
         version: config.project_metadata.version.as_str(),
+        edition: config.project_metadata.edition.as_str(),
+        rust_version: config.project_metadata.rust_version.as_deref(),
+        tls_feature: config.tls.feature(),
+        workspace_member: config.workspace_member,
+        serde_with: config.serde_with.is_enabled(),
+        decimal_dependency: config.decimal.map(|decimal_type| decimal_type.dependency()),
+        // serde_json only reads i128/u128 beyond 64 bits with arbitrary precision
+        arbitrary_precision: config.decimal.is_some() || uses_128_bit_integers(object_database),
+        websocket: websocket && config.stages.clients,
+        response_cache: config.response_cache.is_enabled() && config.stages.clients,
     }
     .render()
     .unwrap();
+    let template = apply_dependency_overrides(&template, &config.dependencies);
+    let template = match config.workspace_member {
+        true => inherit_workspace_dependencies(&template),
+        false => template,
+    };
 
     write_filename(&cargo_target_file, &template)?;
 
@@ -105,6 +390,21 @@ pub fn populate_client_files(output_dir: &PathBuf, config: &Config) -> Result<()
     let template = RustGitIgnoreTemplate {}.render().unwrap();
     write_filename(&git_ignore_file, &template)?;
 
+    if config.generated_headers {
+        write_filename(&output_dir.join(".gitattributes"), GIT_ATTRIBUTES)?;
+        write_filename(&output_dir.join(".editorconfig"), EDITOR_CONFIG)?;
+    }
+
+    // models only, the lib.rs of the client is not rendered
+    if !config.stages.clients {
+        let mut lib_code = String::from("pub mod models;\n");
+        if !config.version_conversions.is_empty() {
+            lib_code.push_str("pub mod conversions;\n");
+        }
+        write_filename(&output_dir.join("src/lib.rs"), &lib_code)?;
+        return Ok(());
+    }
+
     // producing other files
     let files = vec![
         (
@@ -119,6 +419,26 @@ pub fn populate_client_files(output_dir: &PathBuf, config: &Config) -> Result<()
             embed_file::embed_string!("embedded/rust/client.rs"),
             "src/client.rs",
         ),
+        (
+            embed_file::embed_string!("embedded/rust/vcr.rs"),
+            "src/vcr.rs",
+        ),
+        (
+            embed_file::embed_string!("embedded/rust/json_patch.rs"),
+            "src/json_patch.rs",
+        ),
+        (
+            embed_file::embed_string!("embedded/rust/links.rs"),
+            "src/links.rs",
+        ),
+        (
+            embed_file::embed_string!("embedded/rust/polling.rs"),
+            "src/polling.rs",
+        ),
+        (
+            embed_file::embed_string!("embedded/rust/circuit_breaker.rs"),
+            "src/circuit_breaker.rs",
+        ),
     ];
 
     for (content, file_name) in files {
@@ -126,6 +446,24 @@ pub fn populate_client_files(output_dir: &PathBuf, config: &Config) -> Result<()
         write_filename(&target_file, &content)?;
     }
 
+    let template = RustAuthProviderTemplate {
+        send_futures: config.send_futures,
+        async_trait: match config.send_futures {
+            true => SEND_ASYNC_TRAIT,
+            false => LOCAL_ASYNC_TRAIT,
+        },
+    }
+    .render()
+    .unwrap();
+    write_filename(&output_dir.join("src/auth_provider.rs"), &template)?;
+
+    if config.response_cache.is_enabled() {
+        write_filename(
+            &output_dir.join("src/response_cache.rs"),
+            &embed_file::embed_string!("embedded/rust/response_cache.rs"),
+        )?;
+    }
+
     Ok(())
 }
 
@@ -134,8 +472,35 @@ pub fn populate_client_files(output_dir: &PathBuf, config: &Config) -> Result<()
 pub struct RustClientFunctionTemplate<'a> {
     pub name: &'a str,
     pub description: String,
-    pub required_properties: Vec<PropertyDefinition>,
+    pub required_properties: Vec<&'a PropertyDefinition>,
     pub builder_name: String,
+    pub typestate: bool,
+    pub client_expression: &'a str,
+    pub beta: bool,
+}
+
+#[derive(Template)]
+#[template(path = "rust/sub_client.j2", escape = "none")]
+pub struct RustSubClientTemplate<'a> {
+    pub tag: &'a str,
+    pub name: &'a str,
+    pub accessor: &'a str,
+    pub client_name: &'a str,
+    pub functions: &'a str,
+}
+
+// futures of the runtime traits are `Send` except on wasm32, where nothing is
+const SEND_ASYNC_TRAIT: &str =
+    "#[cfg_attr(not(target_arch = \"wasm32\"), async_trait::async_trait)]
+#[cfg_attr(target_arch = \"wasm32\", async_trait::async_trait(?Send))]";
+// without `send_futures` no future of the client is `Send`
+const LOCAL_ASYNC_TRAIT: &str = "#[async_trait::async_trait(?Send)]";
+
+#[derive(Template)]
+#[template(path = "rust/auth_provider.j2", escape = "none")]
+pub struct RustAuthProviderTemplate<'a> {
+    pub send_futures: bool,
+    pub async_trait: &'a str,
 }
 
 #[derive(Template)]
@@ -145,7 +510,47 @@ pub struct RustClientInitTemplate<'a> {
     pub client_name: &'a str,
     pub server_url: &'a str,
     pub user_agent: &'a str,
+    pub default_headers: Vec<(String, String)>,
     pub version: &'a str,
+    pub batch_concurrency: usize,
+    pub circuit_breaker_failure_threshold: u32,
+    pub circuit_breaker_reset_timeout_secs: u64,
+    pub models_crate: Option<&'a str>,
+    pub conversions: bool,
+    pub send_futures: bool,
+    // shared pointer and lock of the client internals
+    pub shared: &'a str,
+    pub lock: &'a str,
+    pub crate_name: &'a str,
+    pub prelude_models: Vec<String>,
+    pub websocket: bool,
+    pub response_cache: bool,
+    pub profiles: Vec<ClientProfileTemplate>,
+    // prefix of the environment variables overriding the profile, e.g. `PETSTORE`
+    pub env_prefix: String,
+}
+
+/// Variant of the generated `ClientProfile`, strings are quoted rust literals
+#[derive(Clone, Debug)]
+pub struct ClientProfileTemplate {
+    pub variant: String,
+    pub name: String,
+    pub base_url: Option<String>,
+    pub timeout_secs: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+    pub read_timeout_secs: Option<u64>,
+    pub proxy: Option<String>,
+}
+
+/// Polling of an operation answered with `202 Accepted`, strings are quoted rust literals
+#[derive(Clone, Debug)]
+pub struct AsyncOperationTemplate {
+    // response type of the status operation, `serde_json::Value` without one
+    pub status_type: String,
+    pub status_field: String,
+    pub terminal_states: Vec<String>,
+    pub poll_interval: u64,
+    pub max_attempts: Option<u32>,
 }
 
 #[derive(Clone, Debug)]
@@ -156,20 +561,71 @@ pub struct BuilderInfo {
 }
 
 pub fn generate_rust_client_code(
-    paths: Vec<crate::generator::types::PathDefinition>,
+    paths: &[Arc<PathDefinition>],
     config: &Config,
     object_database: &ObjectDatabase,
-) -> (String, Vec<BuilderInfo>) {
+) -> (String, String, Vec<BuilderInfo>) {
     let mut imports = HashSet::new();
 
     let mut client_code = String::new();
     let mut function_code = String::new();
+    // functions of the sub-clients by tag
+    let mut sub_client_functions: BTreeMap<String, String> = BTreeMap::new();
 
     let mut builders: Vec<BuilderInfo> = vec![];
 
     for path in paths.iter() {
         let required_properties = path.get_required_properties();
-        let response_type = extract_default_rust_response_type(path.extract_response_type());
+        // HEAD responses carry no body, only status and headers are returned
+        let is_head = path.method == Method::HEAD;
+        // 204 and other responses documented without content return `()`
+        let no_content = !is_head && path.has_empty_response();
+        // csv and ndjson bodies are streamed, the builder gets a send_rows helper
+        let (row_format, row_type) = match path.extract_row_response() {
+            Some(row_response) => (
+                match row_response {
+                    TransferMediaType::TextCsv(_) => Some("csv"),
+                    _ => Some("ndjson"),
+                },
+                row_response.type_definition().map(|row_type| {
+                    extract_default_rust_response_type(Some(row_type.clone()), config)
+                }),
+            ),
+            None => (None, None),
+        };
+        // with several documented content types the json one is requested unless
+        // the user selects another one with `accept`
+        let response_content_types = path.extract_response_content_types();
+        // responses without schema are read according to their content type
+        let mut fallback_reader = None;
+        let response_type = match (is_head || no_content, row_format) {
+            (true, _) => "()".to_owned(),
+            (false, Some(_)) => "crate::client::ByteStream".to_owned(),
+            (false, None) => match path.extract_response_type() {
+                Some(response) => extract_default_rust_response_type(Some(response), config),
+                None => {
+                    let fallback = fallback_response_type(&response_content_types, config);
+                    fallback_reader = fallback.reader();
+                    fallback.rust_type().to_owned()
+                }
+            },
+        };
+        let default_accept = match response_content_types.len() > 1 {
+            true => response_content_types
+                .iter()
+                .find(|content_type| is_json_content_type(content_type))
+                .map(|content_type| content_type_constant_path(content_type)),
+            false => None,
+        };
+        let response_content_types = response_content_types
+            .iter()
+            .map(|content_type| content_type_constant_path(content_type))
+            .collect::<Vec<String>>();
+        let default_response_type = path
+            .extract_default_response_type()
+            .map(|default_response| {
+                extract_default_rust_response_type(Some(default_response), config)
+            });
         let scope: Vec<String> = vec![];
         let builder_name = format!("{}Builder", convert_name(&path.name));
 
@@ -178,7 +634,12 @@ pub fn generate_rust_client_code(
         description.push_str("\n");
         description.push_str("\n");
         description.push_str(
-            format!("Sends a `{:?}` request to `{}`\n\n", path.method, path.url).as_str(),
+            format!(
+                "Sends a `{}` request to `{}`\n\n",
+                path.method.to_string(),
+                path.url
+            )
+            .as_str(),
         );
         description.push_str("Arguments:\n");
         for property in required_properties.iter() {
@@ -195,13 +656,38 @@ pub fn generate_rust_client_code(
             );
         }
 
+        if path.beta {
+            description.push_str(&format!(
+                "\n**Beta**: this operation may change or be removed, it requires the `{}` feature\n",
+                UNSTABLE_FEATURE
+            ));
+        }
+        let sub_client_tag = path.tag.as_ref().filter(|_| config.sub_clients);
+        if config.doc_examples {
+            if let Some(example) = doc_example(path, &required_properties, sub_client_tag, config) {
+                description.push_str("\n");
+                description.push_str(&example);
+            }
+        }
         let function = RustClientFunctionTemplate {
             name: &path.name,
             description: fix_rust_description("", &description),
             required_properties,
             builder_name: builder_name.clone(),
+            typestate: config.typestate_builders,
+            client_expression: match sub_client_tag {
+                Some(_) => "self.client",
+                None => "self",
+            },
+            beta: path.beta,
         };
-        function_code.push_str(&function.render().unwrap());
+        match sub_client_tag {
+            Some(tag) => sub_client_functions
+                .entry(tag.clone())
+                .or_default()
+                .push_str(&function.render().unwrap()),
+            None => function_code.push_str(&function.render().unwrap()),
+        }
 
         let mut builder_imports = HashSet::new();
 
@@ -213,19 +699,26 @@ pub fn generate_rust_client_code(
         // generating builder code
         let required_properties = path.get_required_properties();
         let optional_properties = path.get_optional_properties();
+        let required_names = required_properties
+            .iter()
+            .map(|property| property.name.clone())
+            .collect::<Vec<String>>();
         let mut fields = vec![];
         let mut processed_builder_fields = vec![];
         let mut description = String::new();
         description.push_str(
             format!(
-                "Builder used to sends a `{:?}` request to `{}`\n\n",
-                path.method, path.url
+                "Builder used to sends a `{}` request to `{}`\n\n",
+                path.method.to_string(),
+                path.url
             )
             .as_str(),
         );
         description.push_str("Arguments:\n");
         // we emit client code
         description.push_str("- `client`: The client used to send the request\n");
+        // the generated client keeps its state behind an Arc, so holding it by
+        // value keeps builders cheap to create and to move across tasks
         fields.push(Field {
             annotations: vec![], //"#[builder(setter)]".to_string()
             description: fix_rust_description("", "The client used to send the request"),
@@ -236,7 +729,13 @@ pub fn generate_rust_client_code(
 
         for fields_group in [required_properties, optional_properties].iter() {
             for property in fields_group.iter() {
-                let annotations = vec![];
+                let mut annotations = vec![];
+                if property.deprecated {
+                    annotations.push(
+                        "#[builder_setter_attr(deprecated = \"deprecated in the API spec\")]"
+                            .to_string(),
+                    );
+                }
                 let name = property.name.clone();
                 if processed_builder_fields.contains(&name) {
                     continue;
@@ -266,50 +765,114 @@ pub fn generate_rust_client_code(
                     ),
                     modifier: "pub".to_string(),
                     name: property.name.clone(),
-                    typ: fix_type_name_property(&property.type_name),
+                    typ: builder_field_type(property, config),
                 };
                 fields.push(field);
                 processed_builder_fields.push(property.name.clone());
             }
         }
-        let builder_imports: Vec<ModuleInfo> = builder_imports.iter().cloned().collect();
+        let builder_imports: Vec<ModuleInfo> = builder_imports
+            .iter()
+            .map(|import| ModuleInfo::new(&model_path(&import.path, config), &import.name))
+            .collect();
         let body_fields: Vec<Field> = path
             .extract_body_properties()
-            .iter()
-            .map(|p| property_definition_to_field(&p.1))
+            .into_iter()
+            .map(|(_, property)| property_definition_to_field(property, config))
             .collect();
-        let body_request = path.get_request_type();
+        let required_fields = fields
+            .iter()
+            .filter(|field| required_names.contains(&field.name))
+            .cloned()
+            .collect::<Vec<Field>>();
+        let body_request = path.get_request_type().map(|mut body_request| {
+            if let Some(ref mut module) = body_request.module {
+                module.path = model_path(&module.path, config);
+            }
+            body_request
+        });
+
+        let mut path_fields = path
+            .path_parameters
+            .parameters_struct
+            .properties
+            .values()
+            .map(|property| {
+                let (style, explode) = path
+                    .path_parameters
+                    .url_template
+                    .parameter_style(&property.real_name);
+                (
+                    format!("{{{}}}", property.real_name),
+                    path_value_expression(property, style, explode, config),
+                )
+            })
+            .collect::<Vec<(String, String)>>();
+        path_fields.sort();
 
         let builder_template = RustBuilderStructTemplate {
             imports: builder_imports.clone(),
-            derivations: vec!["Builder", "Debug", "Default"],
+            // typestate builders are only created with `new`, which takes the required parameters
+            derivations: match config.typestate_builders {
+                true => vec!["Builder", "Debug"],
+                false => vec!["Builder", "Debug", "Default"],
+            },
             description: &fix_rust_description("", &description),
             name: &convert_name(&path.name),
             builder_name: &builder_name,
+            operation_id: &path.name,
             response_type: &response_type,
+            default_response_type,
+            response_content_types,
+            default_accept,
+            is_head,
+            no_content,
+            fallback_reader,
+            row_format,
+            row_type,
+            ndjson_request: path.has_ndjson_request_body(),
+            streaming_request: path.has_streaming_request_body(),
+            streaming_content_type: path
+                .streaming_request_content_type()
+                .map(content_type_constant_path),
+            json_patch_content_type: path
+                .json_patch_request_content_type()
+                .map(content_type_constant_path),
+            idempotent: path.idempotent,
+            anonymous: path.anonymous,
             fields,
+            typestate: config.typestate_builders,
+            send_futures: config.send_futures,
+            content_checksum: path
+                .content_checksum
+                .map(|content_checksum| content_checksum.rust_expression()),
+            client_name: &config.project_metadata.client_name,
+            required_fields,
             method: &path.method.to_string(),
+            method_expression: &rust_method_expression(&path.method),
             path: &path.url,
-            path_fields: path
-                .path_parameters
-                .parameters_struct
-                .properties
-                .clone()
-                .into_iter()
-                .map(|p| property_definition_to_field(&p.1))
-                .collect(),
-            query_fields: path
-                .query_parameters
-                .query_struct
-                .properties
-                .clone()
-                .into_iter()
-                .map(|p| property_definition_to_field(&p.1))
-                .collect(),
+            path_constant: &format!("crate::consts::{}", path_constant_name(&path.name)),
+            path_fields,
+            query_parameters: &path.query_parameters.parameters,
             body_fields,
             body_request,
+            body_unknown_fields: match path.request_body.as_deref() {
+                Some(ObjectDefinition::Struct(struct_definition)) => {
+                    captures_unknown_fields(struct_definition, config)
+                }
+                _ => false,
+            },
+            async_operation: path
+                .async_operation
+                .as_ref()
+                .map(|async_operation| async_operation_template(async_operation, paths, config)),
+            cached: path.cached && config.response_cache.is_enabled(),
+            circuit_breaker: circuit_breaker_settings(path.circuit_breaker.as_ref(), config),
         };
-        let builder_code = builder_template.render().unwrap();
+        let mut builder_code = builder_template.render().unwrap();
+        if path.beta {
+            builder_code = unstable_module(&path.name, &builder_code);
+        }
         builders.push(BuilderInfo {
             name: path.name.clone(),
             code: builder_code,
@@ -317,10 +880,29 @@ pub fn generate_rust_client_code(
         });
     }
     client_code.push_str(&function_code);
-    (client_code, builders)
+
+    let client_name = &config.project_metadata.client_name;
+    let mut sub_client_code = String::new();
+    for (tag, functions) in sub_client_functions.iter() {
+        let accessor = tag.to_case(convert_case::Case::Snake);
+        let name = format!("{}Client", tag.to_case(convert_case::Case::Pascal));
+        client_code.push_str(&format!(
+            "\n/// Operations of the `{}` tag\npub fn {}(&self) -> {}<'_> {{\n    {} {{ client: self }}\n}}\n",
+            tag, accessor, name, name
+        ));
+        let sub_client = RustSubClientTemplate {
+            tag,
+            name: &name,
+            accessor: &accessor,
+            client_name,
+            functions,
+        };
+        sub_client_code.push_str(&sub_client.render().unwrap());
+    }
+    (client_code, sub_client_code, builders)
 }
 
-fn property_definition_to_field(property: &PropertyDefinition) -> Field {
+fn property_definition_to_field(property: &PropertyDefinition, config: &Config) -> Field {
     Field {
         annotations: vec![],
         description: fix_rust_description(
@@ -332,21 +914,95 @@ fn property_definition_to_field(property: &PropertyDefinition) -> Field {
         ),
         modifier: "pub".to_string(),
         name: property.name.clone(),
-        typ: fix_type_name_property(&property.type_name),
+        typ: fix_type_name_property(&property.type_name, config),
+    }
+}
+
+// value of a path parameter of the builder, percent-encoded and written in the `style` of the spec
+fn path_value_expression(
+    property: &PropertyDefinition,
+    style: PathStyle,
+    explode: bool,
+    config: &Config,
+) -> String {
+    // optional path parameters are Option in the builder, both are iterated
+    let field_type = builder_field_type(property, config);
+    // the values are encoded before styling, the separators of the style are kept
+    let values = match field_type.starts_with("Vec<") || field_type.starts_with("Option<") {
+        true => format!(
+            "self.{}.iter().map(|value| crate::client::encode_path(&value.to_string())).collect::<Vec<String>>()",
+            property.name
+        ),
+        false => format!(
+            "vec![crate::client::encode_path(&self.{}.to_string())]",
+            property.name
+        ),
+    };
+    match style {
+        PathStyle::Simple => format!("{}.join(\",\")", values),
+        _ => format!(
+            "crate::client::styled_path_value(\"{}\", \"{}\", &{}, {})",
+            style.name(),
+            property.real_name,
+            values,
+            explode
+        ),
     }
 }
 
-pub fn fix_type_name_property(property: &str) -> String {
+// optional fields are Option like in the models, except for collections
+// where an empty value means unset
+fn builder_field_type(property: &PropertyDefinition, config: &Config) -> String {
+    let type_name = fix_type_name_property(&property.type_name, config);
+    if property.required || type_name.starts_with("Vec<") || type_name.starts_with("Map<") {
+        return type_name;
+    }
+    format!("Option<{}>", type_name)
+}
+
+pub fn fix_type_name_property(property: &str, config: &Config) -> String {
     if property.starts_with("crate::") {
-        return property.to_string();
+        return model_path(property, config);
     }
     if RUST_PRIMITIVE_TYPES.contains(&property) {
         return property.to_string();
     }
     if property.starts_with("models::") {
-        return format!("crate::{}", property);
+        return format!("{}{}", config.model_path_prefix, property);
+    }
+    return model_path(property, config);
+}
+
+/// Replaces `crate::` of the models referenced in `type_name` (also inside generics)
+/// by the configured `model_path_prefix`
+pub fn model_path(type_name: &str, config: &Config) -> String {
+    if config.model_path_prefix == "crate::" {
+        return type_name.to_string();
     }
-    return property.to_string();
+    type_name.replace(
+        "crate::models::",
+        &format!("{}models::", config.model_path_prefix),
+    )
+}
+
+/// Cargo feature of the generated crate enabling the beta operations
+pub const UNSTABLE_FEATURE: &str = "unstable-endpoints";
+
+// the builder of a beta operation is only compiled with the unstable feature
+fn unstable_module(operation_name: &str, code: &str) -> String {
+    let module_name = format!(
+        "unstable_{}",
+        operation_name
+            .replace("::", "_")
+            .replace('.', "_")
+            .to_case(convert_case::Case::Snake)
+    );
+    format!(
+        "#[cfg(feature = \"{feature}\")]\nmod {module} {{\n    use super::*;\n\n{code}}}\n#[cfg(feature = \"{feature}\")]\npub use {module}::*;\n",
+        feature = UNSTABLE_FEATURE,
+        module = module_name,
+        code = code
+    )
 }
 
 pub fn fix_rust_description(ident: &str, description: &str) -> String {
@@ -366,28 +1022,318 @@ pub fn fix_rust_description(ident: &str, description: &str) -> String {
     }
 }
 
-pub fn extract_default_rust_response_type(optional_response: Option<TypeDefinition>) -> String {
+// placeholder argument of a doc example, None for types without an obvious value
+fn example_argument(property: &PropertyDefinition) -> Option<String> {
+    let example = property.example.as_ref();
+    match property.type_name.as_str() {
+        "String" => Some(format!(
+            "{:?}.to_owned()",
+            example
+                .and_then(|example| example.as_str())
+                .unwrap_or(&property.real_name)
+        )),
+        "bool" => Some(
+            example
+                .and_then(|example| example.as_bool())
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        "f32" | "f64" => Some(format!(
+            "{:?}",
+            example
+                .and_then(|example| example.as_f64())
+                .unwrap_or_default()
+        )),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            Some(
+                example
+                    .and_then(|example| example.as_u64())
+                    .unwrap_or_default()
+                    .to_string(),
+            )
+        }
+        type_name if type_name.starts_with("Vec<") => Some("vec![]".to_owned()),
+        _ => None,
+    }
+}
+
+/// `# Examples` section sending the request with placeholder arguments, None when
+/// a required argument has no placeholder
+fn doc_example(
+    path: &PathDefinition,
+    required_properties: &[&PropertyDefinition],
+    sub_client_tag: Option<&String>,
+    config: &Config,
+) -> Option<String> {
+    let arguments = required_properties
+        .iter()
+        .map(|property| example_argument(property))
+        .collect::<Option<Vec<String>>>()?;
+    let crate_name = config.project_metadata.name.replace('-', "_");
+    let client = match sub_client_tag {
+        Some(tag) => format!("client.{}()", tag.to_case(convert_case::Case::Snake)),
+        None => "client".to_owned(),
+    };
+    let mut example = String::from("# Examples\n\n```no_run\n");
+    example.push_str(&format!(
+        "# async fn example() -> Result<(), {}::Error> {{\n",
+        crate_name
+    ));
+    example.push_str(&format!(
+        "let client = {}::{}Builder::new()\n    .base_url(url::Url::parse(\"http://localhost:8080\").unwrap())\n    .build();\n",
+        crate_name, config.project_metadata.client_name
+    ));
+    example.push_str(&format!(
+        "let response = {}.{}({}).send().await?;\n",
+        client,
+        path.name,
+        arguments.join(", ")
+    ));
+    example.push_str("# Ok(())\n# }\n```\n");
+    Some(example)
+}
+
+pub fn extract_default_rust_response_type(
+    optional_response: Option<TypeDefinition>,
+    config: &Config,
+) -> String {
     match optional_response {
         Some(response) => {
             let name = response.name.clone();
             if !name.starts_with("crate::") {
-                format!("crate::{}", name)
+                model_path(&format!("crate::{}", name), config)
             } else {
-                name
+                model_path(&name, config)
             }
         }
-        None => "serde_json:Value".to_string(),
+        None => config.fallback_response_type.rust_type().to_string(),
     }
 }
 
+// settings of an operation overriding the defaults of the client
+fn circuit_breaker_settings(
+    operation: Option<&CircuitBreakerOperation>,
+    config: &Config,
+) -> Option<String> {
+    let operation = match operation {
+        Some(operation) if !operation.enabled => return None,
+        Some(operation)
+            if operation.failure_threshold.is_some() || operation.reset_timeout_secs.is_some() =>
+        {
+            operation
+        }
+        _ => return Some("None".to_owned()),
+    };
+    Some(format!(
+        "Some(crate::circuit_breaker::CircuitBreakerSettings {{ failure_threshold: {}, reset_timeout: std::time::Duration::from_secs({}) }})",
+        operation
+            .failure_threshold
+            .unwrap_or(config.circuit_breaker.failure_threshold),
+        operation
+            .reset_timeout_secs
+            .unwrap_or(config.circuit_breaker.reset_timeout_secs),
+    ))
+}
+
+fn async_operation_template(
+    async_operation: &AsyncOperation,
+    paths: &[Arc<PathDefinition>],
+    config: &Config,
+) -> AsyncOperationTemplate {
+    let status_type = async_operation
+        .status_function
+        .as_ref()
+        .and_then(|status_function| paths.iter().find(|path| &path.name == status_function))
+        .and_then(|status_path| status_path.extract_response_type())
+        .map_or("serde_json::Value".to_owned(), |status_type| {
+            extract_default_rust_response_type(Some(status_type), config)
+        });
+    AsyncOperationTemplate {
+        status_type,
+        status_field: format!("{:?}", async_operation.status_field),
+        terminal_states: async_operation
+            .terminal_states
+            .iter()
+            .map(|terminal_state| format!("{:?}", terminal_state))
+            .collect(),
+        poll_interval: async_operation.poll_interval,
+        max_attempts: async_operation.max_attempts,
+    }
+}
+
+/// Type of successful responses without schema: the configured fallback for json
+/// (or undocumented) content, `String` for text and `bytes::Bytes` for other content
+fn fallback_response_type(content_types: &[String], config: &Config) -> FallbackResponseType {
+    if content_types.is_empty()
+        || content_types
+            .iter()
+            .any(|content_type| is_json_content_type(content_type))
+    {
+        return config.fallback_response_type;
+    }
+    match content_types
+        .iter()
+        .all(|content_type| content_type.starts_with("text/"))
+    {
+        true => FallbackResponseType::String,
+        false => FallbackResponseType::Bytes,
+    }
+}
+
+pub fn path_constant_name(operation_name: &str) -> String {
+    format!(
+        "{}_PATH",
+        operation_name
+            .replace("::", "_")
+            .to_case(convert_case::Case::UpperSnake)
+    )
+}
+
+/// Module of a websocket operation in `src/websocket`
+pub fn websocket_module_name(operation_name: &str) -> String {
+    operation_name
+        .replace("::", "_")
+        .replace('.', "_")
+        .to_case(convert_case::Case::Snake)
+}
+
+pub fn content_type_constant_name(content_type: &str) -> String {
+    format!(
+        "CONTENT_TYPE_{}",
+        content_type
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() {
+                true => c.to_ascii_uppercase(),
+                false => '_',
+            })
+            .collect::<String>()
+    )
+}
+
+fn content_type_constant_path(content_type: &str) -> String {
+    format!(
+        "crate::consts::{}",
+        content_type_constant_name(content_type)
+    )
+}
+
+// Writes consts.rs with the literals referenced by the builders
+fn write_consts(target_dir: &PathBuf, path_database: &PathDatabase) -> Result<(), GeneratorError> {
+    // always referenced by the builder template
+    let mut content_types = BTreeSet::from([
+        "application/json".to_owned(),
+        "application/x-ndjson".to_owned(),
+    ]);
+    let mut paths = BTreeMap::new();
+    for item in path_database.iter() {
+        let path = item.value();
+        content_types.extend(path.extract_response_content_types());
+        if let Some(request_entity) = &path.request_entity {
+            content_types.extend(request_entity.content.keys().cloned());
+        }
+        paths.insert(
+            path_constant_name(&path.name),
+            ConstantDefinition {
+                name: path_constant_name(&path.name),
+                value: path.url.clone(),
+                description: format!("Path of `{}` ({})", path.name, path.method.to_string()),
+            },
+        );
+    }
+
+    let template = RustConstsTemplate {
+        content_types: content_types
+            .iter()
+            .map(|content_type| ConstantDefinition {
+                name: content_type_constant_name(content_type),
+                value: content_type.clone(),
+                description: String::new(),
+            })
+            .collect(),
+        paths: paths.into_values().collect(),
+    };
+    write_filename(&target_dir.join("consts.rs"), &template.render().unwrap())?;
+    Ok(())
+}
+
+// Writes one module per websocket operation into `src/websocket`, nothing without websockets
+fn write_websockets(
+    target_dir: &PathBuf,
+    websocket_database: &WebsocketDatabase,
+) -> Result<(), GeneratorError> {
+    if websocket_database.is_empty() {
+        return Ok(());
+    }
+    let modules = websocket_database
+        .iter()
+        .map(|item| (item.key().clone(), item.value().clone()))
+        .collect::<BTreeMap<String, String>>();
+    let mut module_code = String::from(
+        "//! Websocket operations (`x-serverstream`), built with the `websocket` feature
+
+",
+    );
+    for (name, code) in modules.iter() {
+        module_code.push_str(&format!(
+            "pub mod {};
+",
+            name
+        ));
+        write_filename(
+            &target_dir.join("websocket").join(format!("{}.rs", name)),
+            code,
+        )?;
+    }
+    write_filename(&target_dir.join("websocket").join("mod.rs"), &module_code)?;
+    Ok(())
+}
+
+/// Paths of the models re-exported by the prelude, models sharing their name
+/// with another one (or with an item of the prelude) keep their full path
+fn prelude_models(object_database: &ObjectDatabase, config: &Config) -> Vec<String> {
+    let mut models: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for item in object_database.iter() {
+        let name = extract_rust_name(&item.value().name());
+        let namespace = extract_base_name(item.key())
+            .split("::")
+            .flat_map(|part| part.split('.'))
+            .filter(|part| !part.is_empty())
+            .join("::");
+        if namespace.is_empty() {
+            continue;
+        }
+        models
+            .entry(name.clone())
+            .or_default()
+            .push(format!("crate::{}::{}", namespace, name));
+    }
+    let client_name = &config.project_metadata.client_name;
+    let reserved = [
+        "AuthProvider".to_owned(),
+        "Error".to_owned(),
+        "ResponseValue".to_owned(),
+        "builders".to_owned(),
+        client_name.clone(),
+        format!("{}Builder", client_name),
+    ];
+    models
+        .into_iter()
+        .filter(|(name, paths)| paths.len() == 1 && !reserved.contains(name))
+        .flat_map(|(_, paths)| paths)
+        .collect()
+}
+
 pub fn generate_clients(
     output_dir: &PathBuf,
     path_database: &PathDatabase,
+    websocket_database: &WebsocketDatabase,
     config: &Config,
     object_database: &ObjectDatabase,
 ) -> Result<(), GeneratorError> {
     // Write all registered API calls in a client
     let target_dir = output_dir.join("src");
+    write_consts(&target_dir, path_database)?;
+    write_websockets(&target_dir, websocket_database)?;
     let chunks = path_database.iter().chunk_by(|f| f.value().package.clone());
 
     let mut grouped_paths: Vec<_> = chunks.into_iter().collect();
@@ -395,8 +1341,9 @@ pub fn generate_clients(
     grouped_paths.sort_by(|a, b| a.0.cmp(&b.0));
 
     for (namespace, group) in grouped_paths {
-        let items = group.map(|f| f.clone()).collect::<Vec<_>>();
-        let (client_code, builders) = generate_rust_client_code(items, config, object_database);
+        let items = group.map(|f| f.value().clone()).collect::<Vec<_>>();
+        let (client_code, sub_client_code, builders) =
+            generate_rust_client_code(&items, config, object_database);
         let mut path = namespace.replace(".", "/").replace("::", "/");
         if path.is_empty() {
             path = "lib".to_owned();
@@ -408,51 +1355,92 @@ pub fn generate_clients(
             client_name: config.project_metadata.client_name.as_str(),
             server_url: config.project_metadata.server_url.as_str(),
             user_agent: config.project_metadata.user_agent.as_str(),
+            // quoted rust literals, header names must be lowercase for `HeaderMap::insert`
+            default_headers: config
+                .project_metadata
+                .default_headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        format!("{:?}", name.to_lowercase()),
+                        format!("{:?}", config.project_metadata.render_header_value(value)),
+                    )
+                })
+                .collect(),
             version: config.project_metadata.version.as_str(),
+            batch_concurrency: config.batch_concurrency,
+            circuit_breaker_failure_threshold: config.circuit_breaker.failure_threshold,
+            circuit_breaker_reset_timeout_secs: config.circuit_breaker.reset_timeout_secs,
+            models_crate: config.models_crate.as_deref(),
+            conversions: !config.version_conversions.is_empty(),
+            send_futures: config.send_futures,
+            shared: match config.send_futures {
+                true => "Arc",
+                false => "Rc",
+            },
+            lock: match config.send_futures {
+                true => "Mutex",
+                false => "RefCell",
+            },
+            crate_name: &config.project_metadata.name.replace('-', "_"),
+            prelude_models: prelude_models(object_database, config),
+            websocket: !websocket_database.is_empty(),
+            response_cache: config.response_cache.is_enabled(),
+            profiles: config
+                .profiles
+                .iter()
+                .map(|(name, profile)| ClientProfileTemplate {
+                    variant: name.to_case(convert_case::Case::Pascal),
+                    name: format!("{:?}", name),
+                    base_url: profile.base_url.as_ref().map(|url| format!("{:?}", url)),
+                    timeout_secs: profile.timeout_secs,
+                    connect_timeout_secs: profile.connect_timeout_secs,
+                    read_timeout_secs: profile.read_timeout_secs,
+                    proxy: profile.proxy.as_ref().map(|proxy| format!("{:?}", proxy)),
+                })
+                .collect(),
+            env_prefix: config
+                .project_metadata
+                .name
+                .to_case(convert_case::Case::UpperSnake),
         };
         final_client_code.push_str(&client_init_template.render().unwrap());
         final_client_code.push_str("\n");
         final_client_code.push_str(&client_code);
         final_client_code.push_str("}\n");
+        final_client_code.push_str(&sub_client_code);
 
         let full_path = target_dir.join(format!("{}.rs", path));
-        println!(
+        debug!(
             "Writing to {} \n{}",
             full_path.to_str().unwrap(),
-            &client_code
+            &final_client_code
         );
-        write_filename(&full_path, &client_code)?;
+        write_filename(&full_path, &final_client_code)?;
 
         // we create builder files
-        let mut imports = vec![];
+        let mut imports = ImportCollector::new();
+        imports.extend([
+            "use crate::Client;",
+            "use crate::client::ResponseValue;",
+            "use crate::client::Request;",
+            "use reqwest::Method;",
+            "use derive_builder::Builder;",
+        ]);
         let mut builder_code = String::new();
         for builder in builders {
-            for import in builder.imports {
-                let use_def = import.to_use();
-                if imports.contains(&use_def) {
-                    continue;
-                }
-                imports.push(import.to_use());
+            for import in builder.imports.iter() {
+                imports.add_module(import);
             }
             builder_code.push_str(&builder.code);
             builder_code.push_str("\n");
         }
-        let mut full_builder = String::new();
-        full_builder.push_str("use crate::Client;\n");
-        full_builder.push_str("use crate::client::ResponseValue;\n");
-        full_builder.push_str("use crate::client::Request;\n");
-        full_builder.push_str("use reqwest::Method;\n");
-        full_builder.push_str("use derive_builder::Builder;\n");
-        imports.sort();
-        for import in imports {
-            full_builder.push_str(&import);
-            full_builder.push_str("\n");
-        }
+        let mut full_builder = imports.render();
         full_builder.push_str("\n");
         full_builder.push_str(&builder_code);
 
         let builder_path = target_dir.join("builders.rs");
-        println!(
+        debug!(
             "Writing to {} \n{}",
             builder_path.to_str().unwrap(),
             &full_builder
@@ -463,6 +1451,103 @@ pub fn generate_clients(
     Ok(())
 }
 
+fn conversion_expression(conversion: &FieldConversion, value: &str, real_name: &str) -> String {
+    match conversion {
+        FieldConversion::Move => value.to_owned(),
+        FieldConversion::Convert { is_vec: false } => format!("{}.try_into()?", value),
+        FieldConversion::Convert { is_vec: true } => format!(
+            "{}.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?",
+            value
+        ),
+        FieldConversion::ConvertOption => {
+            format!("{}.map(TryInto::try_into).transpose()?", value)
+        }
+        FieldConversion::Required(inner) => conversion_expression(
+            inner,
+            &format!(
+                "{}.ok_or(ConversionError::MissingField(\"{}\"))?",
+                value, real_name
+            ),
+            real_name,
+        ),
+        FieldConversion::Optional(inner) => {
+            format!("Some({})", conversion_expression(inner, value, real_name))
+        }
+        FieldConversion::Default => "Default::default()".to_owned(),
+    }
+}
+
+/// Writes conversions.rs with `From`/`TryFrom` impls between the models
+/// of the configured `version_conversions`
+pub fn write_conversions(
+    output_dir: &PathBuf,
+    object_database: &ObjectDatabase,
+    config: &Config,
+) -> Result<(), GeneratorError> {
+    let mut conversions = vec![];
+    for version_conversion in config.version_conversions.iter() {
+        for conversion in generate_version_conversions(
+            object_database,
+            &version_conversion.source,
+            &version_conversion.target,
+        ) {
+            let mut fields: Vec<ConversionField> = conversion
+                .fields
+                .iter()
+                .map(|field| {
+                    let name = extract_rust_name(&field.name);
+                    ConversionField {
+                        expression: conversion_expression(
+                            &field.conversion,
+                            &format!("value.{}", name),
+                            &field.real_name,
+                        ),
+                        name,
+                    }
+                })
+                .collect();
+            let has_unknown_fields = |key: &str| match object_database.get(key) {
+                Some(object_definition) => match object_definition.value().as_ref() {
+                    ObjectDefinition::Struct(struct_definition) => {
+                        captures_unknown_fields(struct_definition, config)
+                    }
+                    _ => false,
+                },
+                None => false,
+            };
+            // unknown fields of the source are kept when both versions capture them
+            match (
+                has_unknown_fields(&conversion.source),
+                has_unknown_fields(&conversion.target),
+            ) {
+                (true, true) => fields.push(ConversionField {
+                    name: UNKNOWN_FIELDS_NAME.to_owned(),
+                    expression: format!("value.{}", UNKNOWN_FIELDS_NAME),
+                }),
+                (false, true) => fields.push(ConversionField {
+                    name: UNKNOWN_FIELDS_NAME.to_owned(),
+                    expression: "Default::default()".to_owned(),
+                }),
+                _ => (),
+            }
+            conversions.push(ConversionDefinition {
+                source: format!("crate::{}", conversion.source),
+                target: format!("crate::{}", conversion.target),
+                fallible: conversion.is_fallible(),
+                fields,
+            });
+        }
+    }
+
+    let template = RustConversionsTemplate {
+        conversions,
+        edition_2018: config.project_metadata.edition == "2018",
+    }
+    .render()
+    .unwrap();
+    write_filename(&output_dir.join("src").join("conversions.rs"), &template)
+}
+
 // extract scoped name from the full name
 fn extract_base_name(name: &str) -> String {
     let parts = name.split("::").collect::<Vec<&str>>();
@@ -474,186 +1559,214 @@ pub fn write_object_database(
     object_database: &ObjectDatabase,
     config: &Config,
 ) -> Result<(), GeneratorError> {
-    let name_mapping = &config.name_mapping;
-    let target_dir = if config.name_mapping.use_scope {
-        output_dir.join("src")
-    } else {
-        output_dir.join("src")
-    };
-
-    for item in object_database.iter() {
-        println!("Object: {}", item.key());
+    if config.layout != OutputLayout::Namespace {
+        return write_object_database_grouped(output_dir, object_database, config);
     }
 
-    std::fs::create_dir_all(&target_dir).expect("Creating objects dir failed");
+    let target_dir = output_dir.join("src");
 
-    let chunks = object_database
+    let mut items = object_database
         .iter()
-        .chunk_by(|f| extract_base_name(&f.key()));
-
-    let mut grouped_objects: Vec<_> = chunks.into_iter().collect();
-
-    grouped_objects.sort_by(|a, b| a.0.cmp(&b.0));
-
-    for (namespace, group) in grouped_objects {
-        let mut type_map: HashMap<String, (Vec<String>, Vec<String>)> =
-            std::collections::HashMap::new();
-        let mut mods_map: HashMap<String, Vec<String>> = HashMap::new();
-
-        let mut items = group.map(|f| f.clone()).collect::<Vec<_>>();
-        items.sort_by(|a, b| a.name().cmp(&b.name()));
-
-        let target_file = target_dir.join(format!(
-            "{}.rs",
-            namespace.replace(".", "/").replace("::", "/")
-        ));
-        let mut struct_codes = String::new();
-        let mut all_imports = HashSet::new();
-        for object_definition in items.iter() {
-            let object_name = get_object_name(object_definition);
+        .map(|item| (item.key().clone(), item.value().clone()))
+        .collect::<Vec<_>>();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // scoped (`::`) and dotted namespaces become nested modules
+    let mut root = ModuleNode::default();
+    for (key, object_definition) in items.iter() {
+        debug!("Object: {}", key);
+        let namespace = extract_base_name(key)
+            .split("::")
+            .flat_map(|part| part.split('.'))
+            .filter(|part| !part.is_empty())
+            .map(|part| part.to_owned())
+            .collect::<Vec<String>>();
+        if namespace.is_empty() {
+            warn!("{} has no namespace and is not written", key);
+            continue;
+        }
+        let (imports, code) = render_object_definition(object_definition, config)?;
+        let module = root.get_or_create(&namespace);
+        module.imports.extend(imports);
+        module.codes.push(code);
+    }
 
-            let module_name = name_mapping.name_to_module_name(&object_name);
+    for (name, module) in root.children.iter() {
+        module.write_files(&target_dir, name)?;
+    }
 
-            let namespace = extract_rust_namespace(&module_name);
+    Ok(())
+}
 
-            match object_definition {
-                ObjectDefinition::Struct(struct_definition) => {
-                    for module in struct_definition.get_required_modules() {
-                        all_imports.insert(module.to_use());
-                    }
+#[derive(Default)]
+struct ModuleNode {
+    imports: ImportCollector,
+    codes: Vec<String>,
+    children: BTreeMap<String, ModuleNode>,
+}
 
-                    let mut result = String::new();
-                    result.push_str("\n");
-                    result.push_str(&struct_definition.to_string(true, config)?);
-                    struct_codes.push_str(&result);
-                    // write_filename(&target_file, &result).unwrap();
-                    let mut mods = vec![];
-                    if mods_map.contains_key(&namespace) {
-                        mods = mods_map.get(&namespace).unwrap().clone();
-                    }
-                    mods.push(format!(
-                        "pub mod {};",
-                        &target_file.file_stem().unwrap().to_str().unwrap()
-                    ));
-                    mods_map.insert(namespace, mods);
-                }
-                ObjectDefinition::Enum(enum_definition) => {
-                    for module in enum_definition.get_required_modules() {
-                        all_imports.insert(module.to_use());
-                    }
+impl ModuleNode {
+    fn get_or_create(&mut self, path: &[String]) -> &mut ModuleNode {
+        match path.split_first() {
+            Some((first, rest)) => self
+                .children
+                .entry(first.clone())
+                .or_default()
+                .get_or_create(rest),
+            None => self,
+        }
+    }
 
-                    let mut result = String::new();
-                    result.push_str("\n");
-                    result.push_str(&enum_definition.to_string(true, config)?);
-                    struct_codes.push_str(&result);
-                    // write_filename(&target_file, &result).unwrap();
-                    // we update the mods list
-                    let mut mods = vec![];
-                    if mods_map.contains_key(&namespace) {
-                        mods = mods_map.get(&namespace).unwrap().clone();
-                    }
-                    mods.push(format!(
-                        "pub mod {};",
-                        &target_file.file_stem().unwrap().to_str().unwrap()
-                    ));
-                    mods_map.insert(namespace, mods);
+    /// Writes the module as `name.rs`, or `name/mod.rs` when it has submodules
+    /// which are written into the `name` directory and declared with `pub mod`
+    fn write_files(&self, dir: &PathBuf, name: &str) -> Result<(), GeneratorError> {
+        let mut result = self.imports.render();
+        for code in self.codes.iter() {
+            result.push_str("\n");
+            result.push_str(code);
+            result.push_str("\n");
+        }
+        let target_file = match self.children.is_empty() {
+            true => dir.join(format!("{}.rs", name)),
+            false => {
+                result.push_str("\n");
+                for (child_name, child) in self.children.iter() {
+                    result.push_str(&format!("pub mod {};\n", child_name));
+                    child.write_files(&dir.join(name), child_name)?;
                 }
-                ObjectDefinition::Primitive(primitive_definition) => {
-                    let mut imports = vec![];
-                    let mut codes = vec![];
-                    if type_map.contains_key(&namespace) {
-                        let (import, code) = type_map.get(&namespace).unwrap();
-                        imports = import.clone();
-                        codes = code.clone();
-                    }
-
-                    if let Some(module) = &primitive_definition.primitive_type.module {
-                        imports.push(module.to_use());
-                    }
-
-                    let description = fix_rust_description(
-                        "",
-                        &primitive_definition
-                            .description
-                            .as_ref()
-                            .map_or("", |d| d.as_str()),
-                    );
+                dir.join(name).join("mod.rs")
+            }
+        };
+        debug!("Writing to {} \n{}", target_file.to_str().unwrap(), &result);
+        write_filename(&target_file, &result)?;
+        Ok(())
+    }
 
-                    let template = RustTypeTemplate {
-                        name: extract_rust_name(&primitive_definition.name).as_str(),
-                        description: description.as_str(),
-                        value: extract_rust_name(&primitive_definition.primitive_type.name)
-                            .as_str(),
-                    }
-                    .render()
-                    .unwrap();
+    fn render(&self, result: &mut String) {
+        result.push_str(&self.imports.render());
+        for code in self.codes.iter() {
+            result.push_str("\n");
+            result.push_str(code);
+            result.push_str("\n");
+        }
+        for (name, child) in self.children.iter() {
+            result.push_str(&format!("\npub mod {} {{\n", name));
+            child.render(result);
+            result.push_str("}\n");
+        }
+    }
+}
 
-                    codes.push(template);
-                    type_map.insert(namespace, (imports, codes));
-                }
+// file name and inline module path of a namespace for the given layout
+fn layout_file_and_modules(layout: OutputLayout, namespace: &str) -> (String, Vec<String>) {
+    let mut parts = namespace
+        .split("::")
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_owned())
+        .collect::<Vec<String>>();
+    match layout {
+        OutputLayout::Namespace => (namespace.to_owned(), vec![]),
+        OutputLayout::Package => {
+            if parts.is_empty() {
+                return ("models".to_owned(), vec![]);
             }
+            let package = parts.remove(0);
+            (package, parts)
         }
-
-        let mut created_modules = vec![];
-
-        for (module_name, mods) in mods_map.iter() {
-            let mut mods = mods.clone();
-            let target_file = target_dir.join(format!("{}/mod.rs", module_name.replace("::", "/")));
-            mods.sort();
-            let mut result = mods.join("\n");
-
-            if type_map.contains_key(module_name) {
-                let (imports, codes) = type_map.get(module_name).unwrap();
-                let mut imports = imports.clone();
-                imports.sort();
-                result.push_str("\n");
-                result.push_str(&imports.join("\n"));
-                result.push_str("\n");
-                result.push_str(&codes.join("\n"));
+        // the models package is the file itself, all other namespaces are inline modules
+        OutputLayout::SingleFile => {
+            if parts.first().is_some_and(|package| package == "models") {
+                parts.remove(0);
             }
-
-            write_filename(&target_file, &result).unwrap();
-            created_modules.push(module_name);
+            ("models".to_owned(), parts)
         }
+    }
+}
 
-        let mut types = String::new();
-        for (module_name, (imports, codes)) in type_map.iter() {
-            if created_modules.contains(&module_name) {
-                continue;
-            }
-            for import in imports {
-                all_imports.insert(import.clone());
+fn render_object_definition(
+    object_definition: &ObjectDefinition,
+    config: &Config,
+) -> Result<(Vec<String>, String), GeneratorError> {
+    match object_definition {
+        ObjectDefinition::Struct(struct_definition) => Ok((
+            struct_definition
+                .get_required_modules()
+                .iter()
+                .map(|module| module.to_use())
+                .collect(),
+            struct_definition.to_string(true, config)?,
+        )),
+        ObjectDefinition::Enum(enum_definition) => Ok((
+            enum_definition
+                .get_required_modules()
+                .iter()
+                .map(|module| module.to_use())
+                .collect(),
+            enum_definition.to_string(true, config)?,
+        )),
+        ObjectDefinition::Primitive(primitive_definition) => {
+            let description = fix_rust_description(
+                "",
+                &primitive_definition
+                    .description
+                    .as_ref()
+                    .map_or("", |d| d.as_str()),
+            );
+            let code = RustTypeTemplate {
+                name: extract_rust_name(&primitive_definition.name).as_str(),
+                description: description.as_str(),
+                value: extract_rust_name(&primitive_definition.primitive_type.name).as_str(),
             }
-            // let target_file = target_dir.join(format!("{}/mod.rs", module_name.replace("::", "/")));
-            types.push_str(&codes.join("\n"));
-            created_modules.push(module_name);
-        }
-        let mut imports = all_imports.iter().cloned().collect::<Vec<String>>();
-        imports.sort();
-        let mut result = imports.join("\n");
-        result.push_str("\n");
-        result.push_str(&types);
-        result.push_str(&struct_codes);
-        write_filename(&target_file, &result).unwrap();
-        println!("Writing to {} \n{}", target_file.to_str().unwrap(), &result);
-    }
-
-    // let target_mod = target_dir.join("mod.rs");
-    // let mut mods = vec![];
-
-    // for struct_name in object_database.iter().map(|x| x.key().clone()) {
-    //     mods.push(
-    //         format!(
-    //             "pub mod {};\n",
-    //             name_mapping.name_to_module_name(&struct_name)
-    //         )
-    //         .to_string(),
-    //     )
-    // }
-
-    // mods.sort();
-    // let result = mods.join("\n");
-    // write_filename(&target_mod, &result)?;
+            .render()
+            .unwrap();
+            Ok((
+                primitive_definition
+                    .primitive_type
+                    .module
+                    .iter()
+                    .map(|module| module.to_use())
+                    .collect(),
+                code,
+            ))
+        }
+    }
+}
+
+// Writes objects for the package and single_file layouts, nested namespaces
+// are rendered as inline modules so no mod.rs files are required
+fn write_object_database_grouped(
+    output_dir: &PathBuf,
+    object_database: &ObjectDatabase,
+    config: &Config,
+) -> Result<(), GeneratorError> {
+    let target_dir = output_dir.join("src");
+    let mut files: BTreeMap<String, ModuleNode> = BTreeMap::new();
+
+    let mut items = object_database
+        .iter()
+        .map(|item| (item.key().clone(), item.value().clone()))
+        .collect::<Vec<_>>();
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (key, object_definition) in items.iter() {
+        let (file_name, inline_modules) =
+            layout_file_and_modules(config.layout, &extract_base_name(key));
+        let (imports, code) = render_object_definition(object_definition, config)?;
+        let module = files
+            .entry(file_name)
+            .or_default()
+            .get_or_create(&inline_modules);
+        module.imports.extend(imports);
+        module.codes.push(code);
+    }
+
+    for (file_name, module) in files.iter() {
+        let mut result = String::new();
+        module.render(&mut result);
+        let target_file = target_dir.join(format!("{}.rs", file_name));
+        write_filename(&target_file, &result)?;
+        debug!("Writing to {} \n{}", target_file.to_str().unwrap(), &result);
+    }
 
     Ok(())
 }
@@ -679,11 +1792,26 @@ pub fn extract_rust_namespace(name: &str) -> String {
 fn fix_private_name(name: &str) -> String {
     if name.eq_ignore_ascii_case("type") {
         "r#type".to_string()
+    } else if name == "gen" {
+        // reserved since edition 2024, raw identifiers work in all editions
+        "r#gen".to_string()
     } else {
         name.to_string()
     }
 }
 
+// name of the map field capturing the unknown fields
+const UNKNOWN_FIELDS_NAME: &str = "unknown";
+
+/// Whether the struct gets the `unknown` map, unless a property has this name
+fn captures_unknown_fields(struct_definition: &StructDefinition, config: &Config) -> bool {
+    config.unknown_fields == UnknownFields::Capture
+        && !struct_definition
+            .properties
+            .values()
+            .any(|property| property.name == UNKNOWN_FIELDS_NAME)
+}
+
 pub fn render_struct_definition(
     struct_definition: &crate::generator::types::StructDefinition,
     serializable: bool,
@@ -696,7 +1824,11 @@ pub fn render_struct_definition(
             .as_ref()
             .map_or("", |d| d.as_str()),
     );
+    let capture_unknown_fields = serializable && captures_unknown_fields(struct_definition, config);
     let mut derivations = vec!["Debug", "Clone", "PartialEq"];
+    if struct_definition.derive_copy && !capture_unknown_fields {
+        derivations.push("Copy");
+    }
     if serializable {
         derivations.push("Serialize");
         derivations.push("Deserialize");
@@ -706,6 +1838,8 @@ pub fn render_struct_definition(
         derivations.push("Default");
     }
     let mut fields: Vec<Field> = vec![];
+    // adapters are only needed (and supported) with serde
+    let mut has_serde_as = false;
     for (_, property) in &struct_definition.properties {
         let mut annotations = vec![];
         let mut serde_parts = HashSet::new();
@@ -716,7 +1850,13 @@ pub fn render_struct_definition(
         }
         let field_description = fix_rust_description(
             "  ",
-            &property.description.as_ref().map_or("", |d| d.as_str()),
+            &with_external_docs(
+                &with_example(
+                    property.description.as_ref().map_or("", |d| d.as_str()),
+                    property.example.as_ref(),
+                ),
+                property.external_docs.as_ref(),
+            ),
         );
 
         if property.type_name.starts_with("Vec<") {
@@ -748,12 +1888,21 @@ pub fn render_struct_definition(
                 serds.sort();
                 annotations.push(format!("#[serde({})]", serds.join(", ")));
             }
+            let mut typ = property.type_name.clone();
+            if let Some(adapter) = serde_with_adapter(&struct_definition.name, property, config)
+                .filter(|_| serializable)
+            {
+                let (adapter_type, serde_as) = serde_with_type(adapter, &typ, property);
+                annotations.push(format!("#[serde_as(as = \"{}\")]", serde_as));
+                typ = adapter_type;
+                has_serde_as = true;
+            }
             fields.push(Field {
                 annotations,
                 description: field_description,
                 modifier: "pub".to_string(),
                 name: extract_rust_name(&property.name),
-                typ: property.type_name.clone(),
+                typ,
             });
         } else {
             if serializable {
@@ -762,17 +1911,38 @@ pub fn render_struct_definition(
                 annotations.push(format!("#[serde({})]", serds.join(", ")));
             }
             let name = extract_rust_name(&property.name);
+            let mut typ = extract_rust_name(&property.type_name);
+            if let Some(adapter) = serde_with_adapter(&struct_definition.name, property, config)
+                .filter(|_| serializable)
+            {
+                let (adapter_type, serde_as) = serde_with_type(adapter, &typ, property);
+                annotations.push(format!("#[serde_as(as = \"Option<{}>\")]", serde_as));
+                typ = adapter_type;
+                has_serde_as = true;
+            }
             fields.push(Field {
                 annotations,
                 description: field_description,
                 modifier: "pub".to_string(),
                 name,
-                typ: format!("Option<{}>", extract_rust_name(&property.type_name)),
+                typ: format!("Option<{}>", typ),
             });
         }
     }
     fields.sort();
+    if capture_unknown_fields {
+        fields.push(Field {
+            annotations: vec!["#[serde(flatten)]".to_owned()],
+            description: "/// Fields not documented in the spec".to_owned(),
+            modifier: "pub".to_owned(),
+            name: UNKNOWN_FIELDS_NAME.to_owned(),
+            typ: "std::collections::HashMap<String, serde_json::Value>".to_owned(),
+        });
+    }
+
     let template = RustStructTemplate {
+        serde_as: has_serde_as,
+        deny_unknown_fields: serializable && config.unknown_fields == UnknownFields::Deny,
         name: extract_rust_name(&struct_definition.name).as_str(),
         description: description.as_str(),
         derivations,
@@ -785,16 +1955,208 @@ pub fn render_struct_definition(
     }
     .render()
     .unwrap();
-    template
+    let template = match links_helpers(struct_definition, serializable, config) {
+        Some(links_helpers) => format!("{}\n{}", template, links_helpers),
+        None => template,
+    };
+
+    // whole-object examples are checked with a serde round trip
+    match (
+        &struct_definition.example,
+        serializable && config.example_tests,
+    ) {
+        (Some(example), true) => {
+            let name = extract_rust_name(&struct_definition.name);
+            let example_test = RustExampleTestTemplate {
+                module_name: &format!("{}_example_tests", name.to_case(convert_case::Case::Snake)),
+                name: &name,
+                example: &format!("{:?}", example.to_string()),
+            }
+            .render()
+            .unwrap();
+            format!("{}\n{}", template, example_test)
+        }
+        _ => template,
+    }
+}
+
+// `link` helpers of models holding hypermedia links, the `links` module is part of the client crate
+fn links_helpers(
+    struct_definition: &crate::generator::types::StructDefinition,
+    serializable: bool,
+    config: &Config,
+) -> Option<String> {
+    if !serializable || !config.stages.clients || config.models_crate.is_some() {
+        return None;
+    }
+    let property = struct_definition
+        .properties
+        .values()
+        .find(|property| property.real_name == config.links_property)?;
+    Some(format!(
+        r#"impl {name} {{
+    /// Link of the relation `rel` in `{property}`, sent with `Client::follow`
+    pub fn link(&self, rel: &str) -> Option<crate::links::Link> {{
+        crate::links::find_link(&serde_json::to_value(&self.{field}).ok()?, rel)
+    }}
+
+    /// Relations of the links in `{property}`
+    pub fn link_relations(&self) -> Vec<String> {{
+        serde_json::to_value(&self.{field})
+            .map(|links| crate::links::link_relations(&links))
+            .unwrap_or_default()
+    }}
+}}
+"#,
+        name = extract_rust_name(&struct_definition.name),
+        property = property.real_name,
+        field = extract_rust_name(&property.name),
+    ))
+}
+
+fn uses_128_bit_integers(object_database: &ObjectDatabase) -> bool {
+    let is_128_bit = |type_name: &str| type_name.contains("i128") || type_name.contains("u128");
+    object_database
+        .iter()
+        .any(|item| match item.value().as_ref() {
+            ObjectDefinition::Struct(struct_definition) => struct_definition
+                .properties
+                .values()
+                .any(|property| is_128_bit(&property.type_name)),
+            ObjectDefinition::Primitive(primitive_definition) => {
+                is_128_bit(&primitive_definition.primitive_type.name)
+            }
+            ObjectDefinition::Enum(_) => false,
+        })
+}
+
+// explicit mappings by `Struct.field` win over the format of the schema
+fn serde_with_adapter(
+    struct_name: &str,
+    property: &PropertyDefinition,
+    config: &Config,
+) -> Option<SerdeWithAdapter> {
+    let field_path = format!("{}.{}", struct_name, property.real_name);
+    match config.serde_with.fields.get(&field_path) {
+        Some(adapter) => Some(*adapter),
+        None if config.serde_with.by_format => property
+            .format
+            .as_ref()
+            .and_then(|format| SerdeWithAdapter::from_format(&property.type_name, format)),
+        None => None,
+    }
+}
+
+// field type and `serde_as` type of an adapter, collections use the adapter per element
+fn serde_with_type(
+    adapter: SerdeWithAdapter,
+    type_name: &str,
+    property: &PropertyDefinition,
+) -> (String, String) {
+    if let Some(item_type) = type_name
+        .strip_prefix("Vec<")
+        .and_then(|item_type| item_type.strip_suffix('>'))
+    {
+        if adapter != SerdeWithAdapter::CommaSeparated {
+            let (item_type, serde_as) = serde_with_type(adapter, item_type, property);
+            return (format!("Vec<{}>", item_type), format!("Vec<{}>", serde_as));
+        }
+    }
+    match adapter {
+        SerdeWithAdapter::DisplayFromStr => {
+            let number_type = match (type_name, property.format.as_deref()) {
+                ("String", Some("int32")) => "i32",
+                ("String", Some("int64")) => "i64",
+                ("String", Some("float")) => "f32",
+                ("String", Some("double")) => "f64",
+                (type_name, _) => type_name,
+            };
+            (
+                number_type.to_owned(),
+                "serde_with::DisplayFromStr".to_owned(),
+            )
+        }
+        SerdeWithAdapter::TimestampSeconds => (
+            "std::time::SystemTime".to_owned(),
+            "serde_with::TimestampSeconds<i64>".to_owned(),
+        ),
+        SerdeWithAdapter::TimestampMilliseconds => (
+            "std::time::SystemTime".to_owned(),
+            "serde_with::TimestampMilliSeconds<i64>".to_owned(),
+        ),
+        SerdeWithAdapter::CommaSeparated => (
+            "Vec<String>".to_owned(),
+            "serde_with::StringWithSeparator::<serde_with::formats::CommaSeparator, String>"
+                .to_owned(),
+        ),
+        SerdeWithAdapter::Base64 => (
+            "Vec<u8>".to_owned(),
+            "serde_with::base64::Base64".to_owned(),
+        ),
+    }
+}
+
+// appends the example of the spec as fenced block to a description
+fn with_example(description: &str, example: Option<&serde_json::Value>) -> String {
+    let example = match example {
+        Some(example) => serde_json::to_string_pretty(example).unwrap_or_default(),
+        None => return description.to_owned(),
+    };
+    match description.is_empty() {
+        true => format!("Example:\n```text\n{}\n```", example),
+        false => format!("{}\n\nExample:\n```text\n{}\n```", description, example),
+    }
+}
+
+fn with_external_docs(description: &str, external_docs: Option<&ExternalDocs>) -> String {
+    let link = match external_docs {
+        Some(ExternalDocs {
+            url,
+            description: Some(link_description),
+        }) if !link_description.is_empty() => format!("See [{}]({})", link_description.trim(), url),
+        Some(ExternalDocs { url, .. }) => format!("See <{}>", url),
+        None => return description.to_owned(),
+    };
+    match description.is_empty() {
+        true => link,
+        false => format!("{}\n\n{}", description, link),
+    }
 }
 
 fn is_private_name(name: &str) -> bool {
     name.eq_ignore_ascii_case("type") || name.starts_with("r#")
 }
 
+// Extension methods have no reqwest constant and are passed through as bytes
+fn rust_method_expression(method: &Method) -> String {
+    match method {
+        Method::Other(method) => format!("Method::from_bytes(b\"{}\").unwrap()", method),
+        _ => format!("Method::{}", method.to_string()),
+    }
+}
+
+pub fn render_query_parameters(
+    struct_variable_name: &str,
+    vector_name: &str,
+    parameters: &[QueryParameterDefinition],
+) -> String {
+    RustQueryParametersTemplate {
+        struct_variable_name,
+        vector_name,
+        parameters,
+        // optional, array and serialized parameters are pushed after the vec is created
+        has_repeated_parameters: parameters.iter().any(|parameter| {
+            !parameter.required || parameter.is_array || parameter.is_serialized()
+        }),
+    }
+    .render()
+    .unwrap()
+}
+
 pub fn render_enum_definition(
     enum_definition: &crate::generator::types::EnumDefinition,
     serializable: bool,
+    unknown_variant: bool,
 ) -> String {
     // let mut definition_str = String::new();
     let description = fix_rust_description(
@@ -804,19 +2166,40 @@ pub fn render_enum_definition(
             .as_ref()
             .map_or("", |d| d.as_str()),
     );
-    let variants = enum_definition
+    let mut variants: Vec<String> = enum_definition
         .values
         .iter()
         .map(|(_, enum_value)| {
-            format!(
+            let mut variant = String::new();
+            if let Some(ref description) = enum_value.description {
+                variant.push_str(&fix_rust_description("    ", description));
+                variant.push_str("\n    ");
+            }
+            if enum_value.deprecated {
+                variant.push_str("#[deprecated = \"deprecated in the API spec\"]\n    ");
+            }
+            variant.push_str(&format!(
                 "{}({})",
                 extract_rust_name(&enum_value.name),
                 extract_rust_name(&enum_value.value_type.name)
-            )
+            ));
+            variant
         })
         .collect();
+    // untagged catch-all, it must stay the last variant to be tried after all others.
+    // Enums without variants stand for `false` schemas and must stay uninhabited
+    let unknown_variant = unknown_variant && serializable && !enum_definition.values.is_empty();
+    if unknown_variant {
+        variants.push(
+            "/// Value not matching any variant known when the client was generated\n    #[serde(untagged)]\n    Unknown(serde_json::Value)"
+                .to_owned(),
+        );
+    }
 
     let mut derivations = vec!["Debug", "Clone", "PartialEq"];
+    if enum_definition.derive_copy {
+        derivations.push("Copy");
+    }
     if serializable {
         derivations.push("Serialize");
         derivations.push("Deserialize");
@@ -850,3 +2233,48 @@ pub fn modules_to_string(modules: &Vec<&ModuleInfo>) -> String {
     }
     module_import_string
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::types::PrimitiveDefinition;
+    use crate::utils::file::{capture_files, take_captured_files};
+
+    use super::*;
+
+    fn primitive(name: &str) -> Arc<ObjectDefinition> {
+        Arc::new(ObjectDefinition::Primitive(PrimitiveDefinition {
+            name: name.to_owned(),
+            primitive_type: TypeDefinition {
+                name: "u64".to_owned(),
+                module: None,
+                description: None,
+                example: None,
+            },
+            description: None,
+        }))
+    }
+
+    #[test]
+    fn test_single_file_layout() {
+        let output_dir = PathBuf::from("/nonexistent/opage");
+        let object_database = ObjectDatabase::new();
+        object_database.insert(
+            "models::pets::PetId".to_owned(),
+            primitive("models::pets::PetId"),
+        );
+        object_database.insert(
+            "models::store::OrderId".to_owned(),
+            primitive("models::store::OrderId"),
+        );
+        let mut config = Config::new();
+        config.layout = OutputLayout::SingleFile;
+
+        capture_files();
+        write_object_database(&output_dir, &object_database, &config).unwrap();
+        let files = take_captured_files();
+        assert_eq!(files.len(), 1);
+        let models = files.get(&output_dir.join("src/models.rs")).unwrap();
+        assert!(models.contains("pub mod pets {"));
+        assert!(models.contains("pub mod store {"));
+    }
+}
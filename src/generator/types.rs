@@ -1,13 +1,17 @@
 use crate::generator::templates::rust::{Field, RustEnumTemplate, RustStructTemplate};
-use crate::utils::config::Config;
+use crate::utils::config::{CircuitBreakerOperation, Config, ContentChecksum};
 use crate::GeneratorError;
 use askama::Template;
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
 
+use super::path::utils::is_json_patch_content_type;
 use super::templates::rust;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModuleInfo {
     pub name: String,
     pub path: String,
@@ -45,7 +49,7 @@ impl ModuleInfo {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TypeDefinition {
     pub name: String,
     pub module: Option<ModuleInfo>,
@@ -53,7 +57,7 @@ pub struct TypeDefinition {
     pub example: Option<serde_json::Value>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct PropertyDefinition {
     pub name: String,
     pub real_name: String,
@@ -62,9 +66,27 @@ pub struct PropertyDefinition {
     pub required: bool,
     pub description: Option<String>,
     pub example: Option<serde_json::Value>,
+    /// Parameter marked as `deprecated`
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Query parameter sent as `?name` without `=` when its value is empty
+    #[serde(default)]
+    pub allow_empty_value: bool,
+    /// `format` of the schema, e.g. int64 or byte
+    #[serde(default)]
+    pub format: Option<String>,
+    /// `externalDocs` of the schema, linked from the field doc
+    #[serde(default)]
+    pub external_docs: Option<ExternalDocs>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExternalDocs {
+    pub url: String,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ObjectDefinition {
     Struct(StructDefinition),
     Enum(EnumDefinition),
@@ -81,22 +103,33 @@ impl ObjectDefinition {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EnumValue {
     pub name: String,
     pub value_type: TypeDefinition,
+    /// Entry of `x-enum-descriptions` at the index of the variant
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Entry of `x-enum-deprecated` at the index of the variant
+    #[serde(default)]
+    pub deprecated: bool,
 }
 
-pub type ObjectDatabase = DashMap<String, ObjectDefinition>;
-pub type PathDatabase = DashMap<String, PathDefinition>;
+// Definitions are shared through Arc so reading them for rendering never deep copies
+pub type ObjectDatabase = DashMap<String, Arc<ObjectDefinition>>;
+pub type PathDatabase = DashMap<String, Arc<PathDefinition>>;
+/// Source code of the websocket operations (`x-serverstream`) by module name
+pub type WebsocketDatabase = DashMap<String, String>;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EnumDefinition {
     pub name: String,
     // pub namespace: String,
     pub used_modules: Vec<ModuleInfo>,
     pub values: HashMap<String, EnumValue>,
     pub description: Option<String>,
+    // set by the copy analysis pass when all values are plain data
+    pub derive_copy: bool,
 }
 
 impl EnumDefinition {
@@ -118,7 +151,11 @@ impl EnumDefinition {
 
     pub fn to_string(&self, serializable: bool, config: &Config) -> Result<String, GeneratorError> {
         match config.language {
-            crate::Language::Rust => Ok(rust::render_enum_definition(&self, serializable)),
+            crate::Language::Rust => Ok(rust::render_enum_definition(
+                &self,
+                serializable,
+                config.unknown_enum_variant,
+            )),
             _ => Err(GeneratorError::UnsupportedLanguageError(format!(
                 "Error rendering StructDefinition {} {}",
                 self.name,
@@ -128,7 +165,7 @@ impl EnumDefinition {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct StructDefinition {
     pub package: String,
     pub name: String,
@@ -136,6 +173,11 @@ pub struct StructDefinition {
     pub properties: HashMap<String, PropertyDefinition>,
     pub local_objects: HashMap<String, Box<ObjectDefinition>>,
     pub description: Option<String>,
+    // set by the copy analysis pass when all properties are plain data
+    pub derive_copy: bool,
+    /// Example of the whole object from the spec
+    #[serde(default)]
+    pub example: Option<serde_json::Value>,
 }
 
 impl StructDefinition {
@@ -175,49 +217,334 @@ impl StructDefinition {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PrimitiveDefinition {
     pub name: String,
     pub primitive_type: TypeDefinition,
     pub description: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum TransferMediaType {
     ApplicationJson(Option<TypeDefinition>),
     TextPlain,
+    /// `text/csv`, each record is deserialized into the row type
+    TextCsv(Option<TypeDefinition>),
+    /// `application/x-ndjson`, each line is deserialized into the row type
+    ApplicationNdjson(Option<TypeDefinition>),
+    /// `application/octet-stream` and other binary content, sent and received as bytes
+    Binary,
+}
+
+impl TransferMediaType {
+    pub fn type_definition(&self) -> Option<&TypeDefinition> {
+        match self {
+            TransferMediaType::ApplicationJson(type_definition)
+            | TransferMediaType::TextCsv(type_definition)
+            | TransferMediaType::ApplicationNdjson(type_definition) => type_definition.as_ref(),
+            TransferMediaType::TextPlain | TransferMediaType::Binary => None,
+        }
+    }
+
+    /// Formats streamed as a sequence of rows instead of a single document
+    pub fn is_row_format(&self) -> bool {
+        matches!(
+            self,
+            TransferMediaType::TextCsv(_) | TransferMediaType::ApplicationNdjson(_)
+        )
+    }
 }
 
 pub type ContentTypeValue = String;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ResponseEntity {
     pub canonical_status_code: String,
     pub content: HashMap<ContentTypeValue, TransferMediaType>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RequestEntity {
     pub content: HashMap<ContentTypeValue, TransferMediaType>,
 }
 
 pub type ResponseEntities = HashMap<String, ResponseEntity>;
 
-#[derive(Clone, Debug, Default)]
+/// Key of the response entity generated from the `default` response
+pub const DEFAULT_RESPONSE_KEY: &str = "default";
+
+/// `style` of a query parameter, how arrays and objects are written into the query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QueryStyle {
+    #[default]
+    Form,
+    SpaceDelimited,
+    PipeDelimited,
+    DeepObject,
+}
+
+impl QueryStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QueryStyle::Form => "form",
+            QueryStyle::SpaceDelimited => "spaceDelimited",
+            QueryStyle::PipeDelimited => "pipeDelimited",
+            QueryStyle::DeepObject => "deepObject",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct QueryParameterDefinition {
+    pub name: String,
+    pub real_name: String,
+    pub required: bool,
+    pub is_array: bool,
+    pub allow_empty_value: bool,
+    /// Objects and enums (or arrays of them), serialized with serde instead of Display
+    #[serde(default)]
+    pub is_complex: bool,
+    #[serde(default)]
+    pub style: QueryStyle,
+    /// `explode` of the spec, `true` by default for the form style
+    #[serde(default = "default_explode")]
+    pub explode: bool,
+}
+
+fn default_explode() -> bool {
+    true
+}
+
+impl QueryParameterDefinition {
+    /// Written with `query_pairs` of the client: objects, enums and arrays which are not
+    /// repeated (`form` exploded) as the Display implementation can't express them
+    pub fn is_serialized(&self) -> bool {
+        self.is_complex || (self.is_array && !(self.style == QueryStyle::Form && self.explode))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct QueryParameters {
     pub query_struct: StructDefinition,
     pub query_struct_variable_name: String,
-    pub unroll_query_parameters_code: String,
+    pub parameters: Vec<QueryParameterDefinition>,
 }
 
-#[derive(Clone, Debug, Default)]
+impl QueryParameters {
+    pub fn new(query_struct: StructDefinition, query_struct_variable_name: String) -> Self {
+        let mut parameters = query_struct
+            .properties
+            .values()
+            .map(|property| QueryParameterDefinition {
+                name: property.name.clone(),
+                real_name: property.real_name.clone(),
+                required: property.required,
+                is_array: property.type_name.starts_with("Vec<"),
+                allow_empty_value: property.allow_empty_value,
+                is_complex: property.module.is_some()
+                    || property.type_name.contains("serde_json::Value"),
+                style: QueryStyle::Form,
+                explode: true,
+            })
+            .collect::<Vec<QueryParameterDefinition>>();
+        parameters.sort_by(|a, b| a.name.cmp(&b.name));
+        QueryParameters {
+            query_struct,
+            query_struct_variable_name,
+            parameters,
+        }
+    }
+
+    /// Sets the style of the parameter sent as `real_name`
+    pub fn set_style(&mut self, real_name: &str, style: QueryStyle, explode: bool) {
+        for parameter in self.parameters.iter_mut() {
+            if parameter.real_name == real_name {
+                parameter.style = style;
+                parameter.explode = explode;
+            }
+        }
+    }
+
+    /// Renders the code collecting the query parameters into a `Vec<(String, String)>` named `vector_name`
+    pub fn to_string(&self, vector_name: &str, config: &Config) -> Result<String, GeneratorError> {
+        match config.language {
+            crate::Language::Rust => Ok(rust::render_query_parameters(
+                &self.query_struct_variable_name,
+                vector_name,
+                &self.parameters,
+            )),
+            _ => Err(GeneratorError::UnsupportedLanguageError(format!(
+                "Error rendering QueryParameters {} {}",
+                self.query_struct.name,
+                config.language.to_string()
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PathParameters {
     pub parameters_struct_variable_name: String,
     pub parameters_struct: StructDefinition,
-    pub path_format_string: String,
+    #[serde(default)]
+    pub url_template: UrlTemplate,
+}
+
+/// `style` of a path parameter, how its value is written into the path
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathStyle {
+    /// `blue` or `blue,black`
+    #[default]
+    Simple,
+    /// `.blue` or `.blue.black` exploded
+    Label,
+    /// `;color=blue` or `;color=blue;color=black` exploded
+    Matrix,
+}
+
+impl PathStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PathStyle::Simple => "simple",
+            PathStyle::Label => "label",
+            PathStyle::Matrix => "matrix",
+        }
+    }
+}
+
+/// Part of a `UrlTemplate`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UrlSegment {
+    /// Text used as is, including the `/` separators
+    Literal(String),
+    /// `{name}` placeholder replaced by the path parameter `name`
+    Parameter {
+        name: String,
+        type_name: String,
+        #[serde(default)]
+        style: PathStyle,
+        #[serde(default)]
+        explode: bool,
+    },
+}
+
+/// Path of an operation split into literals and parameters, e.g. `/pets/{petId}`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlTemplate {
+    pub segments: Vec<UrlSegment>,
+}
+
+impl UrlTemplate {
+    /// Parses the `{name}` placeholders of `path`, parameters are typed `String`
+    /// until `set_parameter_type` is called
+    pub fn parse(path: &str) -> Self {
+        let mut segments = vec![];
+        let mut rest = path;
+        while let Some(start) = rest.find('{') {
+            let end = match rest[start..].find('}') {
+                Some(end) => start + end,
+                None => break,
+            };
+            if start > 0 {
+                segments.push(UrlSegment::Literal(rest[..start].to_owned()));
+            }
+            segments.push(UrlSegment::Parameter {
+                name: rest[start + 1..end].to_owned(),
+                type_name: "String".to_owned(),
+                style: PathStyle::Simple,
+                explode: false,
+            });
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(UrlSegment::Literal(rest.to_owned()));
+        }
+        UrlTemplate { segments }
+    }
+
+    /// Names and types of the parameters in path order
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.segments.iter().filter_map(|segment| match segment {
+            UrlSegment::Parameter {
+                name, type_name, ..
+            } => Some((name.as_str(), type_name.as_str())),
+            UrlSegment::Literal(_) => None,
+        })
+    }
+
+    pub fn set_parameter_type(&mut self, parameter_name: &str, parameter_type: &str) {
+        for segment in self.segments.iter_mut() {
+            if let UrlSegment::Parameter {
+                name, type_name, ..
+            } = segment
+            {
+                if name == parameter_name {
+                    *type_name = parameter_type.to_owned();
+                }
+            }
+        }
+    }
+
+    pub fn set_parameter_style(&mut self, parameter_name: &str, style: PathStyle, explode: bool) {
+        for segment in self.segments.iter_mut() {
+            if let UrlSegment::Parameter {
+                name,
+                style: parameter_style,
+                explode: parameter_explode,
+                ..
+            } = segment
+            {
+                if name == parameter_name {
+                    *parameter_style = style;
+                    *parameter_explode = explode;
+                }
+            }
+        }
+    }
+
+    /// Style and explode of a parameter, `simple` for unknown parameters
+    pub fn parameter_style(&self, parameter_name: &str) -> (PathStyle, bool) {
+        self.segments
+            .iter()
+            .find_map(|segment| match segment {
+                UrlSegment::Parameter {
+                    name,
+                    style,
+                    explode,
+                    ..
+                } if name == parameter_name => Some((*style, *explode)),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// `format!` string of the path with a `{}` for each parameter
+    pub fn format_string(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                UrlSegment::Literal(literal) => literal.replace('{', "{{").replace('}', "}}"),
+                UrlSegment::Parameter { .. } => "{}".to_owned(),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for UrlTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for segment in self.segments.iter() {
+            match segment {
+                UrlSegment::Literal(literal) => f.write_str(literal)?,
+                UrlSegment::Parameter { name, .. } => write!(f, "{{{}}}", name)?,
+            }
+        }
+        Ok(())
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Method {
     GET,
     POST,
@@ -227,6 +554,27 @@ pub enum Method {
     HEAD,
     OPTIONS,
     TRACE,
+    /// Extension method (e.g. `QUERY`), always upper case
+    Other(String),
+}
+
+impl Method {
+    pub fn is_extension(&self) -> bool {
+        matches!(self, Method::Other(_))
+    }
+
+    /// Methods defined as idempotent by RFC 9110
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Method::GET
+                | Method::HEAD
+                | Method::PUT
+                | Method::DELETE
+                | Method::OPTIONS
+                | Method::TRACE
+        )
+    }
 }
 
 impl ToString for Method {
@@ -240,17 +588,138 @@ impl ToString for Method {
             Method::HEAD => "HEAD".to_string(),
             Method::OPTIONS => "OPTIONS".to_string(),
             Method::TRACE => "TRACE".to_string(),
+            Method::Other(method) => method.clone(),
         }
     }
 }
 
+impl FromStr for Method {
+    type Err = GeneratorError;
+
+    /// Parses a method case insensitively, unknown tokens become `Method::Other`
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        let method = method.trim().to_uppercase();
+        // RFC 9110 token characters
+        if method.is_empty()
+            || !method
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+        {
+            return Err(GeneratorError::InvalidValueError(format!(
+                "Invalid HTTP method '{}'",
+                method
+            )));
+        }
+        Ok(match method.as_str() {
+            "GET" => Method::GET,
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            "DELETE" => Method::DELETE,
+            "PATCH" => Method::PATCH,
+            "HEAD" => Method::HEAD,
+            "OPTIONS" => Method::OPTIONS,
+            "TRACE" => Method::TRACE,
+            _ => Method::Other(method),
+        })
+    }
+}
+
+impl From<reqwest::Method> for Method {
+    fn from(method: reqwest::Method) -> Self {
+        Method::from(&method)
+    }
+}
+
+impl From<&reqwest::Method> for Method {
+    fn from(method: &reqwest::Method) -> Self {
+        match *method {
+            reqwest::Method::GET => Method::GET,
+            reqwest::Method::POST => Method::POST,
+            reqwest::Method::PUT => Method::PUT,
+            reqwest::Method::DELETE => Method::DELETE,
+            reqwest::Method::PATCH => Method::PATCH,
+            reqwest::Method::HEAD => Method::HEAD,
+            reqwest::Method::OPTIONS => Method::OPTIONS,
+            reqwest::Method::TRACE => Method::TRACE,
+            _ => Method::Other(method.as_str().to_uppercase()),
+        }
+    }
+}
+
+impl TryFrom<&Method> for reqwest::Method {
+    type Error = GeneratorError;
+
+    fn try_from(method: &Method) -> Result<Self, Self::Error> {
+        reqwest::Method::from_bytes(method.to_string().as_bytes()).map_err(|err| {
+            GeneratorError::InvalidValueError(format!(
+                "Invalid HTTP method '{}' {}",
+                method.to_string(),
+                err
+            ))
+        })
+    }
+}
+
 // impl std::fmt::Display for Method {
 //     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 //         write!(f, "{}", self.to_string())
 //     }
 // }
 
-#[derive(Clone, Debug)]
+/// Polling of an operation answered with `202 Accepted` and the `Location` of its status,
+/// from the `x-async-operation` extension
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AsyncOperation {
+    /// Operation id of the status endpoint, its response type is returned once completed
+    #[serde(default)]
+    pub status_operation: Option<String>,
+    /// Function of the status operation, resolved by the generator
+    #[serde(default)]
+    pub status_function: Option<String>,
+    /// Field or JSON Pointer of the state in the status response
+    #[serde(default = "default_status_field")]
+    pub status_field: String,
+    /// States ending the polling
+    #[serde(default = "default_terminal_states")]
+    pub terminal_states: Vec<String>,
+    /// Seconds between two polls
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64,
+    /// Polls before giving up, unlimited by default
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+fn default_status_field() -> String {
+    "status".to_owned()
+}
+
+fn default_terminal_states() -> Vec<String> {
+    ["succeeded", "failed", "cancelled", "completed"]
+        .iter()
+        .map(|state| state.to_string())
+        .collect()
+}
+
+fn default_poll_interval() -> u64 {
+    1
+}
+
+impl Default for AsyncOperation {
+    fn default() -> Self {
+        AsyncOperation {
+            status_operation: None,
+            status_function: None,
+            status_field: default_status_field(),
+            terminal_states: default_terminal_states(),
+            poll_interval: default_poll_interval(),
+            max_attempts: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PathDefinition {
     pub package: String,
     pub name: String,
@@ -258,13 +727,36 @@ pub struct PathDefinition {
     pub url: String,
     pub response_name: String,
     pub used_modules: Vec<ModuleInfo>,
-    pub request_body: Option<ObjectDefinition>,
+    pub request_body: Option<Arc<ObjectDefinition>>,
     pub request_entity: Option<RequestEntity>,
     pub local_objects: HashMap<String, Box<ObjectDefinition>>,
     pub description: String,
     pub response_entities: ResponseEntities,
     pub path_parameters: PathParameters,
     pub query_parameters: QueryParameters,
+    /// Safe to retry, from the method or the `x-idempotent` extension
+    pub idempotent: bool,
+    /// Checksum header of the body, from the `x-content-checksum` extension or the config
+    #[serde(default)]
+    pub content_checksum: Option<ContentChecksum>,
+    /// First tag of the operation, selects the sub-client
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Beta operation (`x-beta`), only available with the `unstable-endpoints` feature
+    #[serde(default)]
+    pub beta: bool,
+    /// Documented with `security: []`, sent without the credentials of the client
+    #[serde(default)]
+    pub anonymous: bool,
+    /// `202 Accepted` with a `Location` header, the builder polls the status until completed
+    #[serde(default)]
+    pub async_operation: Option<AsyncOperation>,
+    /// GET operation whose responses are kept by the response cache of the client
+    #[serde(default)]
+    pub cached: bool,
+    /// Circuit breaker settings of the config for this operation, the defaults apply without
+    #[serde(skip)]
+    pub circuit_breaker: Option<CircuitBreakerOperation>,
 }
 
 impl Default for PathDefinition {
@@ -283,14 +775,82 @@ impl Default for PathDefinition {
             response_entities: HashMap::new(),
             path_parameters: PathParameters::default(),
             query_parameters: QueryParameters::default(),
+            idempotent: false,
+            content_checksum: None,
+            tag: None,
+            beta: false,
+            anonymous: false,
+            async_operation: None,
+            cached: false,
+            circuit_breaker: None,
         }
     }
 }
 
 impl PathDefinition {
+    pub fn has_ndjson_request_body(&self) -> bool {
+        self.request_entity
+            .as_ref()
+            .map_or(false, |request_entity| {
+                request_entity
+                    .content
+                    .values()
+                    .any(|content| matches!(content, TransferMediaType::ApplicationNdjson(_)))
+            })
+    }
+
+    /// Content type of a JSON Patch request body, the first documented content type
+    pub fn json_patch_request_content_type(&self) -> Option<&str> {
+        let request_entity = self.request_entity.as_ref()?;
+        let mut content_types = request_entity.content.keys().collect::<Vec<_>>();
+        content_types.sort();
+        content_types
+            .first()
+            .map(|content_type| content_type.as_str())
+            .filter(|content_type| is_json_patch_content_type(content_type))
+    }
+
+    /// Whether the request body can be streamed (binary or ndjson uploads)
+    pub fn has_streaming_request_body(&self) -> bool {
+        self.request_entity
+            .as_ref()
+            .map_or(false, |request_entity| {
+                request_entity.content.values().any(|content| {
+                    matches!(
+                        content,
+                        TransferMediaType::Binary | TransferMediaType::ApplicationNdjson(_)
+                    )
+                })
+            })
+    }
+
+    /// Content type of the streamed request body, binary types first
+    pub fn streaming_request_content_type(&self) -> Option<&str> {
+        let request_entity = self.request_entity.as_ref()?;
+        let mut content_types = request_entity
+            .content
+            .iter()
+            .filter(|(_, content)| {
+                matches!(
+                    content,
+                    TransferMediaType::Binary | TransferMediaType::ApplicationNdjson(_)
+                )
+            })
+            .collect::<Vec<_>>();
+        content_types.sort_by_key(|(content_type, content)| {
+            (
+                !matches!(content, TransferMediaType::Binary),
+                content_type.as_str(),
+            )
+        });
+        content_types
+            .first()
+            .map(|(content_type, _)| content_type.as_str())
+    }
+
     pub fn get_request_type(&self) -> Option<TypeDefinition> {
         if let Some(object_definition) = &self.request_body {
-            match object_definition {
+            match object_definition.as_ref() {
                 ObjectDefinition::Struct(struct_definition) => {
                     let object_name = struct_definition.id();
                     let object_path = struct_definition.package.clone();
@@ -313,14 +873,15 @@ impl PathDefinition {
         }
         None
     }
-    pub fn extract_body_properties(&self) -> Vec<(String, PropertyDefinition)> {
+    pub fn extract_body_properties(&self) -> Vec<(&String, &PropertyDefinition)> {
         let mut properties = vec![];
         if let Some(object_definition) = &self.request_body {
-            match object_definition {
+            match object_definition.as_ref() {
                 ObjectDefinition::Struct(struct_definition) => {
                     for (name, property) in &struct_definition.properties {
-                        properties.push((name.clone(), property.clone()));
+                        properties.push((name, property));
                     }
+                    properties.sort_by(|a, b| a.0.cmp(b.0));
                 }
                 // TODO manage enums
                 _ => (),
@@ -329,68 +890,59 @@ impl PathDefinition {
         properties
     }
 
-    pub fn get_required_properties(&self) -> Vec<PropertyDefinition> {
-        let mut required_properties = vec![];
-        for (_, property) in &self.path_parameters.parameters_struct.properties {
-            if property.required {
-                required_properties.push(property.clone());
-            }
-        }
-        for (_, property) in &self.query_parameters.query_struct.properties {
-            if property.required {
-                required_properties.push(property.clone());
-            }
-        }
+    // properties are stored in HashMaps, sorting keeps the generated code stable between runs
+    fn sorted_properties(
+        properties: &HashMap<String, PropertyDefinition>,
+    ) -> Vec<&PropertyDefinition> {
+        let mut properties = properties.values().collect::<Vec<&PropertyDefinition>>();
+        properties.sort_by(|a, b| a.name.cmp(&b.name));
+        properties
+    }
 
-        for (_, property) in self.extract_body_properties() {
-            if property.required {
-                required_properties.push(property.clone());
-            }
-        }
-        required_properties
+    /// Path, query and body properties, each group sorted by name
+    fn sorted_parameter_properties(&self) -> Vec<&PropertyDefinition> {
+        let mut properties =
+            Self::sorted_properties(&self.path_parameters.parameters_struct.properties);
+        properties.extend(Self::sorted_properties(
+            &self.query_parameters.query_struct.properties,
+        ));
+        properties.extend(
+            self.extract_body_properties()
+                .into_iter()
+                .map(|(_, property)| property),
+        );
+        properties
     }
 
-    pub fn get_optional_properties(&self) -> Vec<PropertyDefinition> {
-        let mut optional_properties = vec![];
-        for (_, property) in &self.path_parameters.parameters_struct.properties {
-            if !property.required {
-                optional_properties.push(property.clone());
-            }
-        }
-        for (_, property) in &self.query_parameters.query_struct.properties {
-            if !property.required {
-                optional_properties.push(property.clone());
-            }
-        }
-        for (_, property) in self.extract_body_properties() {
-            if !property.required {
-                optional_properties.push(property.clone());
-            }
-        }
+    pub fn get_required_properties(&self) -> Vec<&PropertyDefinition> {
+        self.sorted_parameter_properties()
+            .into_iter()
+            .filter(|property| property.required)
+            .collect()
+    }
 
-        optional_properties
+    pub fn get_optional_properties(&self) -> Vec<&PropertyDefinition> {
+        self.sorted_parameter_properties()
+            .into_iter()
+            .filter(|property| !property.required)
+            .collect()
     }
 
     pub fn extract_response_modules(&self) -> Vec<ModuleInfo> {
         let mut module_imports: Vec<ModuleInfo> = vec![];
         for (_, entity) in &self.response_entities {
             for (_, content) in &entity.content {
-                match content {
-                    TransferMediaType::ApplicationJson(ref type_definition) => {
-                        match type_definition {
-                            Some(type_definition) => match type_definition.module {
-                                Some(ref module_info) => {
-                                    if module_imports.contains(module_info) {
-                                        continue;
-                                    }
-                                    module_imports.push(module_info.clone());
-                                }
-                                _ => (),
-                            },
-                            None => (),
+                match content.type_definition() {
+                    Some(type_definition) => match type_definition.module {
+                        Some(ref module_info) => {
+                            if module_imports.contains(module_info) {
+                                continue;
+                            }
+                            module_imports.push(module_info.clone());
                         }
-                    }
-                    TransferMediaType::TextPlain => (),
+                        _ => (),
+                    },
+                    None => (),
                 }
             }
         }
@@ -399,7 +951,10 @@ impl PathDefinition {
 
     pub fn extract_response_type(&self) -> Option<TypeDefinition> {
         let mut response_type = None;
-        for (_, entity) in &self.response_entities {
+        for (status_code, entity) in &self.response_entities {
+            if status_code == DEFAULT_RESPONSE_KEY {
+                continue;
+            }
             for (_, content) in &entity.content {
                 match content {
                     TransferMediaType::ApplicationJson(ref type_definition) => {
@@ -410,10 +965,52 @@ impl PathDefinition {
                             None => (),
                         }
                     }
-                    TransferMediaType::TextPlain => (),
+                    _ => (),
                 }
             }
         }
         response_type
     }
+
+    /// Whether all successful responses are documented without content, like `204 No Content`
+    pub fn has_empty_response(&self) -> bool {
+        let mut successful_responses = self
+            .response_entities
+            .iter()
+            .filter(|(status_code, _)| status_code.starts_with('2'))
+            .peekable();
+        successful_responses.peek().is_some()
+            && successful_responses.all(|(_, entity)| entity.content.is_empty())
+    }
+
+    /// Row media type (csv, ndjson) of the successful responses
+    pub fn extract_row_response(&self) -> Option<&TransferMediaType> {
+        self.response_entities
+            .iter()
+            .filter(|(status_code, _)| status_code.as_str() != DEFAULT_RESPONSE_KEY)
+            .flat_map(|(_, entity)| entity.content.values())
+            .find(|content| content.is_row_format() && content.type_definition().is_some())
+    }
+
+    /// All content types the successful responses are documented with, sorted
+    pub fn extract_response_content_types(&self) -> Vec<String> {
+        let mut content_types = self
+            .response_entities
+            .iter()
+            .filter(|(status_code, _)| status_code.as_str() != DEFAULT_RESPONSE_KEY)
+            .flat_map(|(_, entity)| entity.content.keys().cloned())
+            .collect::<Vec<String>>();
+        content_types.sort();
+        content_types.dedup();
+        content_types
+    }
+
+    /// Json type of the `default` response, returned for all undocumented status codes
+    pub fn extract_default_response_type(&self) -> Option<TypeDefinition> {
+        let entity = self.response_entities.get(DEFAULT_RESPONSE_KEY)?;
+        entity.content.values().find_map(|content| match content {
+            TransferMediaType::ApplicationJson(type_definition) => type_definition.clone(),
+            _ => None,
+        })
+    }
 }
@@ -1,13 +1,28 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
-use opage::generator::generator::Generator;
+use opage::generator::diagnostics::Severity;
+use opage::generator::dialect::read_spec;
+use opage::generator::generator::{Generator, RunOptions};
+use opage::generator::statistics::GenerationStatistics;
+use opage::utils::artifact::{write_artifacts, ArchiveWriter};
 use opage::utils::config::Config;
-use tracing::{error, info};
+use opage::utils::name_mapping::SchemaPointer;
+use opage::utils::snapshot::{bless_snapshots, compare_snapshots, generate_in_memory};
+use tracing::{error, warn};
+use tracing_subscriber::EnvFilter;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use opage::Language;
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab_case")]
+pub enum LogFormat {
+    Compact,
+    Pretty,
+    Json,
+}
+
 #[derive(Parser)]
 #[clap(author, version, about)]
 pub struct Cli {
@@ -15,13 +30,26 @@ pub struct Cli {
     #[clap(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
 
+    /// Only log errors (overrides verbose)
+    #[clap(short, long)]
+    pub quiet: bool,
+
+    /// Format of the log output
+    #[arg(long, value_enum, default_value = "compact")]
+    pub log_format: LogFormat,
+
     /// (json) Configuration with name mappings and ignores
     #[arg(short, long, value_name = "FILE")]
     pub config: Option<PathBuf>,
 
     /// Client output location
-    #[arg(short, long, value_name = "FILE")]
-    pub output_dir: PathBuf,
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        required_unless_present_any = ["emit_ir", "explain_name", "snapshot_dir", "archive"]
+    )]
+    pub output_dir: Option<PathBuf>,
 
     /// SInput OpenAPI spec/specs
     #[arg(short, long, value_name = "FILE")]
@@ -29,28 +57,103 @@ pub struct Cli {
     /// What mode to run the program in
     #[arg(value_enum, default_value = "rust")]
     pub language: Language,
+
+    /// Write the analysed specs as JSON intermediate representation and stop
+    #[arg(long, value_name = "FILE", conflicts_with = "from_ir")]
+    pub emit_ir: Option<PathBuf>,
+
+    /// Render the client from a JSON intermediate representation instead of specs
+    #[arg(long, value_name = "FILE")]
+    pub from_ir: Option<PathBuf>,
+
+    /// Delete previously generated files which are no longer produced
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Additionally write the generation summary as JSON
+    #[arg(long, value_name = "FILE")]
+    pub summary_json: Option<PathBuf>,
+
+    /// Print the generated file (relative to the output dir, e.g. src/lib.rs) to stdout
+    #[arg(long, value_name = "FILE")]
+    pub emit_stdout: Option<PathBuf>,
+
+    /// Exit with 0 even if some components, paths or stages failed
+    #[arg(long)]
+    pub allow_partial: bool,
+
+    /// Exit with an error if the specs produced warnings
+    #[arg(long)]
+    pub deny_warnings: bool,
+
+    /// Only generate the models
+    #[arg(long, conflicts_with = "client_only")]
+    pub models_only: bool,
+
+    /// Only generate the client, the models are used from the crate given by --models-crate
+    #[arg(long, requires = "models_crate")]
+    pub client_only: bool,
+
+    /// Crate which provides the models for --client-only (e.g. my_models)
+    #[arg(long, value_name = "CRATE")]
+    pub models_crate: Option<String>,
+
+    /// Print the name generated for a schema or property JSON Pointer
+    /// (e.g. '#/components/schemas/Foo/properties/bar') and stop
+    #[arg(long, value_name = "POINTER")]
+    pub explain_name: Option<String>,
+
+    /// Generate in memory and compare the files with the snapshots in DIR instead of writing them
+    #[arg(long, value_name = "DIR")]
+    pub snapshot_dir: Option<PathBuf>,
+
+    /// Update the snapshots of --snapshot-dir with the generated files
+    #[arg(long, requires = "snapshot_dir")]
+    pub bless: bool,
+
+    /// Write the generated files into a .tar.gz archive instead of the output dir
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["emit_ir", "emit_stdout", "prune"])]
+    pub archive: Option<PathBuf>,
 }
 
+/// Exit code used when the generation produced incomplete output
+const PARTIAL_FAILURE_EXIT_CODE: i32 = 2;
+
 fn main() {
     let cli = Cli::parse();
 
     // we setup logging
-    let tracing_level = match cli.verbose {
-        0 => tracing::Level::WARN,
-        1 => tracing::Level::INFO,
-        2 => tracing::Level::DEBUG,
+    let tracing_level = match (cli.quiet, cli.verbose) {
+        (true, _) => tracing::Level::ERROR,
+        (false, 0) => tracing::Level::WARN,
+        (false, 1) => tracing::Level::INFO,
+        (false, 2) => tracing::Level::DEBUG,
         _ => tracing::Level::TRACE,
     };
 
-    tracing_subscriber::fmt()
-        .compact()
+    // RUST_LOG (e.g. opage::generator=debug) refines the level per module,
+    // an explicit -q or -v wins over its global level
+    let mut env_filter = EnvFilter::builder()
+        .with_default_directive(tracing_level.into())
+        .from_env_lossy();
+    if cli.quiet || cli.verbose > 0 {
+        env_filter = env_filter.add_directive(tracing_level.into());
+    }
+
+    let subscriber = tracing_subscriber::fmt()
         .with_thread_names(true)
-        // enable everything
-        .with_max_level(tracing_level)
-        // sets this to be the default, global subscriber for this application.
-        .init();
+        .with_env_filter(env_filter)
+        // stdout is kept for --emit-stdout
+        .with_writer(std::io::stderr);
 
-    let output_dir = cli.output_dir;
+    // sets this to be the default, global subscriber for this application.
+    match cli.log_format {
+        LogFormat::Compact => subscriber.compact().init(),
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+
+    let output_dir = cli.output_dir.unwrap_or_default();
     let spec_file_paths = cli.specs;
     let config_file_path = cli.config;
 
@@ -63,27 +166,162 @@ fn main() {
     };
 
     config.set_language(cli.language);
+    if cli.models_only {
+        config.set_models_only();
+    }
+    if cli.client_only {
+        config.set_client_only(cli.models_crate.as_deref().unwrap_or_default());
+    }
+    if config.project_metadata.needs_spec_version() {
+        match spec_file_paths
+            .first()
+            .map(|spec_file_path| read_spec(spec_file_path))
+        {
+            Some(Ok(spec)) => config
+                .project_metadata
+                .apply_spec_version(&spec.info.version),
+            Some(Err(err)) => error!("Reading info.version failed: {}", err),
+            None => warn!("info.version needs a spec, the spec version is not available"),
+        }
+    }
     config.validate();
 
-    let generator = Generator::new(config, output_dir, spec_file_paths);
+    if let Some(ref pointer) = cli.explain_name {
+        explain_name(&config, &spec_file_paths, pointer);
+        return;
+    }
+
+    if let Some(ref snapshot_dir) = cli.snapshot_dir {
+        check_snapshots(config, spec_file_paths, snapshot_dir, cli.bless);
+        return;
+    }
+
+    let deny_warnings = cli.deny_warnings || config.deny_warnings;
+    let generator = Generator::new(config, output_dir.clone(), spec_file_paths);
+
+    let run_options = RunOptions {
+        from_ir: cli.from_ir,
+        emit_ir: cli.emit_ir.clone(),
+        prune: cli.prune,
+    };
+    let result = match cli.archive {
+        // paths in the archive are relative to the output dir
+        Some(ref archive_path) => generator.artifacts(&run_options).and_then(|artifacts| {
+            write_artifacts(
+                artifacts,
+                &mut ArchiveWriter::new(archive_path, &output_dir),
+            )
+            .map(|_| ())
+            // a missing archive fails the run like any other stage
+            .inspect_err(|_| generator.record_error("archive"))
+        }),
+        None => generator.run(&run_options),
+    };
+    if let Err(err) = result {
+        error!("Generation failed: {}", err);
+    }
 
-    match generator.generate_paths() {
-        Ok(_) => info!("Generation paths completed"),
-        Err(err) => error!("Generation failed: {}", err),
+    if cli.emit_ir.is_some() {
+        exit_on_failures(&generator.statistics(), cli.allow_partial);
+        return;
     }
 
-    match generator.generate_clients() {
-        Ok(_) => info!("Generation clients completed"),
-        Err(err) => error!("Generation clients failed: {}", err),
+    let statistics = generator.statistics();
+    if !generator.diagnostics().is_empty() {
+        eprint!("{}", generator.diagnostics());
+    }
+    match cli.emit_stdout {
+        // stdout only carries the file so it can be piped
+        Some(ref emitted_file_path) => {
+            eprintln!("{}", statistics);
+            match std::fs::read_to_string(output_dir.join(emitted_file_path)) {
+                Ok(content) => print!("{}", content),
+                Err(err) => error!("Emitting {} failed: {}", emitted_file_path.display(), err),
+            }
+        }
+        None => println!("{}", statistics),
+    }
+    if let Some(summary_file_path) = cli.summary_json {
+        let summary =
+            serde_json::to_string_pretty(&statistics).expect("Failed to serialize summary");
+        if let Err(err) = std::fs::write(&summary_file_path, summary) {
+            error!(
+                "Writing summary {} failed: {}",
+                summary_file_path.display(),
+                err
+            );
+        }
     }
+    if deny_warnings && generator.diagnostics().count(Severity::Warning) > 0 {
+        error!("Generation reported warnings, failing because of --deny-warnings");
+        std::process::exit(PARTIAL_FAILURE_EXIT_CODE);
+    }
+    exit_on_failures(&statistics, cli.allow_partial);
+}
 
-    match generator.generate_objects() {
-        Ok(_) => info!("Generation objects completed"),
-        Err(err) => error!("Generation objects failed: {}", err),
+fn explain_name(config: &Config, spec_file_paths: &[PathBuf], pointer: &str) {
+    let schema_pointer = match SchemaPointer::parse(pointer) {
+        Some(schema_pointer) => schema_pointer,
+        None => {
+            error!(
+                "{} is not a #/components/schemas/<name>[/properties/<name>] pointer",
+                pointer
+            );
+            std::process::exit(1);
+        }
+    };
+    let schema_name = match schema_pointer {
+        SchemaPointer::Schema(ref schema) | SchemaPointer::Property(ref schema, _) => schema,
+    };
+    // the title of the schema takes precedence over its key
+    let title = spec_file_paths
+        .iter()
+        .filter_map(|spec_file_path| read_spec(spec_file_path).ok())
+        .find_map(|spec| {
+            let schema = spec.components.as_ref()?.schemas.get(schema_name)?;
+            schema.resolve(&spec).ok()?.title
+        });
+    println!(
+        "{}",
+        config
+            .name_mapping
+            .explain(&schema_pointer, title.as_deref())
+    );
+}
+
+fn check_snapshots(
+    config: Config,
+    spec_file_paths: Vec<PathBuf>,
+    snapshot_dir: &Path,
+    bless: bool,
+) {
+    let report = generate_in_memory(config, spec_file_paths).and_then(|files| match bless {
+        true => bless_snapshots(&files, snapshot_dir),
+        false => compare_snapshots(&files, snapshot_dir),
+    });
+    match report {
+        Ok(report) if report.is_empty() => println!("Snapshots up to date"),
+        Ok(report) if bless => print!("{}", report),
+        Ok(report) => {
+            print!("{}", report);
+            error!("Generated files differ from the snapshots, run with --bless to update them");
+            std::process::exit(1);
+        }
+        Err(err) => {
+            error!("Snapshot check failed: {}", err);
+            std::process::exit(1);
+        }
     }
+}
 
-    match generator.populate_client_files() {
-        Ok(_) => info!("Generation client files completed"),
-        Err(err) => error!("Generation client files failed: {}", err),
+fn exit_on_failures(statistics: &GenerationStatistics, allow_partial: bool) {
+    if !statistics.has_failures() {
+        return;
+    }
+    if allow_partial {
+        warn!("Generation incomplete, continuing because of --allow-partial");
+        return;
     }
+    error!("Generation incomplete, see the summary for failed stages");
+    std::process::exit(PARTIAL_FAILURE_EXIT_CODE);
 }
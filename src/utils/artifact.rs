@@ -0,0 +1,213 @@
+//! Files produced by a generation and the writers consuming them.
+//!
+//! `Generator::artifacts` runs a generation in memory and returns its files,
+//! which are then handed to a writer: the file system, memory or an archive.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::GeneratorError;
+
+use super::file::{write_to_disk, MANIFEST_FILE_NAME};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactKind {
+    /// Rust source file
+    Source,
+    /// Cargo manifest and other configuration files
+    Config,
+    /// README and other documentation
+    Documentation,
+    /// List of the generated files, see `update_manifest`
+    Manifest,
+}
+
+impl ArtifactKind {
+    /// Kind of a file, by its name
+    pub fn of(path: &Path) -> Self {
+        let file_name = path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy())
+            .unwrap_or_default();
+        if file_name == MANIFEST_FILE_NAME {
+            return ArtifactKind::Manifest;
+        }
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("rs") => ArtifactKind::Source,
+            Some("md") => ArtifactKind::Documentation,
+            _ => ArtifactKind::Config,
+        }
+    }
+}
+
+/// Generated file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileArtifact {
+    pub path: PathBuf,
+    pub content: String,
+    pub kind: ArtifactKind,
+}
+
+impl FileArtifact {
+    pub fn new(path: PathBuf, content: String) -> Self {
+        FileArtifact {
+            kind: ArtifactKind::of(&path),
+            path,
+            content,
+        }
+    }
+}
+
+/// Destination of the generated files
+pub trait ArtifactWriter {
+    /// Writes the artifact, returns whether something changed
+    fn write(&mut self, artifact: &FileArtifact) -> Result<bool, GeneratorError>;
+
+    /// Called once all artifacts were written
+    fn finish(&mut self) -> Result<(), GeneratorError> {
+        Ok(())
+    }
+}
+
+/// Writes the artifacts to disk, unchanged files are skipped and hand-written regions kept
+#[derive(Debug, Default)]
+pub struct FsWriter;
+
+impl ArtifactWriter for FsWriter {
+    fn write(&mut self, artifact: &FileArtifact) -> Result<bool, GeneratorError> {
+        write_to_disk(&artifact.path, &artifact.content)
+    }
+}
+
+/// Keeps the content of the artifacts by path
+#[derive(Debug, Default)]
+pub struct MemoryWriter {
+    pub files: BTreeMap<PathBuf, String>,
+}
+
+impl ArtifactWriter for MemoryWriter {
+    fn write(&mut self, artifact: &FileArtifact) -> Result<bool, GeneratorError> {
+        let previous = self
+            .files
+            .insert(artifact.path.clone(), artifact.content.clone());
+        Ok(previous.as_ref() != Some(&artifact.content))
+    }
+}
+
+/// Packs the artifacts into a `.tar.gz` archive, with paths relative to `root`.
+///
+/// Entries are sorted and carry no timestamp, so the same output gives the same archive.
+#[derive(Debug)]
+pub struct ArchiveWriter {
+    archive_path: PathBuf,
+    root: PathBuf,
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl ArchiveWriter {
+    pub fn new(archive_path: &Path, root: &Path) -> Self {
+        ArchiveWriter {
+            archive_path: archive_path.to_path_buf(),
+            root: root.to_path_buf(),
+            files: BTreeMap::new(),
+        }
+    }
+}
+
+impl ArtifactWriter for ArchiveWriter {
+    fn write(&mut self, artifact: &FileArtifact) -> Result<bool, GeneratorError> {
+        let path = artifact
+            .path
+            .strip_prefix(&self.root)
+            .unwrap_or(&artifact.path)
+            .to_path_buf();
+        self.files.insert(path, artifact.content.clone());
+        Ok(true)
+    }
+
+    fn finish(&mut self) -> Result<(), GeneratorError> {
+        let archive_error = |err: std::io::Error| {
+            GeneratorError::FileCreationError(
+                self.archive_path.to_string_lossy().to_string(),
+                err.to_string(),
+            )
+        };
+        if let Some(parent) = self.archive_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent).map_err(archive_error)?;
+            }
+        }
+        let file = File::create(&self.archive_path).map_err(archive_error)?;
+        let mut archive = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        for (path, content) in self.files.iter() {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(0);
+            archive
+                .append_data(&mut header, path, content.as_bytes())
+                .map_err(archive_error)?;
+        }
+        archive
+            .into_inner()
+            .and_then(|encoder| encoder.finish())
+            .map_err(archive_error)?;
+        Ok(())
+    }
+}
+
+/// Hands the artifacts to the writer and finishes it, returns the number of changed files
+pub fn write_artifacts<I: IntoIterator<Item = FileArtifact>>(
+    artifacts: I,
+    writer: &mut dyn ArtifactWriter,
+) -> Result<usize, GeneratorError> {
+    let mut changed = 0;
+    for artifact in artifacts {
+        if writer.write(&artifact)? {
+            changed += 1;
+        }
+    }
+    writer.finish()?;
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_kind() {
+        assert_eq!(
+            ArtifactKind::of(Path::new("out/src/lib.rs")),
+            ArtifactKind::Source
+        );
+        assert_eq!(
+            ArtifactKind::of(Path::new("out/Cargo.toml")),
+            ArtifactKind::Config
+        );
+        assert_eq!(
+            ArtifactKind::of(Path::new("out/README.md")),
+            ArtifactKind::Documentation
+        );
+        assert_eq!(
+            ArtifactKind::of(Path::new("out/.opage-manifest")),
+            ArtifactKind::Manifest
+        );
+    }
+
+    #[test]
+    fn test_memory_writer() {
+        let mut writer = MemoryWriter::default();
+        let artifacts = vec![
+            FileArtifact::new(PathBuf::from("src/lib.rs"), "pub mod client;\n".to_owned()),
+            FileArtifact::new(PathBuf::from("src/lib.rs"), "pub mod client;\n".to_owned()),
+        ];
+        assert_eq!(write_artifacts(artifacts, &mut writer).unwrap(), 1);
+        assert_eq!(writer.files.len(), 1);
+    }
+}
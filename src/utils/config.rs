@@ -1,12 +1,19 @@
 use convert_case::Casing;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_aux::prelude::*;
-use std::{fs::File, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    path::Path,
+};
+use tracing::warn;
 
 use crate::Language;
 
 use super::{name_mapping::NameMapping, spec_ignore::SpecIgnore};
 
+const DEFAULT_EDITION: &str = "2024";
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Default)]
 pub struct ProjectMetadata {
     #[serde(default)]
@@ -19,6 +26,25 @@ pub struct ProjectMetadata {
     pub user_agent: String,
     #[serde(default = "default_server_url")]
     pub server_url: String,
+    /// Headers sent with every request by the generated client, values may contain
+    /// `{name}`, `{version}`, `{client_name}`, `{spec_version}` and `{opage_version}`
+    #[serde(default)]
+    pub default_headers: BTreeMap<String, String>,
+    /// Rust edition of the generated crate: 2018, 2021 or 2024
+    #[serde(default)]
+    pub edition: String,
+    /// Minimum supported Rust version, `rust-version` of the generated Cargo.toml
+    #[serde(default)]
+    pub rust_version: Option<String>,
+    /// Take the version from the spec `info.version` instead of `version`
+    #[serde(default)]
+    pub version_from_spec: bool,
+    /// Appended to the version taken from the spec, e.g. `-beta.1` or `+build.42`
+    #[serde(default)]
+    pub version_suffix: String,
+    /// `info.version` of the first spec, set before generation
+    #[serde(skip)]
+    pub spec_version: String,
 }
 
 impl ProjectMetadata {
@@ -28,6 +54,54 @@ impl ProjectMetadata {
         }
     }
 
+    /// Uses the spec `info.version` (`v2.1` becomes `2.1.0`) when `version_from_spec` is set,
+    /// versions which are not semver keep the configured one
+    pub fn apply_spec_version(&mut self, spec_version: &str) {
+        self.spec_version = spec_version.trim().to_owned();
+        if !self.version_from_spec {
+            return;
+        }
+        let spec_version = spec_version.trim().trim_start_matches(['v', 'V']);
+        let (core, pre_release) = match spec_version.find(['-', '+']) {
+            Some(position) => spec_version.split_at(position),
+            None => (spec_version, ""),
+        };
+        let mut parts = core.split('.').collect::<Vec<&str>>();
+        if parts.is_empty()
+            || parts.len() > 3
+            || parts
+                .iter()
+                .any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()))
+        {
+            warn!(
+                "info.version {} is not a semver version, keeping {}",
+                spec_version, self.version
+            );
+            return;
+        }
+        parts.resize(3, "0");
+        self.version = format!("{}{}{}", parts.join("."), pre_release, self.version_suffix);
+    }
+
+    /// Whether the spec `info.version` is used by the version or a default header
+    pub fn needs_spec_version(&self) -> bool {
+        self.version_from_spec
+            || self
+                .default_headers
+                .values()
+                .any(|value| value.contains("{spec_version}"))
+    }
+
+    /// Value of a default header with the placeholders replaced
+    pub fn render_header_value(&self, value: &str) -> String {
+        value
+            .replace("{name}", &self.name)
+            .replace("{version}", &self.version)
+            .replace("{client_name}", &self.client_name)
+            .replace("{spec_version}", &self.spec_version)
+            .replace("{opage_version}", env!("CARGO_PKG_VERSION"))
+    }
+
     pub fn validate(&self) -> Self {
         let version = if self.version.is_empty() {
             "0.1.0".to_string()
@@ -43,6 +117,14 @@ impl ProjectMetadata {
         } else {
             self.client_name.clone()
         };
+        let edition = match self.edition.as_str() {
+            "" => DEFAULT_EDITION.to_string(),
+            "2018" | "2021" | "2024" => self.edition.clone(),
+            edition => {
+                warn!("Unsupported edition {}, using {}", edition, DEFAULT_EDITION);
+                DEFAULT_EDITION.to_string()
+            }
+        };
         let user_agent = if self.user_agent.is_empty() {
             format!(
                 "{}/{}",
@@ -58,10 +140,313 @@ impl ProjectMetadata {
             client_name,
             user_agent,
             server_url: self.server_url.clone(),
+            default_headers: self.default_headers.clone(),
+            edition,
+            rust_version: self.rust_version.clone(),
+            version_from_spec: self.version_from_spec,
+            version_suffix: self.version_suffix.clone(),
+            spec_version: self.spec_version.clone(),
+        }
+    }
+}
+
+/// Rust type of successful responses documented without schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackResponseType {
+    /// `serde_json::Value`
+    #[default]
+    Value,
+    /// `bytes::Bytes`, the raw body
+    Bytes,
+    /// `String`, the body as text
+    String,
+}
+
+impl FallbackResponseType {
+    pub fn rust_type(&self) -> &'static str {
+        match self {
+            FallbackResponseType::Value => "serde_json::Value",
+            FallbackResponseType::Bytes => "bytes::Bytes",
+            FallbackResponseType::String => "String",
+        }
+    }
+
+    /// `execute_<reader>` used instead of the json one
+    pub fn reader(&self) -> Option<&'static str> {
+        match self {
+            FallbackResponseType::Value => None,
+            FallbackResponseType::Bytes => Some("bytes"),
+            FallbackResponseType::String => Some("text"),
+        }
+    }
+}
+
+/// TLS backend enabled by the default features of the generated crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum TlsBackend {
+    /// Pure rust TLS (`rustls` feature)
+    #[default]
+    Rustls,
+    /// Platform TLS library (`native-tls` feature)
+    NativeTls,
+    /// No TLS backend by default, selected by the user of the crate
+    None,
+}
+
+impl TlsBackend {
+    /// Feature of the generated Cargo.toml
+    pub fn feature(&self) -> Option<&'static str> {
+        match self {
+            TlsBackend::Rustls => Some("rustls"),
+            TlsBackend::NativeTls => Some("native-tls"),
+            TlsBackend::None => None,
+        }
+    }
+}
+
+/// What deserialization does with fields not documented in the spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownFields {
+    /// Unknown fields are dropped
+    #[default]
+    Ignore,
+    /// Unknown fields fail deserialization (`deny_unknown_fields`), for contract testing
+    Deny,
+    /// Unknown fields are kept in a flattened `unknown` map and serialized back
+    Capture,
+}
+
+/// How generated models are split into files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLayout {
+    /// One file per namespace
+    #[default]
+    Namespace,
+    /// One file per top level package, nested namespaces become inline modules
+    Package,
+    /// All models in a single `models.rs`
+    SingleFile,
+}
+
+/// Stages run by `Generator::run` after the specs have been analysed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct GenerationStages {
+    #[serde(default = "bool_true")]
+    pub clients: bool,
+    #[serde(default = "bool_true")]
+    pub objects: bool,
+    #[serde(default = "bool_true")]
+    pub client_files: bool,
+}
+
+impl Default for GenerationStages {
+    fn default() -> Self {
+        GenerationStages {
+            clients: true,
+            objects: true,
+            client_files: true,
+        }
+    }
+}
+
+/// `serde_with` adapter used for a field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerdeWithAdapter {
+    /// Numbers sent as strings
+    DisplayFromStr,
+    /// Unix timestamps in seconds as `SystemTime`
+    TimestampSeconds,
+    /// Unix timestamps in milliseconds as `SystemTime`
+    TimestampMilliseconds,
+    /// Comma separated string as `Vec<String>`
+    CommaSeparated,
+    /// Base64 encoded string as `Vec<u8>`
+    Base64,
+}
+
+impl SerdeWithAdapter {
+    /// Adapter implied by the schema `format` of a field with the type `type_name`
+    pub fn from_format(type_name: &str, format: &str) -> Option<Self> {
+        match (type_name, format) {
+            ("String", "int32" | "int64" | "float" | "double") => {
+                Some(SerdeWithAdapter::DisplayFromStr)
+            }
+            ("String", "byte") => Some(SerdeWithAdapter::Base64),
+            ("i32" | "i64", "unix-time" | "timestamp") => Some(SerdeWithAdapter::TimestampSeconds),
+            ("i32" | "i64", "unix-time-millis" | "timestamp-millis") => {
+                Some(SerdeWithAdapter::TimestampMilliseconds)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+pub struct SerdeWithConfig {
+    // Select adapters by the schema format (int64 strings, byte, unix-time)
+    #[serde(default)]
+    pub by_format: bool,
+    // Adapters per field path `Struct.field` (name of the spec)
+    #[serde(default)]
+    pub fields: HashMap<String, SerdeWithAdapter>,
+}
+
+impl SerdeWithConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.by_format || !self.fields.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Default)]
+pub struct ResponseCacheConfig {
+    // Cache the responses of all GET operations
+    #[serde(default)]
+    pub all: bool,
+    // Cache by operationId, overriding `all` (`false` keeps an operation out of the cache)
+    #[serde(default)]
+    pub operations: HashMap<String, bool>,
+}
+
+impl ResponseCacheConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.all || self.operations.values().any(|cached| *cached)
+    }
+
+    pub fn caches(&self, operation_id: &str) -> bool {
+        self.operations
+            .get(operation_id)
+            .copied()
+            .unwrap_or(self.all)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CircuitBreakerConfig {
+    // Consecutive failures opening the circuit of an operation
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    // Seconds an open circuit rejects requests before letting one through
+    #[serde(default = "default_reset_timeout_secs")]
+    pub reset_timeout_secs: u64,
+    // Settings by operationId
+    #[serde(default)]
+    pub operations: HashMap<String, CircuitBreakerOperation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CircuitBreakerOperation {
+    // `false` sends the requests of the operation without circuit breaker
+    #[serde(default = "default_circuit_breaker_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub failure_threshold: Option<u32>,
+    #[serde(default)]
+    pub reset_timeout_secs: Option<u64>,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: default_failure_threshold(),
+            reset_timeout_secs: default_reset_timeout_secs(),
+            operations: HashMap::new(),
+        }
+    }
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_reset_timeout_secs() -> u64 {
+    30
+}
+
+fn default_circuit_breaker_enabled() -> bool {
+    true
+}
+
+/// Arbitrary precision type used instead of f64
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecimalType {
+    RustDecimal,
+    BigDecimal,
+}
+
+impl DecimalType {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            DecimalType::RustDecimal => "rust_decimal::Decimal",
+            DecimalType::BigDecimal => "bigdecimal::BigDecimal",
+        }
+    }
+
+    /// Dependency line of the generated Cargo.toml
+    pub fn dependency(&self) -> &'static str {
+        match self {
+            DecimalType::RustDecimal => {
+                r#"rust_decimal = { version = "1.37.1", features = ["serde-arbitrary-precision"] }"#
+            }
+            DecimalType::BigDecimal => {
+                r#"bigdecimal = { version = "0.4.8", features = ["serde"] }"#
+            }
+        }
+    }
+}
+
+/// Hash of the serialized body sent in a header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentChecksum {
+    /// Base64 md5 digest in `Content-MD5`
+    Md5,
+    /// Hex sha256 digest in `x-amz-content-sha256`
+    Sha256,
+}
+
+impl ContentChecksum {
+    /// Variant of the generated `client::ContentChecksum`
+    pub fn rust_expression(&self) -> &'static str {
+        match self {
+            ContentChecksum::Md5 => "crate::client::ContentChecksum::Md5",
+            ContentChecksum::Sha256 => "crate::client::ContentChecksum::Sha256",
         }
     }
 }
 
+/// Preset of the generated client for an environment (e.g. dev, staging, prod)
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+pub struct ClientProfile {
+    // Base url of the environment, the server of the spec when missing
+    #[serde(default)]
+    pub base_url: Option<String>,
+    // Timeout of a whole request in seconds
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    // Timeout of establishing the connection in seconds
+    #[serde(default)]
+    pub connect_timeout_secs: Option<u64>,
+    // Timeout of each read of the response in seconds
+    #[serde(default)]
+    pub read_timeout_secs: Option<u64>,
+    // Proxy url, e.g. `http://proxy.internal:3128`
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+/// Models of `source` converted into the models with the same name in `target`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VersionConversion {
+    pub source: String,
+    pub target: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Config {
     pub project_metadata: ProjectMetadata,
@@ -79,6 +464,101 @@ pub struct Config {
     pub serde_deserialize: bool,
     #[serde(default = "default_language")]
     pub language: Language,
+    // Derive Copy for small structs/enums made only of Copy types
+    #[serde(default)]
+    pub derive_copy: bool,
+    #[serde(default = "default_copy_max_fields")]
+    pub copy_max_fields: usize,
+    // Requests in flight for the generated send_batch helpers
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
+    #[serde(default)]
+    pub layout: OutputLayout,
+    // Keep the schema types of path parameters instead of String
+    #[serde(default = "bool_true")]
+    pub typed_parameters: bool,
+    #[serde(default)]
+    pub stages: GenerationStages,
+    // Crate providing the models when they are not generated (client only)
+    #[serde(default)]
+    pub models_crate: Option<String>,
+    // Prefix of the models referenced by the client, e.g. `my_models::`
+    #[serde(default = "default_model_path_prefix")]
+    pub model_path_prefix: String,
+    // Packages (e.g. models::v1 -> models::v2) to generate From/TryFrom impls for
+    #[serde(default)]
+    pub version_conversions: Vec<VersionConversion>,
+    // Serde round trip tests for models with an example
+    #[serde(default)]
+    pub example_tests: bool,
+    #[serde(default)]
+    pub serde_with: SerdeWithConfig,
+    // `format: decimal` numbers (and all numbers of `decimal_packages`) use this type
+    #[serde(default)]
+    pub decimal: Option<DecimalType>,
+    #[serde(default)]
+    pub decimal_packages: Vec<String>,
+    // Type of integers without (or with an unknown) format
+    #[serde(default = "default_integer_type")]
+    pub integer_type: String,
+    // `[T; N]` for arrays of primitives with minItems == maxItems
+    #[serde(default)]
+    pub fixed_size_arrays: bool,
+    // Required parameters are arguments of the builder constructor instead of runtime checks
+    #[serde(default)]
+    pub typestate_builders: bool,
+    // Checksum header of the body by operationId, `x-content-checksum` on the operation takes precedence
+    #[serde(default)]
+    pub content_checksums: HashMap<String, ContentChecksum>,
+    // `Unknown(serde_json::Value)` catch-all variant in oneOf/anyOf enums
+    #[serde(default)]
+    pub unknown_enum_variant: bool,
+    #[serde(default)]
+    pub unknown_fields: UnknownFields,
+    // Operations grouped by their first tag into sub-clients (`client.indices().create(..)`)
+    #[serde(default)]
+    pub sub_clients: bool,
+    // Futures of the generated client are Send (work-stealing runtimes), or may be !Send (current-thread, wasm)
+    #[serde(default = "default_send_futures")]
+    pub send_futures: bool,
+    #[serde(default)]
+    pub tls: TlsBackend,
+    // Dependencies of the generated Cargo.toml: a version or a TOML table overrides/adds one, null removes it
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Option<String>>,
+    // The generated crate inherits package fields and dependencies from its cargo workspace
+    #[serde(default)]
+    pub workspace_member: bool,
+    // Namespace of the models and suffix of the operations of a spec, by spec file name or path
+    #[serde(default)]
+    pub spec_namespaces: HashMap<String, String>,
+    // Type of json responses without schema: value, bytes or string
+    #[serde(default)]
+    pub fallback_response_type: FallbackResponseType,
+    // `# Examples` section (no_run doc test) on the client functions
+    #[serde(default)]
+    pub doc_examples: bool,
+    // Extension marking beta operations, generated behind the `unstable-endpoints` feature
+    #[serde(default = "default_beta_extension")]
+    pub beta_extension: String,
+    // Exit with an error if the generation reported warnings
+    #[serde(default)]
+    pub deny_warnings: bool,
+    // Client presets by environment name, generated as the `ClientProfile` enum
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ClientProfile>,
+    // "Generated by opage" header in the generated files, .gitattributes and .editorconfig
+    #[serde(default)]
+    pub generated_headers: bool,
+    // Property of the responses holding hypermedia links (HAL `_links`), models having it get link helpers
+    #[serde(default = "default_links_property")]
+    pub links_property: String,
+    // In-memory cache (moka) of the responses of GET operations, keyed by url
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    // Defaults and per operation settings of the circuit breakers (`circuit-breaker` feature)
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 pub fn default_client_name() -> String {
@@ -89,10 +569,38 @@ pub fn default_server_url() -> String {
     "http://localhost:8080".to_string()
 }
 
+pub fn default_model_path_prefix() -> String {
+    "crate::".to_string()
+}
+
+pub fn default_integer_type() -> String {
+    "i32".to_string()
+}
+
+pub fn default_send_futures() -> bool {
+    true
+}
+
+pub fn default_beta_extension() -> String {
+    "x-beta".to_owned()
+}
+
+pub fn default_links_property() -> String {
+    "_links".to_owned()
+}
+
 pub fn default_language() -> Language {
     Language::Rust
 }
 
+pub fn default_copy_max_fields() -> usize {
+    4
+}
+
+pub fn default_batch_concurrency() -> usize {
+    8
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -105,6 +613,40 @@ impl Default for Config {
             serde_serialize: true,
             serde_deserialize: true,
             language: default_language(),
+            derive_copy: false,
+            copy_max_fields: default_copy_max_fields(),
+            batch_concurrency: default_batch_concurrency(),
+            layout: OutputLayout::default(),
+            typed_parameters: true,
+            stages: GenerationStages::default(),
+            models_crate: None,
+            model_path_prefix: default_model_path_prefix(),
+            version_conversions: vec![],
+            example_tests: false,
+            serde_with: SerdeWithConfig::default(),
+            decimal: None,
+            decimal_packages: vec![],
+            integer_type: default_integer_type(),
+            fixed_size_arrays: false,
+            typestate_builders: false,
+            content_checksums: HashMap::new(),
+            unknown_enum_variant: false,
+            unknown_fields: UnknownFields::Ignore,
+            sub_clients: false,
+            send_futures: default_send_futures(),
+            tls: TlsBackend::Rustls,
+            dependencies: BTreeMap::new(),
+            workspace_member: false,
+            spec_namespaces: HashMap::new(),
+            fallback_response_type: FallbackResponseType::Value,
+            doc_examples: false,
+            beta_extension: default_beta_extension(),
+            deny_warnings: false,
+            profiles: BTreeMap::new(),
+            generated_headers: false,
+            links_property: default_links_property(),
+            response_cache: ResponseCacheConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -129,7 +671,70 @@ impl Config {
         self.language = language;
     }
 
+    /// Only the models, without client, builders and runtime files
+    pub fn set_models_only(&mut self) {
+        self.stages.clients = false;
+        self.stages.objects = true;
+    }
+
+    /// Only the client, the models are re-exported from `models_crate`
+    pub fn set_client_only(&mut self, models_crate: &str) {
+        self.stages.clients = true;
+        self.stages.objects = false;
+        self.models_crate = Some(models_crate.to_owned());
+    }
+
+    /// Type of an integer with the schema `format`
+    pub fn integer_type(&self, format: Option<&str>) -> &str {
+        match format {
+            Some("int8") => "i8",
+            Some("int16") => "i16",
+            Some("int32") => "i32",
+            Some("int64") => "i64",
+            Some("int128") => "i128",
+            Some("uint8") => "u8",
+            Some("uint16") => "u16",
+            Some("uint32") => "u32",
+            Some("uint64") => "u64",
+            Some("uint128") => "u128",
+            _ => self.integer_type.as_str(),
+        }
+    }
+
+    /// Type of a number with the schema `format` in `package`
+    pub fn number_type(&self, package: Option<&str>, format: Option<&str>) -> &'static str {
+        let decimal_type = match self.decimal {
+            Some(decimal_type) => decimal_type,
+            None => return "f64",
+        };
+        let in_decimal_package = package.map_or(false, |package| {
+            self.decimal_packages
+                .iter()
+                .any(|decimal_package| package.starts_with(decimal_package.as_str()))
+        });
+        match (format, in_decimal_package) {
+            (Some("decimal"), _) | (_, true) => decimal_type.type_name(),
+            _ => "f64",
+        }
+    }
+
+    /// Configuration used for the given spec, with its namespace from `spec_namespaces`
+    pub fn for_spec(&self, spec_file_path: &Path) -> Config {
+        let file_name = spec_file_path
+            .file_name()
+            .map(|file_name| file_name.to_string_lossy().into_owned());
+        let namespace = self
+            .spec_namespaces
+            .get(spec_file_path.to_string_lossy().as_ref())
+            .or_else(|| file_name.and_then(|file_name| self.spec_namespaces.get(&file_name)))
+            .map(|namespace| namespace.to_case(convert_case::Case::Snake));
+        let mut config = self.clone();
+        config.name_mapping.namespace = namespace;
+        config
+    }
+
     pub fn validate(&mut self) {
         self.project_metadata = self.project_metadata.validate();
+        self.name_mapping.resolve_pointer_keys();
     }
 }
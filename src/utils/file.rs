@@ -1,13 +1,169 @@
 use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
     fs::{self, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
+use tracing::info;
+
 use crate::GeneratorError;
 
-pub fn write_filename(name: &PathBuf, content: &str) -> Result<(), GeneratorError> {
+use super::artifact::FileArtifact;
+
+// Markers of hand-written regions which survive regeneration
+const KEEP_START_MARKER: &str = "opage:keep-start";
+const KEEP_END_MARKER: &str = "opage:keep-end";
+
+// Files and counters of the generation running on a thread, see `start_run`
+#[derive(Default)]
+struct WriteState {
+    files_written: usize,
+    files_unchanged: usize,
+    write_duration: Duration,
+    generated_files: BTreeSet<PathBuf>,
+}
+
+thread_local! {
+    // A generation runs on a single thread, concurrent generations (tests, library users)
+    // each keep their own files
+    static WRITE_STATE: RefCell<WriteState> = RefCell::new(WriteState::default());
+    // Comment added to the start of the files, see `set_file_header`
+    static FILE_HEADER: RefCell<Option<String>> = const { RefCell::new(None) };
+    // Files kept in memory instead of being written, see `capture_files`
+    static CAPTURED_FILES: RefCell<Option<BTreeMap<PathBuf, FileArtifact>>> = const { RefCell::new(None) };
+}
+
+/// File in the output directory listing all generated files
+pub const MANIFEST_FILE_NAME: &str = ".opage-manifest";
+
+/// Forgets the generated files and counters of the previous generation of the current thread
+pub fn start_run() {
+    WRITE_STATE.with(|state| *state.borrow_mut() = WriteState::default());
+}
+
+/// Number of files (written, skipped because unchanged) since `start_run`
+pub fn write_statistics() -> (usize, usize) {
+    WRITE_STATE.with(|state| {
+        let state = state.borrow();
+        (state.files_written, state.files_unchanged)
+    })
+}
+
+/// Time spent in `write_filename` since `start_run`
+pub fn write_duration() -> Duration {
+    WRITE_STATE.with(|state| state.borrow().write_duration)
+}
+
+/// Adds `header` as a comment to the start of the files written by the current thread,
+/// `None` stops adding it
+pub fn set_file_header(header: Option<String>) {
+    FILE_HEADER.with(|file_header| *file_header.borrow_mut() = header);
+}
+
+// header commented in the syntax of the file, files without comments get none
+fn commented_header(name: &Path, header: &str) -> Option<String> {
+    let file_name = name.file_name()?.to_str()?;
+    match name.extension().and_then(|extension| extension.to_str()) {
+        Some("rs") => Some(format!("// {}\n\n", header)),
+        Some("toml") => Some(format!("# {}\n\n", header)),
+        Some("md") => Some(format!("<!-- {} -->\n\n", header)),
+        // .gitignore, .gitattributes and .editorconfig
+        None if file_name.starts_with('.') => Some(format!("# {}\n", header)),
+        _ => None,
+    }
+}
+
+/// Hex FNV-1a hash of a spec, identifies the spec a file was generated from
+pub fn fingerprint(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Keeps the files written by the current thread in memory, nothing is written
+/// to disk until `take_captured_files` is called
+pub fn capture_files() {
+    CAPTURED_FILES.with(|files| *files.borrow_mut() = Some(BTreeMap::new()));
+}
+
+/// Stops capturing and returns the files written since `capture_files` by path
+pub fn take_captured_files() -> BTreeMap<PathBuf, String> {
+    take_artifacts()
+        .into_iter()
+        .map(|artifact| (artifact.path, artifact.content))
+        .collect()
+}
+
+/// Stops capturing and returns the files written since `capture_files`, ordered by path
+pub fn take_artifacts() -> Vec<FileArtifact> {
+    CAPTURED_FILES
+        .with(|files| files.borrow_mut().take())
+        .unwrap_or_default()
+        .into_values()
+        .collect()
+}
+
+// Stores the file when the current thread captures its files
+fn capture_file(name: &Path, content: &str) -> bool {
+    CAPTURED_FILES.with(|files| match files.borrow_mut().as_mut() {
+        Some(files) => {
+            files.insert(
+                name.to_path_buf(),
+                FileArtifact::new(name.to_path_buf(), content.to_owned()),
+            );
+            true
+        }
+        None => false,
+    })
+}
+
+/// Writes `content` to `name` unless the file already has this content,
+/// so unchanged files keep their timestamps and don't trigger rebuilds.
+/// Returns whether the file was written.
+pub fn write_filename(name: &PathBuf, content: &str) -> Result<bool, GeneratorError> {
+    let started = Instant::now();
+    let result = write_file_if_changed(name, content);
+    WRITE_STATE.with(|state| state.borrow_mut().write_duration += started.elapsed());
+    result
+}
+
+fn write_file_if_changed(name: &PathBuf, content: &str) -> Result<bool, GeneratorError> {
+    WRITE_STATE.with(|state| state.borrow_mut().generated_files.insert(name.clone()));
+    let header = FILE_HEADER.with(|header| {
+        header
+            .borrow()
+            .as_ref()
+            .and_then(|header| commented_header(name, header))
+    });
+    let content = match header {
+        Some(header) => format!("{}{}", header, content),
+        None => content.to_owned(),
+    };
+    let content = content.as_str();
+    if capture_file(name, content) {
+        return Ok(true);
+    }
+    write_to_disk(name, content)
+}
+
+/// Writes the file unless it has this content already, keep regions of the existing file are merged
+pub(crate) fn write_to_disk(name: &Path, content: &str) -> Result<bool, GeneratorError> {
     fs::create_dir_all(&name.parent().unwrap()).expect("Creating objects dir failed");
+    let content = match fs::read_to_string(name) {
+        Ok(existing_content) => {
+            let content = merge_keep_regions(&existing_content, content);
+            if content == existing_content {
+                WRITE_STATE.with(|state| state.borrow_mut().files_unchanged += 1);
+                return Ok(false);
+            }
+            content
+        }
+        Err(_) => content.to_owned(),
+    };
     let mut object_file = match File::create(name) {
         Ok(file) => file,
         Err(err) => {
@@ -18,5 +174,241 @@ pub fn write_filename(name: &PathBuf, content: &str) -> Result<(), GeneratorErro
         }
     };
     object_file.write(content.as_bytes()).unwrap();
-    Ok(())
+    WRITE_STATE.with(|state| state.borrow_mut().files_written += 1);
+    Ok(true)
+}
+
+/// Writes the manifest of all files generated into `output_dir`.
+///
+/// With `prune`, files listed in the previous manifest which were not generated
+/// again are deleted. Files not listed in the manifest are never touched.
+/// Returns the deleted files.
+pub fn update_manifest(output_dir: &Path, prune: bool) -> Result<Vec<PathBuf>, GeneratorError> {
+    let manifest_path = output_dir.join(MANIFEST_FILE_NAME);
+    let generated_files = WRITE_STATE.with(|state| {
+        state
+            .borrow()
+            .generated_files
+            .iter()
+            .filter_map(|path| path.strip_prefix(output_dir).ok())
+            .map(|path| path.to_path_buf())
+            .collect::<BTreeSet<PathBuf>>()
+    });
+
+    // captured runs (`Generator::artifacts`) never touch the output directory
+    let capturing = CAPTURED_FILES.with(|files| files.borrow().is_some());
+    let mut pruned_files = vec![];
+    if prune && !capturing {
+        let previous_manifest = fs::read_to_string(&manifest_path).unwrap_or_default();
+        for previous_file in previous_manifest.lines().map(PathBuf::from) {
+            if previous_file.as_os_str().is_empty()
+                || previous_file.is_absolute()
+                || generated_files.contains(&previous_file)
+            {
+                continue;
+            }
+            let full_path = output_dir.join(&previous_file);
+            if !full_path.is_file() {
+                continue;
+            }
+            fs::remove_file(&full_path).map_err(|err| {
+                GeneratorError::FileCreationError(
+                    full_path.to_string_lossy().to_string(),
+                    err.to_string(),
+                )
+            })?;
+            info!("Pruned {}", full_path.display());
+            // remove directories which became empty, fails silently otherwise
+            let mut parent = full_path.parent();
+            while let Some(directory) = parent {
+                if directory == output_dir || fs::remove_dir(directory).is_err() {
+                    break;
+                }
+                parent = directory.parent();
+            }
+            pruned_files.push(full_path);
+        }
+    }
+
+    let manifest = generated_files
+        .iter()
+        .map(|path| path.to_string_lossy().replace('\\', "/"))
+        .collect::<Vec<String>>()
+        .join("\n");
+    if capture_file(&manifest_path, &manifest) {
+        return Ok(pruned_files);
+    }
+    fs::create_dir_all(output_dir).expect("Creating output dir failed");
+    fs::write(&manifest_path, manifest).map_err(|err| {
+        GeneratorError::FileCreationError(
+            manifest_path.to_string_lossy().to_string(),
+            err.to_string(),
+        )
+    })?;
+    Ok(pruned_files)
+}
+
+fn keep_region_name(line: &str, marker: &str) -> Option<String> {
+    line.find(marker)
+        .map(|position| line[position + marker.len()..].trim().to_owned())
+}
+
+/// Returns all keep regions (name, lines including markers) of a file.
+/// Regions without a name are named by their position.
+fn extract_keep_regions(content: &str) -> Vec<(String, String)> {
+    let mut regions = vec![];
+    let mut current: Option<(String, String)> = None;
+    for line in content.lines() {
+        if let Some((_, region)) = current.as_mut() {
+            region.push_str(line);
+            region.push('\n');
+            if line.contains(KEEP_END_MARKER) {
+                regions.extend(current.take());
+            }
+            continue;
+        }
+        if let Some(name) = keep_region_name(line, KEEP_START_MARKER) {
+            let name = match name.is_empty() {
+                true => format!("#{}", regions.len()),
+                false => name,
+            };
+            current = Some((name, format!("{}\n", line)));
+        }
+    }
+    // unterminated region is kept until the end of the file
+    if let Some(region) = current {
+        regions.push(region);
+    }
+    regions
+}
+
+/// Copies the keep regions of `existing` into `generated`.
+///
+/// Regions also present (by name) in the generated content replace them in place,
+/// all others are appended at the end of the generated content.
+pub fn merge_keep_regions(existing: &str, generated: &str) -> String {
+    let mut regions = extract_keep_regions(existing);
+    if regions.is_empty() {
+        return generated.to_owned();
+    }
+
+    let mut result = String::new();
+    let mut skip_until_end = false;
+    let mut unnamed_position = 0;
+    for line in generated.lines() {
+        if skip_until_end {
+            if line.contains(KEEP_END_MARKER) {
+                skip_until_end = false;
+            }
+            continue;
+        }
+        if let Some(name) = keep_region_name(line, KEEP_START_MARKER) {
+            let name = match name.is_empty() {
+                true => {
+                    unnamed_position += 1;
+                    format!("#{}", unnamed_position - 1)
+                }
+                false => name,
+            };
+            if let Some(position) = regions.iter().position(|(region, _)| region == &name) {
+                let (_, region) = regions.remove(position);
+                result.push_str(&region);
+                skip_until_end = true;
+                continue;
+            }
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    for (_, region) in regions {
+        result.push('\n');
+        result.push_str(&region);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_files() {
+        let path = PathBuf::from("/nonexistent/opage/src/lib.rs");
+        capture_files();
+        assert!(write_filename(&path, "pub mod models;\n").unwrap());
+        let files = take_captured_files();
+        assert_eq!(files.get(&path).unwrap(), "pub mod models;\n");
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_file_header() {
+        let path = PathBuf::from("/nonexistent/opage/src/lib.rs");
+        capture_files();
+        set_file_header(Some("Generated by opage".to_owned()));
+        write_filename(&path, "pub mod models;\n").unwrap();
+        set_file_header(None);
+        let files = take_captured_files();
+        assert_eq!(
+            files.get(&path).unwrap(),
+            "// Generated by opage\n\npub mod models;\n"
+        );
+    }
+
+    #[test]
+    fn test_start_run_forgets_previous_files() {
+        let output_dir = PathBuf::from("/nonexistent/opage");
+        capture_files();
+        write_filename(&output_dir.join("src/old.rs"), "").unwrap();
+        start_run();
+        write_filename(&output_dir.join("src/lib.rs"), "").unwrap();
+        update_manifest(&output_dir, false).unwrap();
+        let files = take_captured_files();
+        assert_eq!(
+            files.get(&output_dir.join(MANIFEST_FILE_NAME)).unwrap(),
+            "src/lib.rs"
+        );
+        assert_eq!(write_statistics(), (0, 0));
+    }
+
+    #[test]
+    fn test_captured_run_does_not_prune() {
+        let output_dir = std::env::temp_dir().join("opage-captured-prune");
+        fs::create_dir_all(&output_dir).unwrap();
+        fs::write(output_dir.join(MANIFEST_FILE_NAME), "stale.rs").unwrap();
+        fs::write(output_dir.join("stale.rs"), "").unwrap();
+        capture_files();
+        start_run();
+        let pruned_files = update_manifest(&output_dir, true).unwrap();
+        take_captured_files();
+        assert!(pruned_files.is_empty());
+        assert!(output_dir.join("stale.rs").exists());
+        fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_keep_regions_appends() {
+        let existing = "pub struct A {}\n// opage:keep-start\nimpl A {}\n// opage:keep-end\n";
+        let generated = "pub struct A { pub b: i32 }\n";
+        assert_eq!(
+            merge_keep_regions(existing, generated),
+            "pub struct A { pub b: i32 }\n\n// opage:keep-start\nimpl A {}\n// opage:keep-end\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_keep_regions_in_place() {
+        let existing = "// opage:keep-start imports\nuse std::fmt;\n// opage:keep-end\nold\n";
+        let generated = "// opage:keep-start imports\n// opage:keep-end\nnew\n";
+        assert_eq!(
+            merge_keep_regions(existing, generated),
+            "// opage:keep-start imports\nuse std::fmt;\n// opage:keep-end\nnew\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_without_regions() {
+        assert_eq!(merge_keep_regions("old", "new"), "new");
+    }
 }
@@ -0,0 +1,69 @@
+//! Golden compile tests: a spec is generated into a temporary directory and
+//! the generated crate is checked with `cargo check`, catching templates which
+//! produce code that does not compile.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{
+    generator::generator::{Generator, RunOptions},
+    utils::config::Config,
+    GeneratorError,
+};
+
+/// Generates `spec` as the crate `name` below the system temp dir and runs `cargo check --all-features` on it.
+///
+/// The crates share one target directory (`CARGO_TARGET_DIR` or `opage-golden-target`
+/// in the temp dir) so the dependencies are only built once. Returns the crate directory.
+pub fn check_generated_crate(
+    name: &str,
+    spec: &Path,
+    mut config: Config,
+) -> Result<PathBuf, GeneratorError> {
+    let output_dir = std::env::temp_dir().join(format!("opage-golden-{}", name));
+    // files of a previous run would be kept and merged
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir).map_err(|err| {
+            GeneratorError::FileCreationError(
+                output_dir.to_string_lossy().to_string(),
+                err.to_string(),
+            )
+        })?;
+    }
+
+    config.project_metadata.name = name.to_owned();
+    config.validate();
+    let generator = Generator::new(config, output_dir.clone(), vec![spec.to_path_buf()]);
+    generator.run(&RunOptions::default())?;
+    let statistics = generator.statistics();
+    if statistics.has_failures() {
+        return Err(GeneratorError::CodeGenerationError(
+            name.to_owned(),
+            statistics.to_string(),
+        ));
+    }
+
+    let target_dir = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("opage-golden-target"));
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = Command::new(cargo)
+        .arg("check")
+        .arg("--quiet")
+        // code behind the optional features (websocket, metrics, ...) is checked as well
+        .arg("--all-features")
+        .arg("--manifest-path")
+        .arg(output_dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", target_dir)
+        .output()
+        .map_err(|err| GeneratorError::CodeGenerationError(name.to_owned(), err.to_string()))?;
+    if !output.status.success() {
+        return Err(GeneratorError::CodeGenerationError(
+            name.to_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    Ok(output_dir)
+}
@@ -1,4 +1,7 @@
+pub mod artifact;
 pub mod config;
 pub mod file;
+pub mod golden;
 pub mod name_mapping;
+pub mod snapshot;
 pub mod spec_ignore;
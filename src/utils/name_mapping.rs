@@ -3,7 +3,38 @@ use reqwest::StatusCode;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-use crate::{generator::templates::rust::RUST_PRIMITIVE_TYPES, GeneratorError};
+use crate::{
+    generator::component::{object_definition::get_components_base_path, validate_component_name},
+    generator::templates::rust::RUST_PRIMITIVE_TYPES,
+    GeneratorError,
+};
+use tracing::warn;
+
+/// Schema element addressed by a JSON Pointer mapping key
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaPointer {
+    /// `#/components/schemas/Foo`
+    Schema(String),
+    /// `#/components/schemas/Foo/properties/bar`
+    Property(String, String),
+}
+
+impl SchemaPointer {
+    pub fn parse(pointer: &str) -> Option<Self> {
+        let tokens = pointer
+            .strip_prefix("#/components/schemas/")?
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect::<Vec<String>>();
+        match tokens.as_slice() {
+            [schema] => Some(SchemaPointer::Schema(schema.clone())),
+            [schema, properties, property] if properties == "properties" => {
+                Some(SchemaPointer::Property(schema.clone(), property.clone()))
+            }
+            _ => None,
+        }
+    }
+}
 
 #[derive(Deserialize, Clone, Debug, PartialEq)]
 pub struct NameMapping {
@@ -22,6 +53,9 @@ pub struct NameMapping {
     // Use scope for module names: propagated from config
     #[serde(default)]
     pub use_scope: bool,
+    // Namespace of the spec being generated: set from `Config::spec_namespaces`
+    #[serde(skip)]
+    pub namespace: Option<String>,
 }
 
 fn path_to_string(path: &Vec<String>, token_name: &str) -> String {
@@ -43,6 +77,7 @@ impl NameMapping {
             status_code_mapping: HashMap::new(),
             i32_to_u32: false,
             use_scope: false,
+            namespace: None,
         }
     }
 
@@ -50,6 +85,82 @@ impl NameMapping {
         self.use_scope = use_scope;
     }
 
+    /// Rewrites JSON Pointer keys of `struct_mapping` and `property_mapping` into
+    /// the path keys used during generation, other keys are kept as they are
+    pub fn resolve_pointer_keys(&mut self) {
+        let struct_mapping = std::mem::take(&mut self.struct_mapping);
+        for (key, name) in struct_mapping {
+            let key = match SchemaPointer::parse(&key) {
+                Some(SchemaPointer::Schema(schema)) => self.struct_key(&schema),
+                Some(SchemaPointer::Property(..)) | None => {
+                    if key.starts_with("#/") {
+                        warn!("struct_mapping key {} is not a schema pointer", key);
+                    }
+                    key
+                }
+            };
+            self.struct_mapping.insert(key, name);
+        }
+        // property keys contain the (possibly mapped) struct name
+        let property_mapping = std::mem::take(&mut self.property_mapping);
+        for (key, name) in property_mapping {
+            let key = match SchemaPointer::parse(&key) {
+                Some(SchemaPointer::Property(schema, property)) => {
+                    self.property_key(&schema, &property)
+                }
+                Some(SchemaPointer::Schema(..)) | None => {
+                    if key.starts_with("#/") {
+                        warn!("property_mapping key {} is not a property pointer", key);
+                    }
+                    key
+                }
+            };
+            self.property_mapping.insert(key, name);
+        }
+    }
+
+    fn struct_key(&self, schema: &str) -> String {
+        let name = validate_component_name(schema, self.use_scope);
+        let name = fix_struct_names(&name, self.use_scope);
+        path_to_string(&get_components_base_path(), &convert_name(&name))
+    }
+
+    fn property_key(&self, schema: &str, property: &str) -> String {
+        let mut path = get_components_base_path();
+        path.push(self.extract_struct_name(&self.schema_struct_name(schema)));
+        path_to_string(&path, &property.to_case(convert_case::Case::Snake))
+    }
+
+    /// Full name of the struct generated for a component schema, `title` takes precedence over the key
+    pub fn schema_struct_name(&self, schema: &str) -> String {
+        let name = validate_component_name(schema, self.use_scope);
+        self.name_to_struct_name(&get_components_base_path(), &name)
+    }
+
+    /// Name given to the schema element of a pointer, e.g. `models::Foo` or `models::Foo.bar`
+    pub fn explain(&self, pointer: &SchemaPointer, title: Option<&str>) -> String {
+        let struct_name = match title {
+            Some(title) => self.name_to_struct_name(&get_components_base_path(), title),
+            None => match pointer {
+                SchemaPointer::Schema(schema) | SchemaPointer::Property(schema, _) => {
+                    self.schema_struct_name(schema)
+                }
+            },
+        };
+        match pointer {
+            SchemaPointer::Schema(_) => struct_name,
+            SchemaPointer::Property(_, property) => {
+                let mut path = get_components_base_path();
+                path.push(self.extract_struct_name(&struct_name));
+                format!(
+                    "{}.{}",
+                    struct_name,
+                    self.name_to_property_name(&path, property)
+                )
+            }
+        }
+    }
+
     pub fn name_to_struct_name(&self, path: &Vec<String>, name: &str) -> String {
         for primitive_type in RUST_PRIMITIVE_TYPES.iter() {
             if name.eq_ignore_ascii_case(primitive_type) {
@@ -61,9 +172,33 @@ impl NameMapping {
         let path_str = path_to_string(path, &converted_name);
 
         // trace!("name_to_struct_name {}", path_str);
-        match self.struct_mapping.get(&path_str) {
+        let full_name = match self.struct_mapping.get(&path_str) {
             Some(name) => name.clone(),
             None => name.replace(".", "::").replace("::_common::", "::"),
+        };
+        self.namespaced_struct_name(full_name)
+    }
+
+    // `models::Pet` becomes `models::v1::Pet` in the namespace `v1`
+    fn namespaced_struct_name(&self, full_name: String) -> String {
+        let namespace = match self.namespace {
+            Some(ref namespace) => namespace,
+            None => return full_name,
+        };
+        match full_name.split_once("::") {
+            Some((package, name)) if !name.starts_with(&format!("{}::", namespace)) => {
+                format!("{}::{}::{}", package, namespace, name)
+            }
+            _ => full_name,
+        }
+    }
+
+    /// Module name of an operation, suffixed with the spec namespace (`search_v1`)
+    pub fn name_to_operation_name(&self, operation_id: &str) -> String {
+        let name = self.name_to_module_name(operation_id);
+        match self.namespace {
+            Some(ref namespace) => format!("{}_{}", name, namespace),
+            None => name,
         }
     }
 
@@ -297,6 +432,22 @@ mod tests {
         assert_eq!(path, "common.aggregations.field_date_math");
     }
 
+    #[test]
+    fn test_pointer_keys() {
+        let mut name_mapping = NameMapping::new();
+        name_mapping
+            .struct_mapping
+            .insert("#/components/schemas/Foo".to_owned(), "Bar".to_owned());
+        name_mapping.property_mapping.insert(
+            "#/components/schemas/Foo/properties/type".to_owned(),
+            "kind".to_owned(),
+        );
+        name_mapping.resolve_pointer_keys();
+
+        let pointer = SchemaPointer::parse("#/components/schemas/Foo/properties/type").unwrap();
+        assert_eq!(name_mapping.explain(&pointer, None), "Bar.kind");
+    }
+
     #[test]
     fn test_fix_struct_names() {
         let name = "_common___Metadata";
@@ -0,0 +1,178 @@
+//! Snapshot tests of the generated crate.
+//!
+//! A spec is generated in memory and compared to the files committed in a
+//! snapshot directory. Setting `OPAGE_BLESS=1` (or running `opage --bless`)
+//! writes the generated files as the new snapshots instead.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    generator::generator::{Generator, RunOptions},
+    utils::{artifact::ArtifactKind, config::Config},
+    GeneratorError,
+};
+
+/// Environment variable which makes `assert_snapshots` update the snapshots
+pub const BLESS_ENV: &str = "OPAGE_BLESS";
+
+// Output directory of in-memory generations, never created
+const VIRTUAL_OUTPUT_DIR: &str = "/opage-snapshot";
+
+/// Generates the specs in memory, returns the files by path relative to the crate root
+pub fn generate_in_memory(
+    config: Config,
+    specs: Vec<PathBuf>,
+) -> Result<BTreeMap<PathBuf, String>, GeneratorError> {
+    let output_dir = PathBuf::from(VIRTUAL_OUTPUT_DIR);
+    let artifacts =
+        Generator::new(config, output_dir.clone(), specs).artifacts(&RunOptions::default())?;
+    Ok(artifacts
+        .filter(|artifact| artifact.kind != ArtifactKind::Manifest)
+        .filter_map(|artifact| {
+            let path = artifact.path.strip_prefix(&output_dir).ok()?.to_path_buf();
+            Some((path, artifact.content))
+        })
+        .collect())
+}
+
+/// Differences between generated files and their snapshots
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotReport {
+    /// Files whose content changed, with the first differing line (1-based)
+    pub changed: Vec<(PathBuf, usize)>,
+    /// Generated files without a snapshot
+    pub added: Vec<PathBuf>,
+    /// Snapshots of files which are no longer generated
+    pub removed: Vec<PathBuf>,
+}
+
+impl SnapshotReport {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl Display for SnapshotReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, line) in self.changed.iter() {
+            writeln!(f, "changed  {} (line {})", path.display(), line)?;
+        }
+        for path in self.added.iter() {
+            writeln!(f, "added    {}", path.display())?;
+        }
+        for path in self.removed.iter() {
+            writeln!(f, "removed  {}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the generated files with the snapshots in `snapshot_dir`
+pub fn compare_snapshots(
+    files: &BTreeMap<PathBuf, String>,
+    snapshot_dir: &Path,
+) -> Result<SnapshotReport, GeneratorError> {
+    let mut snapshots = read_snapshots(snapshot_dir)?;
+    let mut report = SnapshotReport::default();
+    for (path, content) in files.iter() {
+        match snapshots.remove(path) {
+            Some(snapshot) if snapshot == *content => {}
+            Some(snapshot) => report
+                .changed
+                .push((path.clone(), first_different_line(&snapshot, content))),
+            None => report.added.push(path.clone()),
+        }
+    }
+    report.removed = snapshots.into_keys().collect();
+    Ok(report)
+}
+
+/// Replaces the snapshots in `snapshot_dir` with the generated files, returns what changed
+pub fn bless_snapshots(
+    files: &BTreeMap<PathBuf, String>,
+    snapshot_dir: &Path,
+) -> Result<SnapshotReport, GeneratorError> {
+    let report = compare_snapshots(files, snapshot_dir)?;
+    for path in report.removed.iter() {
+        let full_path = snapshot_dir.join(path);
+        fs::remove_file(&full_path).map_err(|err| file_error(&full_path, err))?;
+    }
+    let written = report
+        .changed
+        .iter()
+        .map(|(path, _)| path)
+        .chain(report.added.iter());
+    for path in written {
+        let full_path = snapshot_dir.join(path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).map_err(|err| file_error(parent, err))?;
+        }
+        fs::write(&full_path, &files[path]).map_err(|err| file_error(&full_path, err))?;
+    }
+    Ok(report)
+}
+
+/// Panics if the generated files differ from the snapshots in `snapshot_dir`,
+/// updates the snapshots instead when `OPAGE_BLESS` is set
+pub fn assert_snapshots(files: &BTreeMap<PathBuf, String>, snapshot_dir: &Path) {
+    let bless = std::env::var(BLESS_ENV).is_ok_and(|bless| !bless.is_empty() && bless != "0");
+    let report = match bless {
+        true => bless_snapshots(files, snapshot_dir),
+        false => compare_snapshots(files, snapshot_dir),
+    }
+    .unwrap_or_else(|err| panic!("Snapshots {}: {}", snapshot_dir.display(), err));
+    if !bless && !report.is_empty() {
+        panic!(
+            "Generated files differ from the snapshots in {} (set {}=1 to update them):\n{}",
+            snapshot_dir.display(),
+            BLESS_ENV,
+            report
+        );
+    }
+}
+
+fn read_snapshots(snapshot_dir: &Path) -> Result<BTreeMap<PathBuf, String>, GeneratorError> {
+    let mut snapshots = BTreeMap::new();
+    if snapshot_dir.is_dir() {
+        read_snapshot_dir(snapshot_dir, Path::new(""), &mut snapshots)?;
+    }
+    Ok(snapshots)
+}
+
+fn read_snapshot_dir(
+    snapshot_dir: &Path,
+    relative_dir: &Path,
+    snapshots: &mut BTreeMap<PathBuf, String>,
+) -> Result<(), GeneratorError> {
+    let dir = snapshot_dir.join(relative_dir);
+    for entry in fs::read_dir(&dir).map_err(|err| file_error(&dir, err))? {
+        let entry = entry.map_err(|err| file_error(&dir, err))?;
+        let relative_path = relative_dir.join(entry.file_name());
+        if entry.path().is_dir() {
+            read_snapshot_dir(snapshot_dir, &relative_path, snapshots)?;
+            continue;
+        }
+        let content =
+            fs::read_to_string(entry.path()).map_err(|err| file_error(&entry.path(), err))?;
+        snapshots.insert(relative_path, content);
+    }
+    Ok(())
+}
+
+fn first_different_line(snapshot: &str, content: &str) -> usize {
+    snapshot
+        .lines()
+        .zip(content.lines())
+        .position(|(snapshot_line, line)| snapshot_line != line)
+        .unwrap_or_else(|| snapshot.lines().count().min(content.lines().count()))
+        + 1
+}
+
+fn file_error(path: &Path, err: std::io::Error) -> GeneratorError {
+    GeneratorError::FileCreationError(path.to_string_lossy().to_string(), err.to_string())
+}
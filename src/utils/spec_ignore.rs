@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 pub struct SpecIgnore {
     paths: Vec<String>,
     components: Vec<String>,
+    // HTTP methods of the operations not generated, e.g. all mutating ones for a read-only client
+    #[serde(default)]
+    methods: Vec<String>,
 }
 
 impl SpecIgnore {
@@ -11,6 +14,7 @@ impl SpecIgnore {
         SpecIgnore {
             paths: vec![],
             components: vec![],
+            methods: vec![],
         }
     }
 
@@ -18,6 +22,12 @@ impl SpecIgnore {
         self.paths.contains(&path.to_owned())
     }
 
+    pub fn method_ignored(&self, method: &str) -> bool {
+        self.methods
+            .iter()
+            .any(|ignored| ignored.eq_ignore_ascii_case(method))
+    }
+
     pub fn component_ignored(&self, component: &str) -> bool {
         self.components.contains(&component.to_owned())
     }
@@ -10,11 +10,15 @@ do
     echo "### Start test ${dir}"
     #rm -rf $dir/output
     mkdir -p $dir/output
-    cargo run -- -s $dir/spec.openapi.yaml -o $dir/output -c $dir/config.json > $dir/output/generate.log
+    cargo run -- -s $dir/spec.openapi.yaml -o $dir/output -c $dir/config.json > $dir/output/generate.log 2>&1
     cargo build --manifest-path=$dir/output/Cargo.toml
     echo "### End test ${dir}"
 done
 
+echo "### Start golden compile tests"
+cargo test --test golden -- --ignored
+echo "### End golden compile tests"
+
 # cargo run -- -s tests/resources/inline_object_same_name.openapi.yaml -o tests/output/inline_object_same_name -c tests/resources/inline_object_same_name.json
 # cd tests/output/inline_object_same_name
 # cargo build
\ No newline at end of file
@@ -1,8 +1,8 @@
 use std::path::PathBuf;
 
 use opage::{
-    generator::component::generate_components, generator::types::ObjectDatabase,
-    utils::config::Config,
+    generator::component::generate_components, generator::context::GenerationContext,
+    generator::types::ObjectDatabase, utils::config::Config,
 };
 
 #[test]
@@ -13,7 +13,7 @@ fn empty_component() {
     let spec = oas3::from_path(spec_file_path).expect("Failed to read spec");
     let config = Config::new();
     let object_database = ObjectDatabase::new();
-    generate_components(&spec, &config, &object_database).unwrap();
+    generate_components(&GenerationContext::new(&spec, &object_database, &config)).unwrap();
     let names: Vec<String> = object_database.iter().map(|f| f.key().clone()).collect();
     assert_eq!(vec!["Empty"], names);
 }
@@ -0,0 +1,33 @@
+//! Golden compile tests, run with `cargo test --test golden -- --ignored`
+
+use std::path::PathBuf;
+
+use opage::utils::{config::Config, golden::check_generated_crate};
+
+fn check_spec(name: &str, spec_file_name: &str) {
+    let mut spec_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    spec_file_path.push("tests/golden/specs");
+    spec_file_path.push(spec_file_name);
+
+    if let Err(err) = check_generated_crate(name, &spec_file_path, Config::new()) {
+        panic!("{} does not compile: {}", spec_file_name, err);
+    }
+}
+
+#[test]
+#[ignore]
+fn petstore_compiles() {
+    check_spec("golden-petstore", "petstore.openapi.yaml");
+}
+
+#[test]
+#[ignore]
+fn one_of_compiles() {
+    check_spec("golden-one-of", "one_of.openapi.yaml");
+}
+
+#[test]
+#[ignore]
+fn websocket_compiles() {
+    check_spec("golden-websocket", "websocket.openapi.yaml");
+}
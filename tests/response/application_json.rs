@@ -1,9 +1,10 @@
 use opage::{
     generator::{
+        context::GenerationContext,
         path::default_request::generate_operation,
         types::{Method, ObjectDatabase, PathDatabase},
     },
-    utils::{config, name_mapping::NameMapping},
+    utils::config,
 };
 use std::path::PathBuf;
 
@@ -17,18 +18,15 @@ fn empty_json() {
 
     let object_database = ObjectDatabase::new();
     let path_database = PathDatabase::new();
-    let name_mapping = NameMapping::new();
     let config = config::Config::default();
+    let context = GenerationContext::new(&spec, &object_database, &config);
 
     generate_operation(
-        &spec,
-        &name_mapping,
+        &context,
         Method::POST,
         "/test",
         &path_spec.post.as_ref().unwrap(),
-        &object_database,
         &path_database,
-        &config,
     )
     .expect("Failed to generated path");
 }